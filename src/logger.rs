@@ -38,6 +38,7 @@ impl Logger {
     }
 
     /// Creates a new logger with both console and file output
+    #[allow(dead_code)] // logger API the runner doesn't use yet
     pub fn with_file(min_level: LogLevel, file_path: &str) -> std::io::Result<Self> {
         let file = OpenOptions::new()
             .create(true)
@@ -52,11 +53,13 @@ impl Logger {
     }
 
     /// Sets whether console output is enabled
+    #[allow(dead_code)]
     pub fn set_console_output(&mut self, enabled: bool) {
         self.console_output = enabled;
     }
 
     /// Sets the minimum log level
+    #[allow(dead_code)]
     pub fn set_min_level(&mut self, level: LogLevel) {
         self.min_level = level;
     }
@@ -2,7 +2,7 @@ mod logger;
 mod model;
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet},
     env, fs,
     path::Path,
     time::{Duration, Instant},
@@ -14,10 +14,32 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use logger::{LogLevel, Logger};
+use model::batch::{BatchCandidate, BatchCompatibility, BatchDecision, BatchPolicy};
+use model::bottleneck::{BottleneckDetector, BottleneckShare};
+use model::compare::{Comparison, Estimate};
+use model::energy::{EnergyMeter, IdleShutdown, PowerProfile, PowerState};
+use model::cost::{CostBreakdown, CostModel, LaborRate, MachineRates, MachineUsage, StaffUsage};
+use model::layout::Layout as PlantLayout;
+use model::lot::{LotPolicy, LotTracker};
 use model::machine::MachineType;
-use model::staff::{Role, Staff};
-use model::staff_scheduling::ProductionSimulator;
+use model::machine_status::{MachineStatus, StatusTimes, WaitReason};
+use model::maintenance::{MaintenancePlan, MaintenanceState, MaintenanceTrigger};
+use model::material::{Material, MaterialNeed, Replenishment, Stockroom};
+use model::oee::{Oee, OeeCounters};
+use model::optimize::{Dimension, Search};
+use model::process::Process;
+use model::production_line::{Plant, ProductionLine};
+use model::quality::{Disposition, FailureAction, StepQuality, YieldPolicy};
+use model::random::{Distribution, SimRng};
+use model::report::{self as html, GanttSpan};
+use model::resource::{ResourceNeed, ResourcePool, ResourcePools};
+use model::sampler::{BucketSample, Sample, SampleMode, Sampler};
+use model::sweep::{design_points, range_levels, Design, SweepResults, SweepRow};
+use model::warmup::{suggest_warmup, throughput_series, welch_average};
+use model::staff::{FatigueModel, LearningCurve, Proficiency, Role, Staff};
+use model::staff_scheduling::{CrewPace, MachineState, Phase, PhaseKind, ProductionSimulator};
 use model::time::{Event, EventType, SimulationTime, Simulator};
+use model::transport::{Conveyor, Route, TransportNetwork, TransporterPool, Trip};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
@@ -30,958 +52,6320 @@ use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
 struct SimulationConfig {
+    #[serde(default)]
     machines: Vec<MachineConfig>,
+    /// Staff shared by every line (with a multi-line plant)
+    #[serde(default)]
     staff: Vec<StaffConfig>,
+    #[serde(default)]
     processes: Vec<ProcessConfig>,
+    /// Several lines with their own machines, staff and steps, instead of `processes`
+    #[serde(default)]
+    lines: Vec<LineConfig>,
+    /// Lot size and transfer batches for every line (default: items move one by one)
+    #[serde(default)]
+    lots: Option<LotConfig>,
     #[serde(default = "default_items")]
     items: u32,
+    /// Seed for random draws (yield checks etc.) so runs are reproducible
+    #[serde(default)]
+    seed: Option<u64>,
+    /// Minutes per window in the shifting-bottleneck report (default: whole run only)
+    #[serde(default)]
+    bottleneck_window: Option<u32>,
+    /// Minutes after which all statistics restart, leaving out the start-up
+    #[serde(default)]
+    warmup: Option<u32>,
+    /// Stop the run (and its statistics) at this minute even if work is left
+    #[serde(default)]
+    observe_until: Option<u32>,
+    /// Record queue lengths, busy machines and staff, and WIP over time
+    #[serde(default)]
+    sampling: Option<SamplingConfig>,
+    /// Moves between buckets; without it items reach the next queue instantly
+    #[serde(default)]
+    transport: Option<TransportConfig>,
+    /// Shared pools such as fixtures, jigs, pallets and test probes
+    #[serde(default)]
+    resources: Vec<ResourceConfig>,
+    /// Raw materials with opening stock and how they are restocked
+    #[serde(default)]
+    materials: Vec<MaterialConfig>,
+    /// Who sets the pace of a multi-person crew: "slowest" (default) or "average"
+    #[serde(default)]
+    crew_pace: Option<CrewPaceConfig>,
+    /// Bucket positions / walking times; without it staff move between machines instantly
+    #[serde(default)]
+    layout: Option<LayoutConfig>,
+    /// Material and scrap prices, regular hours before overtime
+    #[serde(default)]
+    cost: Option<CostConfig>,
 }
 
 #[derive(Debug, Deserialize)]
-struct MachineConfig {
-    id: u32,
+struct LineConfig {
     name: String,
+    /// Items this line builds (default: the plant's `items`)
     #[serde(default)]
-    staff_required: Option<u32>,
+    items: Option<u32>,
+    /// Store or raw material the line takes one unit of per item
     #[serde(default)]
-    is_automated: Option<bool>,
-    /// Number of identical machines in this bucket (e.g., 2 ovens)
+    start_material: Option<String>,
+    /// What the line makes; lines starting from it are fed through a store
     #[serde(default)]
-    count: Option<u32>,
+    end_material: Option<String>,
+    #[serde(default)]
+    machines: Vec<MachineConfig>,
+    /// Staff who only work on this line's machines
+    #[serde(default)]
+    staff: Vec<StaffConfig>,
+    processes: Vec<ProcessConfig>,
+    /// Overrides the plant's `lots` for this line
+    #[serde(default)]
+    lots: Option<LotConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SamplingConfig {
+    /// Minutes between samples (default: after every event)
+    #[serde(default)]
+    every: Option<u32>,
+    /// CSV file the samples are written to when the run ends
+    #[serde(default)]
+    csv: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct LotConfig {
+    /// Items per lot
+    size: u32,
+    /// Items moving between steps together (default: the whole lot)
+    #[serde(default)]
+    transfer_batch: Option<u32>,
+    /// Minutes between lot releases (default: every lot at the start)
+    #[serde(default)]
+    release_every: u32,
 }
 
 #[derive(Debug, Deserialize)]
-struct StaffConfig {
-    id: u32,
-    name: String,
-    role: RoleConfig,
+struct CostConfig {
+    #[serde(default)]
+    material_per_item: f64,
+    /// Disposal cost per scrapped item, on top of its material
+    #[serde(default)]
+    scrap_cost: f64,
+    /// Hours per person paid at the regular rate (default 8)
+    #[serde(default = "default_regular_hours")]
+    regular_hours: f64,
 }
 
 #[derive(Debug, Deserialize)]
-struct RoleConfig {
-    id: u32,
-    name: String,
+struct LayoutConfig {
+    /// Distance units walked per minute
+    #[serde(default = "default_walk_speed")]
+    walk_speed: f64,
     #[serde(default)]
-    machine_ids: Vec<u32>,
+    positions: Vec<PositionConfig>,
+    /// Measured walking times; these win over distances from positions
+    #[serde(default)]
+    travel_times: Vec<WalkConfig>,
 }
 
 #[derive(Debug, Deserialize)]
-struct ProcessConfig {
+struct PositionConfig {
     machine_id: u32,
-    #[serde(default)]
-    process_id: Option<u32>,
-    /// How long the process runs
-    duration: u32,
+    x: f64,
+    y: f64,
 }
 
-fn default_items() -> u32 {
-    1
+#[derive(Debug, Deserialize)]
+struct WalkConfig {
+    from: u32,
+    to: u32,
+    minutes: u32,
 }
 
-fn main() {
-    let logger = Logger::new(LogLevel::Debug);
-    let args: Vec<String> = env::args().collect();
+/// A parameter sweep, run with `sweep sweep.json`
+#[derive(Debug, Deserialize)]
+struct SweepConfig {
+    /// Scenario the factors are applied to, relative to the sweep file
+    base: String,
+    factors: Vec<FactorConfig>,
+    #[serde(default)]
+    design: Option<DesignConfig>,
+    /// Scenarios drawn by a Latin hypercube
+    #[serde(default)]
+    samples: Option<usize>,
+    #[serde(default = "default_replications")]
+    replications: u32,
+    /// Runs at once (default: one per core)
+    #[serde(default)]
+    threads: Option<usize>,
+    /// CSV file for the results table, one row per scenario, relative to the sweep file
+    output: String,
+}
 
-    if let Some(config_path) = parse_config_path(&args) {
-        if let Err(err) = run_tui_with_config(&config_path, &logger) {
-            logger.error(&format!("Failed to run simulation from config: {}", err));
-            std::process::exit(1);
-        }
-    } else {
-        logger.info("No config file provided - running built-in examples");
-        run_examples(&logger);
-        logger.info("\nSimulation complete");
-    }
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum DesignConfig {
+    FullFactorial,
+    LatinHypercube,
 }
 
-fn parse_config_path(args: &[String]) -> Option<String> {
-    let mut iter = args.iter().skip(1);
-    while let Some(arg) = iter.next() {
-        match arg.as_str() {
-            "--config" | "-c" => return iter.next().cloned(),
-            path => return Some(path.to_string()),
-        }
-    }
-    None
+/// One swept factor: a config field (`path`) or a role's headcount (`role`)
+#[derive(Debug, Deserialize)]
+struct FactorConfig {
+    /// Field such as `machines[id=2].count` or `processes[0].duration`
+    #[serde(default)]
+    path: Option<String>,
+    /// Staff of this role are copied or dropped to reach each level
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    values: Vec<f64>,
+    /// Inclusive [from, to], `step` apart (default 1)
+    #[serde(default)]
+    range: Option<[f64; 2]>,
+    #[serde(default)]
+    step: Option<f64>,
 }
 
-struct App {
-    production: ProductionSimulator,
-    playing: bool,
-    tick_rate: Duration,
-    last_tick: Instant,
-    title: String,
-    machine_buckets: HashMap<u32, Vec<u32>>,
-    machine_to_bucket: HashMap<u32, u32>,
-    job_queues: HashMap<u32, Vec<PendingJob>>,
-    steps: Vec<ProcessConfig>,
-    items: u32,
-    next_pid: u32,
-    process_meta: HashMap<u32, (usize, u32)>, // process_id -> (step_index, item_id)
-    finished_goods: u32,
-    status_tab: usize,
-    process_scroll: usize,
+/// A goal-seeking search, run with `optimize optimize.json`
+#[derive(Debug, Deserialize)]
+struct OptimizeConfig {
+    /// Scenario whose counts are searched, relative to the optimize file
+    base: String,
+    target: TargetConfig,
+    /// Buckets whose machine count may change
+    #[serde(default)]
+    machines: Vec<MachineSearchConfig>,
+    /// Roles whose headcount may change
+    #[serde(default)]
+    roles: Vec<RoleSearchConfig>,
+    #[serde(default)]
+    method: Option<SearchMethodConfig>,
+    /// Steps of simulated annealing
+    #[serde(default = "default_iterations")]
+    iterations: u32,
+    #[serde(default = "default_replications")]
+    replications: u32,
+    /// Where the cheapest config meeting the target is written, relative to the optimize file
+    output: String,
 }
 
-fn run_tui_with_config(config_path: &str, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
-    let LoadedSim {
-        production,
-        machine_buckets,
-        machine_to_bucket,
-        steps,
-        items,
-    } = load_simulation_from_config(config_path, logger)?;
-    let mut app = App {
-        production,
-        playing: true,
-        tick_rate: Duration::from_millis(50),
-        last_tick: Instant::now(),
-        title: format!("AssemblySim - {}", config_path),
-        machine_buckets,
-        machine_to_bucket,
-        job_queues: HashMap::new(),
-        steps,
-        items,
-        next_pid: 0,
-        process_meta: HashMap::new(),
-        finished_goods: 0,
-        status_tab: 0,
-        process_scroll: 0,
-    };
+/// What the config must reach: finished goods per hour, or minutes to finish every item
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TargetConfig {
+    Throughput(f64),
+    Makespan(f64),
+}
 
-    // Seed initial jobs for the first step for all items
-    if let Some(first_step) = app.steps.get(0) {
-        let bucket = first_step.machine_id;
-        let duration = first_step.duration;
-        let queue = app.job_queues.entry(bucket).or_default();
-        for item_id in 0..app.items {
-            queue.push(PendingJob {
-                duration,
-                step_index: 0,
-                item_id,
-            });
-        }
-        try_start_jobs(&mut app, bucket, 0);
-    }
+#[derive(Debug, Deserialize)]
+struct MachineSearchConfig {
+    /// Machine id of the bucket
+    id: u32,
+    #[serde(default = "default_min_count")]
+    min: u32,
+    max: u32,
+    /// Cost of one machine
+    cost: f64,
+}
 
-    enable_raw_mode()?;
-    let mut stdout = std::io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+#[derive(Debug, Deserialize)]
+struct RoleSearchConfig {
+    role: String,
+    #[serde(default = "default_min_count")]
+    min: u32,
+    max: u32,
+    /// Cost of one person
+    cost: f64,
+}
 
-    let res = run_app(&mut terminal, &mut app);
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SearchMethodConfig {
+    Greedy,
+    Annealing,
+}
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CrewPaceConfig {
+    Slowest,
+    Average,
+}
 
-    res
+#[derive(Debug, Deserialize)]
+struct ResourceConfig {
+    id: u32,
+    name: String,
+    capacity: u32,
 }
 
-struct LoadedSim {
-    production: ProductionSimulator,
-    machine_buckets: HashMap<u32, Vec<u32>>,
-    machine_to_bucket: HashMap<u32, u32>,
-    steps: Vec<ProcessConfig>,
-    items: u32,
+#[derive(Debug, Deserialize)]
+struct MaterialConfig {
+    id: u32,
+    name: String,
+    /// Opening stock
+    #[serde(default)]
+    on_hand: u32,
+    /// Minutes from placing an order to its arrival
+    #[serde(default)]
+    lead_time: u32,
+    /// Without it the opening stock is all there is
+    #[serde(default)]
+    replenish: Option<ReplenishConfig>,
 }
 
-fn load_simulation_from_config(
-    config_path: &str,
-    logger: &Logger,
-) -> Result<LoadedSim, Box<dyn std::error::Error>> {
-    logger.info(&format!("Loading simulation config from {}", config_path));
+/// reorder_at + order_up_to = (s, S); reorder_at + order_quantity = reorder point;
+/// every + quantity = scheduled deliveries
+#[derive(Debug, Deserialize)]
+struct ReplenishConfig {
+    #[serde(default)]
+    reorder_at: Option<u32>,
+    #[serde(default)]
+    order_up_to: Option<u32>,
+    #[serde(default)]
+    order_quantity: Option<u32>,
+    #[serde(default)]
+    every: Option<u32>,
+    #[serde(default)]
+    quantity: Option<u32>,
+}
 
-    let path = Path::new(config_path);
-    if !path.exists() {
-        return Err(format!("Config file not found at {}", config_path).into());
-    }
+#[derive(Debug, Deserialize)]
+struct MaterialNeedConfig {
+    id: u32,
+    #[serde(default = "default_quantity")]
+    quantity: u32,
+}
 
-    let contents = fs::read_to_string(path)?;
-    let config: SimulationConfig = serde_json::from_str(&contents)?;
+#[derive(Debug, Deserialize)]
+struct ResourceNeedConfig {
+    id: u32,
+    #[serde(default = "default_quantity")]
+    quantity: u32,
+}
 
-    let mut production = ProductionSimulator::new();
-    let mut machine_buckets: HashMap<u32, Vec<u32>> = HashMap::new();
-    let mut next_machine_id: u32 = 0;
-    let mut machine_to_bucket: HashMap<u32, u32> = HashMap::new();
+#[derive(Debug, Deserialize)]
+struct TransportConfig {
+    /// Used for every bucket-to-bucket move without its own route
+    #[serde(default)]
+    default: Option<LegConfig>,
+    #[serde(default)]
+    routes: Vec<RouteConfig>,
+    /// Shared pool of forklifts / AGVs
+    #[serde(default)]
+    transporters: Option<TransporterConfig>,
+}
 
-    for machine_cfg in &config.machines {
-        let count = machine_cfg.count.unwrap_or(1);
-        for _ in 0..count {
-            let machine_id = next_machine_id;
-            next_machine_id += 1;
+#[derive(Debug, Deserialize)]
+struct RouteConfig {
+    from: u32,
+    to: u32,
+    #[serde(flatten)]
+    leg: LegConfig,
+}
 
-            let machine = if machine_cfg.is_automated.unwrap_or(false) {
-                MachineType::automated(machine_id, &machine_cfg.name)
-            } else {
-                let staff_needed = machine_cfg.staff_required.unwrap_or(1);
-                MachineType::new(machine_id, &machine_cfg.name, staff_needed)
-            };
+#[derive(Debug, Deserialize)]
+struct LegConfig {
+    /// Travel time in minutes (number or distribution)
+    #[serde(default)]
+    time: Option<DistributionConfig>,
+    #[serde(default)]
+    conveyor: Option<ConveyorConfig>,
+    /// Whether the move needs a transporter from the pool
+    #[serde(default)]
+    transporter: bool,
+}
 
-            production.add_machine(machine);
-            machine_buckets
-                .entry(machine_cfg.id)
-                .or_default()
-                .push(machine_id);
-            machine_to_bucket.insert(machine_id, machine_cfg.id);
-        }
-    }
+#[derive(Debug, Deserialize)]
+struct ConveyorConfig {
+    capacity: usize,
+    length: f64,
+    /// Length units per minute
+    speed: f64,
+}
 
-    for staff_cfg in &config.staff {
-        let role = if staff_cfg.role.machine_ids.is_empty() {
-            Role::new(staff_cfg.role.id, &staff_cfg.role.name)
-        } else {
-            Role::specialist(
-                staff_cfg.role.id,
-                &staff_cfg.role.name,
-                staff_cfg.role.machine_ids.clone(),
-            )
-        };
-        let staff = Staff::new(staff_cfg.id, &staff_cfg.name, role);
-        production.add_staff(staff);
-    }
+#[derive(Debug, Deserialize)]
+struct TransporterConfig {
+    #[serde(default = "default_transporter_name")]
+    name: String,
+    count: u32,
+}
 
-    Ok(LoadedSim {
-        production,
-        machine_buckets,
-        machine_to_bucket,
-        steps: config.processes,
-        items: config.items,
-    })
+/// Either a fixed number of minutes or a shaped distribution
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum DistributionConfig {
+    Fixed(u32),
+    Shaped(ShapedDistributionConfig),
 }
 
-fn run_app(
-    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-    app: &mut App,
-) -> Result<(), Box<dyn std::error::Error>> {
-    loop {
-        terminal.draw(|f| draw_ui(f, app))?;
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ShapedDistributionConfig {
+    Uniform { min: u32, max: u32 },
+    Triangular { min: u32, mode: u32, max: u32 },
+    Exponential { mean: f64 },
+}
 
-        let timeout = app
-            .tick_rate
-            .checked_sub(app.last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
+impl TryFrom<DistributionConfig> for Distribution {
+    type Error = String;
 
-        if event::poll(timeout)? {
-            if let CEvent::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) = event::read()? {
-                match code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Char(' ') => app.playing = !app.playing,
-                    KeyCode::Char('n') => {
-                        step_simulation(app);
-                    }
-                    KeyCode::Up => {
-                        app.process_scroll = app.process_scroll.saturating_sub(1);
-                    }
-                    KeyCode::Down => {
-                        let max_scroll = app.steps.len().saturating_sub(1);
-                        if app.process_scroll < max_scroll {
-                            app.process_scroll += 1;
-                        }
-                    }
-                    KeyCode::Tab => {
-                        app.status_tab = (app.status_tab + 1) % 2;
-                    }
-                    KeyCode::BackTab => {
-                        app.status_tab = app.status_tab.saturating_sub(1) % 2;
-                    }
-                    _ => {}
+    fn try_from(config: DistributionConfig) -> Result<Self, String> {
+        match config {
+            DistributionConfig::Fixed(minutes) => Ok(Distribution::Fixed(minutes)),
+            DistributionConfig::Shaped(ShapedDistributionConfig::Uniform { min, max }) => {
+                if min > max {
+                    return Err(format!("uniform min {} is above its max {}", min, max));
+                }
+                Ok(Distribution::Uniform { min, max })
+            }
+            DistributionConfig::Shaped(ShapedDistributionConfig::Triangular { min, mode, max }) => {
+                if !(min <= mode && mode <= max) {
+                    return Err(format!("triangular mode {} is outside [{}, {}]", mode, min, max));
+                }
+                Ok(Distribution::Triangular { min, mode, max })
+            }
+            DistributionConfig::Shaped(ShapedDistributionConfig::Exponential { mean }) => {
+                if mean <= 0.0 {
+                    return Err(format!("exponential mean {} must be above 0", mean));
                 }
+                Ok(Distribution::Exponential { mean })
             }
         }
+    }
+}
 
-        if app.last_tick.elapsed() >= app.tick_rate {
-            if app.playing {
-                step_simulation(app);
-                if sim_complete(app) {
-                    app.playing = false;
+#[derive(Debug, Deserialize)]
+struct MachineConfig {
+    id: u32,
+    name: String,
+    #[serde(default)]
+    staff_required: Option<u32>,
+    #[serde(default)]
+    is_automated: Option<bool>,
+    /// Number of identical machines in this bucket (e.g., 2 ovens)
+    #[serde(default)]
+    count: Option<u32>,
+    /// Load many items per cycle (furnaces, ovens, curing)
+    #[serde(default)]
+    batch: Option<BatchConfig>,
+    /// Staff this bucket with the fastest qualified people first (bottleneck steps)
+    #[serde(default)]
+    prefer_experts: bool,
+    /// Preventive maintenance plan for every machine in this bucket
+    #[serde(default)]
+    maintenance: Option<MaintenanceConfig>,
+    /// Cost per machine-hour while processing or in setup
+    #[serde(default)]
+    operating_cost_per_hour: Option<f64>,
+    /// Cost per machine-hour over the whole run
+    #[serde(default)]
+    fixed_cost_per_hour: Option<f64>,
+    /// Power draw per machine in kW for each state
+    #[serde(default)]
+    power: Option<PowerConfig>,
+    /// Switch idle machines off; requires `power`
+    #[serde(default)]
+    idle_shutdown: Option<IdleShutdownConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PowerConfig {
+    #[serde(default)]
+    off: f64,
+    #[serde(default)]
+    idle: f64,
+    #[serde(default)]
+    processing: f64,
+    #[serde(default)]
+    setup: f64,
+    #[serde(default)]
+    down: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdleShutdownConfig {
+    /// Idle minutes before switching off
+    after: u32,
+    /// Warm-up minutes before the next job after a shutdown
+    #[serde(default)]
+    restart: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MaintenanceConfig {
+    /// Calendar interval in minutes
+    #[serde(default)]
+    every_minutes: Option<u32>,
+    /// Completed processes between PMs
+    #[serde(default)]
+    every_cycles: Option<u32>,
+    /// Processing hours between PMs
+    #[serde(default)]
+    every_run_hours: Option<f64>,
+    duration: u32,
+    /// Let the running job finish first (default true); false pauses it for the PM
+    #[serde(default = "default_true")]
+    wait_for_job: bool,
+    /// Maintenance technicians needed for the PM
+    #[serde(default)]
+    technicians: u32,
+    /// Role id that counts as maintenance technician (default: any staff qualified for the machine)
+    #[serde(default)]
+    technician_role: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchConfig {
+    #[serde(default)]
+    min_size: Option<usize>,
+    max_size: usize,
+    /// Minutes to wait for a fuller load once `min_size` items are waiting
+    #[serde(default)]
+    max_wait: Option<u32>,
+    /// "same_recipe" (default) or "any"
+    #[serde(default)]
+    compatibility: Option<CompatibilityConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CompatibilityConfig {
+    Any,
+    SameRecipe,
+}
+
+#[derive(Debug, Deserialize)]
+struct StaffConfig {
+    id: u32,
+    name: String,
+    role: RoleConfig,
+    /// Skill per machine bucket; buckets not listed run at standard pace
+    #[serde(default)]
+    proficiency: Vec<ProficiencyConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProficiencyConfig {
+    machine_id: u32,
+    level: ProficiencyLevelConfig,
+}
+
+/// A named level or a duration factor (1.0 = standard)
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+enum ProficiencyLevelConfig {
+    Named(NamedProficiencyConfig),
+    Factor(f64),
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum NamedProficiencyConfig {
+    Trainee,
+    Standard,
+    Expert,
+}
+
+#[derive(Debug, Deserialize)]
+struct RoleConfig {
+    id: u32,
+    name: String,
+    /// Machine buckets this role may work on (empty = any)
+    #[serde(default)]
+    machine_ids: Vec<u32>,
+    /// Durations shrink with repetitions per staff member and bucket
+    #[serde(default)]
+    learning: Option<LearningConfig>,
+    /// Durations grow with time worked since the last break
+    #[serde(default)]
+    fatigue: Option<FatigueConfig>,
+    /// Pay per hour
+    #[serde(default)]
+    hourly_rate: Option<f64>,
+    /// Pay factor past the regular hours (default 1.5)
+    #[serde(default)]
+    overtime_multiplier: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct LearningConfig {
+    /// Duration multiplier per doubling of repetitions (0.9 = 90% curve)
+    rate: f64,
+    /// Durations never drop below this factor, in (0, 1]
+    floor: f64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct FatigueConfig {
+    /// Extra duration per hour worked since the last break (0.05 = 5%)
+    per_hour: f64,
+    /// Cap on the fatigue factor (default: no cap)
+    #[serde(default)]
+    max: Option<f64>,
+    /// Minutes of work before a break is taken
+    #[serde(default)]
+    break_after: Option<u32>,
+    /// Break length; idle gaps this long also reset fatigue
+    #[serde(default)]
+    break_minutes: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessConfig {
+    machine_id: u32,
+    /// Accepted for config compatibility; steps are addressed by index
+    #[serde(default)]
+    #[allow(dead_code)]
+    process_id: Option<u32>,
+    /// How long the process runs (the sum of `phases` when those are given)
+    #[serde(default)]
+    duration: u32,
+    /// Minutes per unit on top of `duration`; added to the run phase when there are phases
+    #[serde(default)]
+    time_per_unit: u32,
+    /// Other machine buckets the step needs a machine from at the same time
+    #[serde(default)]
+    also_uses: Vec<u32>,
+    /// Setup/load/run/unload phases with their own staffing; default is one staffed run phase
+    #[serde(default)]
+    phases: Vec<PhaseConfig>,
+    /// Recipe used by batch machines to decide which items share a load (default: one per step)
+    #[serde(default)]
+    recipe: Option<String>,
+    /// Probability (0.0 - 1.0) that an item passes this step
+    #[serde(default, rename = "yield")]
+    yield_rate: Option<f64>,
+    /// What happens to items that fail this step (default: scrap)
+    #[serde(default)]
+    on_fail: Option<FailureConfig>,
+    /// How often one item may loop back from this step before it is scrapped
+    #[serde(default)]
+    max_reworks: Option<u32>,
+    /// Resource pool units held for the whole step, together with the machine and staff
+    #[serde(default)]
+    resources: Vec<ResourceNeedConfig>,
+    /// Raw material taken from stock per item when the step starts
+    #[serde(default)]
+    materials: Vec<MaterialNeedConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PhaseConfig {
+    kind: PhaseKindConfig,
+    duration: u32,
+    /// Staff needed during the phase (default: the machine's `staff_required`, 0 for run)
+    #[serde(default)]
+    staff: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PhaseKindConfig {
+    Setup,
+    Load,
+    Run,
+    Unload,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FailureConfig {
+    Scrap,
+    /// Rework on another bucket, then repeat the failing step
+    Rework { machine_id: u32, duration: u32 },
+    /// Send the item back to an earlier step (index into `processes`)
+    ReturnTo(usize),
+}
+
+fn default_items() -> u32 {
+    1
+}
+
+fn default_seed() -> u64 {
+    42
+}
+
+fn default_replications() -> u32 {
+    1
+}
+
+fn default_iterations() -> u32 {
+    200
+}
+
+fn default_min_count() -> u32 {
+    1
+}
+
+fn default_quantity() -> u32 {
+    1
+}
+
+fn default_regular_hours() -> f64 {
+    8.0
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_walk_speed() -> f64 {
+    80.0
+}
+
+fn default_transporter_name() -> String {
+    "Transporter".to_string()
+}
+
+fn main() {
+    let logger = Logger::new(LogLevel::Debug);
+    let args: Vec<String> = env::args().collect();
+
+    if args.get(1).map(String::as_str) == Some("compare") {
+        let result = parse_compare_args(&args[2..]).and_then(|compare| run_compare(&compare, &logger));
+        if let Err(err) = result {
+            logger.error(&format!("Failed to compare scenarios: {}", err));
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("optimize") {
+        let Some(optimize_path) = args.get(2) else {
+            logger.error("optimize needs a search file: optimize optimize.json");
+            std::process::exit(1);
+        };
+        if let Err(err) = run_optimize(optimize_path, &logger) {
+            logger.error(&format!("Failed to optimize: {}", err));
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("sweep") {
+        let Some(sweep_path) = args.get(2) else {
+            logger.error("sweep needs a sweep file: sweep sweep.json");
+            std::process::exit(1);
+        };
+        if let Err(err) = run_sweep(sweep_path, &logger) {
+            logger.error(&format!("Failed to run sweep: {}", err));
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let cli = parse_args(&args);
+    if let Some(config_path) = cli.config {
+        let result = match &cli.report {
+            Some(report_path) => run_report(&config_path, report_path, cli.suggest_warmup, &logger),
+            None => run_tui_with_config(&config_path, cli.suggest_warmup, &logger),
+        };
+        if let Err(err) = result {
+            logger.error(&format!("Failed to run simulation from config: {}", err));
+            std::process::exit(1);
+        }
+    } else {
+        logger.info("No config file provided - running built-in examples");
+        run_examples(&logger);
+        logger.info("\nSimulation complete");
+    }
+}
+
+/// Command line: a config path (positional or `--config`), plus an optional `--report` file
+#[derive(Debug, Default, PartialEq)]
+struct CliArgs {
+    config: Option<String>,
+    report: Option<String>,
+    /// Suggest a warm-up even when the config sets none
+    suggest_warmup: bool,
+}
+
+/// `compare a.json b.json ... [--replications n] [--json out.json]`
+#[derive(Debug, PartialEq)]
+struct CompareArgs {
+    configs: Vec<String>,
+    replications: u32,
+    json: Option<String>,
+}
+
+fn parse_compare_args(args: &[String]) -> Result<CompareArgs, Box<dyn std::error::Error>> {
+    let mut compare = CompareArgs {
+        configs: Vec::new(),
+        replications: 5,
+        json: None,
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--replications" | "-n" => {
+                let value = iter.next().ok_or("--replications needs a number")?;
+                compare.replications = value
+                    .parse()
+                    .map_err(|_| format!("--replications must be a whole number, got {}", value))?;
+            }
+            "--json" => compare.json = Some(iter.next().ok_or("--json needs a file path")?.clone()),
+            path => compare.configs.push(path.to_string()),
+        }
+    }
+    if compare.configs.len() < 2 {
+        return Err("compare needs at least two config files".into());
+    }
+    if compare.replications == 0 {
+        return Err("--replications must be at least 1".into());
+    }
+    Ok(compare)
+}
+
+fn parse_args(args: &[String]) -> CliArgs {
+    let mut cli = CliArgs::default();
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--config" | "-c" => cli.config = iter.next().cloned(),
+            "--report" | "-r" => cli.report = iter.next().cloned(),
+            "--suggest-warmup" => cli.suggest_warmup = true,
+            path => {
+                if cli.config.is_none() {
+                    cli.config = Some(path.to_string());
+                }
+            }
+        }
+    }
+    cli
+}
+
+struct App {
+    production: ProductionSimulator,
+    playing: bool,
+    tick_rate: Duration,
+    last_tick: Instant,
+    title: String,
+    machine_buckets: BTreeMap<u32, Vec<u32>>,
+    machine_to_bucket: HashMap<u32, u32>,
+    job_queues: HashMap<u32, Vec<PendingJob>>,
+    steps: Vec<ProcessConfig>,
+    items: u32,
+    next_pid: u32,
+    process_meta: HashMap<u32, Vec<PendingJob>>, // process_id -> jobs loaded for it
+    batch_policies: HashMap<u32, BatchPolicy>,
+    batch_wakeups: HashMap<u32, u32>, // bucket -> scheduled timeout
+    transport: TransportNetwork,
+    transit: HashMap<u32, (Trip, PendingJob)>, // transit_id -> move in progress
+    transport_waiting: Vec<WaitingMove>,
+    next_transit_id: u32,
+    resources: ResourcePools,
+    step_resources: Vec<Vec<ResourceNeed>>,
+    resource_holds: HashMap<u32, Vec<ResourceNeed>>, // process_id -> units held
+    materials: Stockroom,
+    step_materials: Vec<Vec<MaterialNeed>>,
+    plant: Plant,
+    step_processes: Vec<Process>,
+    machine_holds: HashMap<u32, Vec<u32>>, // process_id -> machines seized besides its own
+    lots: LotTracker,
+    lot_steps: HashSet<(u32, usize)>,      // (lot_id, step_index) that took the per-lot time
+    unreleased: HashMap<u32, Vec<PendingJob>>, // lot_id -> transfer batches not yet released
+    step_phases: Vec<Vec<Phase>>,
+    phase_runs: HashMap<u32, PhaseRun>, // process_id -> phase progress
+    phase_waiting: Vec<u32>,            // process ids waiting for staff to start their next phase
+    maintenance: HashMap<u32, MaintenanceState>, // machine_id -> PM state
+    paused: HashMap<u32, PausedJob>,             // machine_id -> job stopped for maintenance
+    costs: Option<CostModel>,
+    energy: HashMap<u32, EnergyMeter>, // machine_id -> meter
+    plant_load: Vec<(u32, f64)>,       // (time, total kW) whenever the load changes
+    bottlenecks: BottleneckDetector,
+    bottleneck_window: Option<u32>,
+    warmup: Option<u32>,
+    observe_until: Option<u32>,
+    stats_reset: bool,
+    finished_before_warmup: u32, // finished goods and scrap when statistics restarted
+    scrapped_before_warmup: u32,
+    completion_times: Vec<u32>, // minute each finished good left, for the warm-up suggestion
+    last_release: u32,          // minute work last entered a line; only draining after that
+    warmup_hint: Option<u32>,   // warm-up suggested over replications of this config
+    oee: HashMap<u32, OeeCounters>, // machine_id -> work done, for performance and quality
+    status_log: HashMap<u32, Vec<(u32, MachineStatus)>>, // machine_id -> (time, status) at each change
+    staff_waits: HashMap<u32, u32>, // bucket -> times a machine started waiting for qualified staff
+    sampler: Option<Sampler>,
+    samples_csv: Option<String>,
+    finished_goods: u32,
+    scrapped: u32,
+    rng: SimRng,
+    yield_policies: Vec<Option<YieldPolicy>>,
+    quality: Vec<StepQuality>,
+    step_visits: HashMap<(u32, usize), u32>, // (item_id, step_index) -> completed visits
+    rework_counts: HashMap<(u32, usize), u32>, // (item_id, step_index) -> loops back
+    status_tab: usize,
+    process_scroll: usize,
+}
+
+fn run_tui_with_config(config_path: &str, suggest_warmup: bool, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
+    let loaded = load_simulation_from_config(config_path, logger)?;
+    let mut app = build_app(loaded, format!("AssemblySim - {}", config_path));
+    // Replications run before the UI opens, so their errors can't hide the run's own
+    if suggest_warmup || app.warmup.is_some() {
+        app.warmup_hint = warmup_suggestion(&serde_json::from_str(&fs::read_to_string(config_path)?)?)?;
+    }
+
+    enable_raw_mode()?;
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let res = run_app(&mut terminal, &mut app);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    println!("{}", run_summary(&app));
+    if let Some(path) = &app.samples_csv {
+        fs::write(path, samples_csv(&app))?;
+        println!("Samples written to {}", path);
+    }
+
+    res
+}
+
+/// Run without the UI and write the HTML report; queue lengths are sampled at every event unless configured
+fn run_report(config_path: &str, report_path: &str, suggest_warmup: bool, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
+    let mut loaded = load_simulation_from_config(config_path, logger)?;
+    loaded.sampler.get_or_insert_with(|| Sampler::new(SampleMode::EveryEvent));
+    let mut app = build_app(loaded, format!("AssemblySim - {}", config_path));
+    if !run_headless(&mut app) {
+        logger.warning("Simulation stalled before every item was done");
+    }
+    if let Some(warning) = warmup_unreached(&app) {
+        logger.warning(&warning);
+    }
+    if suggest_warmup || app.warmup.is_some() {
+        app.warmup_hint = warmup_suggestion(&serde_json::from_str(&fs::read_to_string(config_path)?)?)?;
+    }
+
+    println!("{}", run_summary(&app));
+    if let Some(path) = &app.samples_csv {
+        fs::write(path, samples_csv(&app))?;
+        println!("Samples written to {}", path);
+    }
+    fs::write(report_path, html_report(&app))?;
+    println!("Report written to {}", report_path);
+    Ok(())
+}
+
+/// Print the KPIs of every config side by side, and write them as JSON if asked
+fn run_compare(compare: &CompareArgs, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
+    let comparison = compare_scenarios(compare, logger)?;
+    println!("{}", comparison.to_table());
+    if let Some(path) = &compare.json {
+        fs::write(path, serde_json::to_string_pretty(&comparison_json(&comparison, compare.replications))?)?;
+        println!("Comparison written to {}", path);
+    }
+    Ok(())
+}
+
+/// Run every config `replications` times, with seeds counting up from its own
+fn compare_scenarios(compare: &CompareArgs, logger: &Logger) -> Result<Comparison, Box<dyn std::error::Error>> {
+    let mut runs = Vec::with_capacity(compare.configs.len());
+    for config_path in &compare.configs {
+        logger.info(&format!("Running {} x{}", config_path, compare.replications));
+        let config: serde_json::Value = serde_json::from_str(&fs::read_to_string(config_path)?)?;
+        let reps = replicate(&config, compare.replications, config_path, logger)?;
+        runs.push(reps.into_iter().map(|rep| rep.completed.then_some(rep.kpis)).collect::<Vec<_>>());
+    }
+    Ok(Comparison::new(compare.configs.clone(), &runs))
+}
+
+/// KPIs of one headless run, and whether it got every item done
+struct Replication {
+    kpis: Kpis,
+    completed: bool,
+}
+
+/// `replications` headless runs of a config, seeds counting up from its own
+fn replicate(
+    config: &serde_json::Value,
+    replications: u32,
+    label: &str,
+    logger: &Logger,
+) -> Result<Vec<Replication>, String> {
+    let mut reps = Vec::with_capacity(replications as usize);
+    for rep in 0..replications {
+        let parsed = serde_json::from_value(config.clone()).map_err(|e| format!("{}: {}", label, e))?;
+        let mut loaded = load_simulation(parsed).map_err(|e| format!("{}: {}", label, e))?;
+        loaded.seed = loaded.seed.wrapping_add(rep as u64);
+        let mut app = build_app(loaded, label.to_string());
+        let completed = run_headless(&mut app);
+        if !completed {
+            logger.warning(&format!("{} (replication {}) stalled before every item was done", label, rep + 1));
+        }
+        if let Some(warning) = warmup_unreached(&app) {
+            logger.warning(&format!("{} (replication {}): {}", label, rep + 1, warning));
+        }
+        reps.push(Replication {
+            kpis: run_kpis(&app),
+            completed,
+        });
+    }
+    Ok(reps)
+}
+
+/// Run every scenario of a sweep file in parallel and write one results row per scenario
+fn run_sweep(sweep_path: &str, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
+    let sweep: SweepConfig = serde_json::from_str(&fs::read_to_string(sweep_path)?)?;
+    let dir = Path::new(sweep_path).parent().unwrap_or(Path::new(""));
+    let base: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join(&sweep.base))?)?;
+    let results = sweep_scenarios(&sweep, &base, logger)?;
+    let output = dir.join(&sweep.output);
+    fs::write(&output, results.to_csv())?;
+    println!("{} scenarios written to {}", results.rows.len(), output.display());
+    Ok(())
+}
+
+fn sweep_scenarios(
+    sweep: &SweepConfig,
+    base: &serde_json::Value,
+    logger: &Logger,
+) -> Result<SweepResults, Box<dyn std::error::Error>> {
+    if sweep.replications == 0 {
+        return Err("replications must be at least 1".into());
+    }
+    let mut names = Vec::with_capacity(sweep.factors.len());
+    let mut levels = Vec::with_capacity(sweep.factors.len());
+    for factor in &sweep.factors {
+        let name = match (&factor.path, &factor.role) {
+            (Some(path), None) => path.clone(),
+            (None, Some(role)) => format!("{} staff", role),
+            _ => return Err("each factor needs either a path or a role".into()),
+        };
+        let values = match factor.range {
+            Some([from, to]) => range_levels(from, to, factor.step.unwrap_or(1.0)),
+            None => factor.values.clone(),
+        };
+        if values.is_empty() {
+            return Err(format!("factor {} has no levels (give values, or a range from low to high)", name).into());
+        }
+        names.push(name);
+        levels.push(values);
+    }
+    let design = match sweep.design.unwrap_or(DesignConfig::FullFactorial) {
+        DesignConfig::FullFactorial => Design::FullFactorial,
+        DesignConfig::LatinHypercube => match sweep.samples {
+            Some(samples) if samples > 0 => Design::LatinHypercube { samples },
+            _ => return Err("latin_hypercube needs samples of at least 1".into()),
+        },
+    };
+    let seed = base.get("seed").and_then(|s| s.as_u64()).unwrap_or_else(default_seed);
+    let counts: Vec<usize> = levels.iter().map(Vec::len).collect();
+    let points = design_points(design, &counts, &mut SimRng::new(seed));
+
+    // Build every scenario first so a bad path fails before anything runs
+    let mut scenarios = Vec::with_capacity(points.len());
+    for point in &points {
+        let values: Vec<f64> = point.iter().enumerate().map(|(f, &level)| levels[f][level]).collect();
+        let mut config = base.clone();
+        for (factor, &value) in sweep.factors.iter().zip(&values) {
+            match (&factor.path, &factor.role) {
+                (Some(path), _) => set_config_field(&mut config, path, value)?,
+                (_, Some(role)) => {
+                    if value < 0.0 || value.fract() != 0.0 {
+                        return Err(format!("Headcount for role {} must be a whole number, got {}", role, value).into());
+                    }
+                    set_headcount(&mut config, role, value as usize)?
+                }
+                _ => unreachable!("checked above"),
+            }
+        }
+        scenarios.push((values, config));
+    }
+    logger.info(&format!("Running {} scenarios x{}", scenarios.len(), sweep.replications));
+
+    let threads = sweep
+        .threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1))
+        .clamp(1, scenarios.len().max(1));
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let outcomes = std::sync::Mutex::new((0..scenarios.len()).map(|_| None).collect::<Vec<_>>());
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let idx = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let Some((_, config)) = scenarios.get(idx) else { break };
+                let label = format!("scenario {}", idx + 1);
+                let outcome = replicate(config, sweep.replications, &label, logger);
+                outcomes.lock().unwrap()[idx] = Some(outcome);
+            });
+        }
+    });
+
+    let mut results = SweepResults {
+        factors: names,
+        ..Default::default()
+    };
+    for ((values, _), outcome) in scenarios.into_iter().zip(outcomes.into_inner().unwrap()) {
+        let reps = outcome.expect("every scenario ran")?;
+        if results.kpis.is_empty() {
+            results.kpis = reps[0].kpis.iter().map(|&(name, _)| name.to_string()).collect();
+        }
+        // A stalled run's figures describe a plant that never finished, so only completed runs count
+        let completed: Vec<&Replication> = reps.iter().filter(|rep| rep.completed).collect();
+        let kpis = results
+            .kpis
+            .iter()
+            .map(|name| {
+                let values: Vec<f64> = completed
+                    .iter()
+                    .filter_map(|rep| rep.kpis.iter().find(|(n, _)| n == name).map(|&(_, v)| v))
+                    .collect();
+                (!values.is_empty()).then(|| Estimate::new(&values).mean)
+            })
+            .collect();
+        results.rows.push(SweepRow {
+            factors: values,
+            completed: completed.len() as u32,
+            kpis,
+        });
+    }
+    Ok(results)
+}
+
+/// Search for the cheapest counts meeting the target and write that config
+fn run_optimize(optimize_path: &str, logger: &Logger) -> Result<(), Box<dyn std::error::Error>> {
+    let optimize: OptimizeConfig = serde_json::from_str(&fs::read_to_string(optimize_path)?)?;
+    let dir = Path::new(optimize_path).parent().unwrap_or(Path::new(""));
+    let base: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join(&optimize.base))?)?;
+    let Some(found) = optimize_counts(&optimize, &base, logger)? else {
+        return Err("no config within the bounds meets the target".into());
+    };
+    let counts: Vec<String> = found
+        .counts
+        .iter()
+        .map(|(name, count)| format!("{} x{}", name, count))
+        .collect();
+    println!(
+        "Cheapest config meeting the target: {} | cost {:.2} | {} | {} configs simulated",
+        counts.join(", "),
+        found.cost,
+        found.achieved,
+        found.evaluations
+    );
+    let output = dir.join(&optimize.output);
+    fs::write(&output, serde_json::to_string_pretty(&found.config)?)?;
+    println!("Config written to {}", output.display());
+    Ok(())
+}
+
+/// Result of a goal-seeking search
+struct Optimum {
+    /// (bucket or role, count)
+    counts: Vec<(String, u32)>,
+    cost: f64,
+    /// The KPI reached, e.g. "throughput 6.20/hr"
+    achieved: String,
+    evaluations: usize,
+    config: serde_json::Value,
+}
+
+fn optimize_counts(
+    optimize: &OptimizeConfig,
+    base: &serde_json::Value,
+    logger: &Logger,
+) -> Result<Option<Optimum>, Box<dyn std::error::Error>> {
+    if optimize.machines.is_empty() && optimize.roles.is_empty() {
+        return Err("optimize needs at least one machine or role to vary".into());
+    }
+    if optimize.replications == 0 {
+        return Err("replications must be at least 1".into());
+    }
+    let mut dimensions = Vec::new();
+    let mut names = Vec::new();
+    for machine in &optimize.machines {
+        dimensions.push(Dimension { min: machine.min, max: machine.max, unit_cost: machine.cost });
+        let name = base["machines"]
+            .as_array()
+            .and_then(|list| list.iter().find(|m| m["id"].as_u64() == Some(machine.id as u64)))
+            .and_then(|m| m["name"].as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Machine {}", machine.id));
+        names.push(name);
+    }
+    for role in &optimize.roles {
+        dimensions.push(Dimension { min: role.min, max: role.max, unit_cost: role.cost });
+        names.push(role.role.clone());
+    }
+    if let Some((name, _)) = names.iter().zip(&dimensions).find(|(_, d)| d.min > d.max) {
+        return Err(format!("{}: min is above max", name).into());
+    }
+
+    let with_counts = |point: &[u32]| -> Result<serde_json::Value, String> {
+        let mut config = base.clone();
+        for (machine, &count) in optimize.machines.iter().zip(point) {
+            set_config_field(&mut config, &format!("machines[id={}].count", machine.id), count as f64)?;
+        }
+        for (role, &count) in optimize.roles.iter().zip(&point[optimize.machines.len()..]) {
+            set_headcount(&mut config, &role.role, count as usize)?;
+        }
+        Ok(config)
+    };
+    // None when a replication stalls: a plant that can't finish its items misses any target
+    let kpi = |config: &serde_json::Value| -> Result<Option<f64>, String> {
+        let name = match optimize.target {
+            TargetConfig::Throughput(_) => "Throughput (/hr)",
+            TargetConfig::Makespan(_) => "Makespan (mins)",
+        };
+        let reps = replicate(config, optimize.replications, "candidate", logger)?;
+        if reps.iter().any(|rep| !rep.completed) {
+            return Ok(None);
+        }
+        let values: Vec<f64> = reps
+            .iter()
+            .filter_map(|rep| rep.kpis.iter().find(|(n, _)| *n == name).map(|&(_, v)| v))
+            .collect();
+        Ok(Some(Estimate::new(&values).mean))
+    };
+    // Relative distance from the target, 0 once it is met
+    let shortfall = |value: Option<f64>| match (value, optimize.target) {
+        (None, _) => f64::INFINITY,
+        (Some(value), TargetConfig::Throughput(target)) => (target - value) / target.max(1e-9),
+        (Some(value), TargetConfig::Makespan(target)) => (value - target) / target.max(1e-9),
+    };
+
+    // Surface bad ids, roles or configs before searching
+    let highest: Vec<u32> = dimensions.iter().map(|d| d.max).collect();
+    load_simulation(serde_json::from_value(with_counts(&highest)?)?)?;
+
+    let mut failure: Option<String> = None;
+    let mut search = Search::new(dimensions, |point: &[u32]| {
+        match with_counts(point).and_then(|config| kpi(&config)) {
+            Ok(value) => shortfall(value),
+            Err(err) => {
+                failure.get_or_insert(err);
+                f64::INFINITY
+            }
+        }
+    });
+    let mut best = search.greedy();
+    if matches!(optimize.method, Some(SearchMethodConfig::Annealing)) {
+        let seed = base.get("seed").and_then(|s| s.as_u64()).unwrap_or_else(default_seed);
+        let start = best.clone().unwrap_or_else(|| highest.clone());
+        if let Some(annealed) = search.anneal(start, optimize.iterations, &mut SimRng::new(seed)) {
+            if best.as_ref().is_none_or(|b| search.cost(&annealed) < search.cost(b)) {
+                best = Some(annealed);
+            }
+        }
+    }
+    let evaluations = search.evaluations();
+    let cost = best.as_ref().map(|point| search.cost(point)).unwrap_or(0.0);
+    drop(search);
+    if let Some(err) = failure {
+        return Err(err.into());
+    }
+    let Some(point) = best else { return Ok(None) };
+
+    let config = with_counts(&point)?;
+    let value = kpi(&config)?.ok_or("the chosen counts stalled on a second run")?;
+    let achieved = match optimize.target {
+        TargetConfig::Throughput(_) => format!("throughput {:.2}/hr", value),
+        TargetConfig::Makespan(_) => format!("makespan {:.0} mins", value),
+    };
+    Ok(Some(Optimum {
+        counts: names.into_iter().zip(point).collect(),
+        cost,
+        achieved,
+        evaluations,
+        config,
+    }))
+}
+
+/// Set a config field addressed like `machines[id=2].count` or `processes[0].duration`
+fn set_config_field(config: &mut serde_json::Value, path: &str, value: f64) -> Result<(), String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    let mut node = config;
+    for (i, segment) in segments.iter().enumerate() {
+        let (field, selector) = match segment.split_once('[') {
+            Some((field, rest)) => {
+                let selector = rest.strip_suffix(']').ok_or_else(|| format!("{}: unclosed [", path))?;
+                (field, Some(selector))
+            }
+            None => (*segment, None),
+        };
+        if i + 1 == segments.len() && selector.is_none() {
+            let object = node
+                .as_object_mut()
+                .ok_or_else(|| format!("{}: {} is not inside an object", path, field))?;
+            object.insert(field.to_string(), json_number(value));
+            return Ok(());
+        }
+        node = node.get_mut(field).ok_or_else(|| format!("{}: no field {}", path, field))?;
+        if let Some(selector) = selector {
+            let items = node.as_array_mut().ok_or_else(|| format!("{}: {} is not a list", path, field))?;
+            let found = match selector.split_once('=') {
+                Some((key, wanted)) => items.iter_mut().find(|item| match item.get(key) {
+                    Some(serde_json::Value::String(text)) => text == wanted,
+                    Some(other) => serde_json::from_str::<serde_json::Value>(wanted).is_ok_and(|w| &w == other),
+                    None => false,
+                }),
+                None => selector.parse::<usize>().ok().and_then(|idx| items.get_mut(idx)),
+            };
+            node = found.ok_or_else(|| format!("{}: nothing in {} matches [{}]", path, field, selector))?;
+        }
+    }
+    Err(format!("{}: path must end in a field name", path))
+}
+
+/// Whole numbers as integers, so they fit count and id fields
+fn json_number(value: f64) -> serde_json::Value {
+    if value.fract() == 0.0 && value >= 0.0 {
+        serde_json::json!(value as u64)
+    } else {
+        serde_json::json!(value)
+    }
+}
+
+/// Copy the role's first member, or drop its last ones, until the role has `count` staff
+fn set_headcount(config: &mut serde_json::Value, role: &str, count: usize) -> Result<(), String> {
+    let staff = config
+        .get_mut("staff")
+        .and_then(serde_json::Value::as_array_mut)
+        .ok_or("config has no staff list")?;
+    let of_role = |member: &serde_json::Value| member["role"]["name"].as_str() == Some(role);
+    let template = staff
+        .iter()
+        .find(|member| of_role(member))
+        .cloned()
+        .ok_or_else(|| format!("no staff with role {}", role))?;
+    let mut have = staff.iter().filter(|member| of_role(member)).count();
+    while have > count {
+        if let Some(idx) = staff.iter().rposition(of_role) {
+            staff.remove(idx);
+        }
+        have -= 1;
+    }
+    let mut next_id = staff.iter().filter_map(|member| member["id"].as_u64()).max().map_or(0, |id| id + 1);
+    let mut number = have;
+    while have < count {
+        // Skip numbers already taken, so every name stays unique
+        let name = loop {
+            number += 1;
+            let name = format!("{} {}", role, number);
+            if !staff.iter().any(|member| member["name"].as_str() == Some(name.as_str())) {
+                break name;
+            }
+        };
+        let mut copy = template.clone();
+        copy["id"] = serde_json::json!(next_id);
+        copy["name"] = serde_json::json!(name);
+        staff.push(copy);
+        next_id += 1;
+        have += 1;
+    }
+    Ok(())
+}
+
+/// (name, value) pairs, in the same order for every run
+type Kpis = Vec<(&'static str, f64)>;
+
+/// Headline figures of one run, for comparing scenarios
+fn run_kpis(app: &App) -> Kpis {
+    let observed = observed_minutes(app);
+    let goods = observed_goods(app);
+    let throughput = if observed > 0 { goods as f64 / (observed as f64 / 60.0) } else { 0.0 };
+    let staff_busy = if app.production.staff.is_empty() || observed == 0 {
+        0.0
+    } else {
+        let available = app.production.staff.len() as f64 * observed as f64;
+        let away: u32 = app
+            .production
+            .staff
+            .iter()
+            .map(|s| s.idle_time + s.break_time + s.travel_time)
+            .sum();
+        (available - away as f64).max(0.0) / available * 100.0
+    };
+    let mut kpis = vec![
+        ("Makespan (mins)", app.production.simulator.elapsed_time() as f64),
+        ("Finished goods", goods as f64),
+        ("Scrapped", (app.scrapped - app.scrapped_before_warmup) as f64),
+        ("Throughput (/hr)", throughput),
+        ("Cycle time (mins)", app.lots.average_cycle_time().unwrap_or(0.0)),
+        ("Average WIP", app.lots.average_wip(observed)),
+        ("Staff busy (%)", staff_busy),
+    ];
+    if let Some(cost) = run_costs(app) {
+        kpis.push(("Cost", cost.total()));
+    }
+    kpis
+}
+
+fn comparison_json(comparison: &Comparison, replications: u32) -> serde_json::Value {
+    let kpis: Vec<serde_json::Value> = comparison
+        .kpis
+        .iter()
+        .map(|kpi| {
+            let scenarios: Vec<serde_json::Value> = kpi
+                .estimates
+                .iter()
+                .enumerate()
+                .map(|(idx, estimate)| {
+                    let delta = kpi.delta(idx);
+                    serde_json::json!({
+                        "scenario": comparison.scenarios[idx],
+                        "mean": estimate.map(|e| e.mean),
+                        "std_dev": estimate.map(|e| e.std_dev),
+                        "delta": delta.map(|(delta, _)| delta),
+                        "significant": idx > 0 && delta.is_some_and(|(_, significant)| significant),
+                    })
+                })
+                .collect();
+            serde_json::json!({ "kpi": kpi.name, "scenarios": scenarios })
+        })
+        .collect();
+    serde_json::json!({
+        "scenarios": comparison.scenarios,
+        "replications": replications,
+        "stalled": comparison.stalled,
+        "kpis": kpis,
+    })
+}
+
+/// Step until the run completes; false if it stalls (no events, nothing running)
+fn run_headless(app: &mut App) -> bool {
+    let mut stalled = 0;
+    while !sim_complete(app) {
+        step_simulation(app);
+        let still = app.production.simulator.peek_next_event().is_none()
+            && app.transit.is_empty()
+            && app.production.machines.iter().all(|m| !m.is_operating)
+            && app.production.staff.iter().all(|s| s.is_available);
+        stalled = if still { stalled + 1 } else { 0 };
+        if stalled > 2 && !sim_complete(app) {
+            return false;
+        }
+    }
+    true
+}
+
+fn build_app(loaded: LoadedSim, title: String) -> App {
+    let LoadedSim {
+        production,
+        machine_buckets,
+        machine_to_bucket,
+        steps,
+        items,
+        yield_policies,
+        batch_policies,
+        transport,
+        resources,
+        step_resources,
+        materials,
+        step_materials,
+        plant,
+        step_processes,
+        step_phases,
+        maintenance,
+        costs,
+        energy,
+        seed,
+        bottleneck_window,
+        warmup,
+        observe_until,
+        sampler,
+        samples_csv,
+    } = loaded;
+    let step_count = steps.len();
+    let mut app = App {
+        production,
+        playing: true,
+        tick_rate: Duration::from_millis(50),
+        last_tick: Instant::now(),
+        title,
+        machine_buckets,
+        machine_to_bucket,
+        job_queues: HashMap::new(),
+        steps,
+        items,
+        next_pid: 0,
+        process_meta: HashMap::new(),
+        batch_policies,
+        batch_wakeups: HashMap::new(),
+        transport,
+        transit: HashMap::new(),
+        transport_waiting: Vec::new(),
+        next_transit_id: 0,
+        resources,
+        step_resources,
+        resource_holds: HashMap::new(),
+        materials,
+        step_materials,
+        plant,
+        step_processes,
+        machine_holds: HashMap::new(),
+        lots: LotTracker::new(),
+        lot_steps: HashSet::new(),
+        unreleased: HashMap::new(),
+        step_phases,
+        phase_runs: HashMap::new(),
+        phase_waiting: Vec::new(),
+        maintenance,
+        paused: HashMap::new(),
+        costs,
+        energy,
+        plant_load: Vec::new(),
+        bottlenecks: BottleneckDetector::new(),
+        bottleneck_window,
+        warmup,
+        observe_until,
+        stats_reset: false,
+        finished_before_warmup: 0,
+        scrapped_before_warmup: 0,
+        completion_times: Vec::new(),
+        last_release: 0,
+        warmup_hint: None,
+        oee: HashMap::new(),
+        status_log: HashMap::new(),
+        staff_waits: HashMap::new(),
+        sampler,
+        samples_csv,
+        finished_goods: 0,
+        scrapped: 0,
+        rng: SimRng::new(seed),
+        yield_policies,
+        quality: vec![StepQuality::default(); step_count],
+        step_visits: HashMap::new(),
+        rework_counts: HashMap::new(),
+        status_tab: 0,
+        process_scroll: 0,
+    };
+
+    let mut planned: Vec<u32> = app.maintenance.keys().cloned().collect();
+    planned.sort_unstable();
+    for machine_id in planned {
+        if let MaintenanceTrigger::Calendar { every } = app.maintenance[&machine_id].plan.trigger {
+            app.production.simulator.schedule_event(
+                SimulationTime::new(every),
+                EventType::MaintenanceDue { machine_id },
+            );
+        }
+    }
+
+    for idx in 0..app.materials.materials.len() {
+        let material = &mut app.materials.materials[idx];
+        if let Some(arrives_at) = material.replenish(0) {
+            let material_id = material.id;
+            app.production.simulator.schedule_event(
+                SimulationTime::new(arrives_at),
+                EventType::MaterialArrival { material_id },
+            );
+        }
+    }
+
+    // Seed the first step of each line with its lots; later lots wait for their release
+    let mut next_item = 0;
+    let mut next_lot = 0;
+    for idx in 0..app.plant.lines.len() {
+        let line = &app.plant.lines[idx];
+        let (first, items, lots) = (line.steps.start, line.items, line.lots);
+        if app.steps.get(first).is_none() {
+            continue;
+        }
+        for (release_at, batches) in lots.plan(items) {
+            let lot_id = next_lot;
+            next_lot += 1;
+            let mut jobs = Vec::with_capacity(batches.len());
+            for units in batches {
+                let items: Vec<u32> = (next_item..next_item + units).collect();
+                next_item += units;
+                jobs.push(step_job(&mut app, first, items, lot_id, release_at));
+            }
+            if release_at == 0 {
+                release_lot(&mut app, lot_id, jobs, 0);
+            } else {
+                app.unreleased.insert(lot_id, jobs);
+                app.production
+                    .simulator
+                    .schedule_event(SimulationTime::new(release_at), EventType::LotRelease { lot_id });
+            }
+        }
+    }
+    update_energy(&mut app, 0);
+    update_machine_status(&mut app, 0);
+    if app.sampler.as_mut().map(|s| s.due_at(0)).unwrap_or(false) {
+        take_sample(&mut app, 0);
+    }
+
+    app
+}
+
+struct LoadedSim {
+    production: ProductionSimulator,
+    machine_buckets: BTreeMap<u32, Vec<u32>>,
+    machine_to_bucket: HashMap<u32, u32>,
+    steps: Vec<ProcessConfig>,
+    items: u32,
+    yield_policies: Vec<Option<YieldPolicy>>,
+    batch_policies: HashMap<u32, BatchPolicy>,
+    transport: TransportNetwork,
+    resources: ResourcePools,
+    step_resources: Vec<Vec<ResourceNeed>>,
+    materials: Stockroom,
+    step_materials: Vec<Vec<MaterialNeed>>,
+    plant: Plant,
+    step_processes: Vec<Process>,
+    step_phases: Vec<Vec<Phase>>,
+    maintenance: HashMap<u32, MaintenanceState>,
+    costs: Option<CostModel>,
+    energy: HashMap<u32, EnergyMeter>,
+    seed: u64,
+    bottleneck_window: Option<u32>,
+    warmup: Option<u32>,
+    observe_until: Option<u32>,
+    sampler: Option<Sampler>,
+    samples_csv: Option<String>,
+}
+
+fn load_simulation_from_config(
+    config_path: &str,
+    logger: &Logger,
+) -> Result<LoadedSim, Box<dyn std::error::Error>> {
+    logger.info(&format!("Loading simulation config from {}", config_path));
+
+    let path = Path::new(config_path);
+    if !path.exists() {
+        return Err(format!("Config file not found at {}", config_path).into());
+    }
+
+    let contents = fs::read_to_string(path)?;
+    load_simulation(serde_json::from_str(&contents)?)
+}
+
+/// Build a simulation from a parsed config
+fn load_simulation(mut config: SimulationConfig) -> Result<LoadedSim, Box<dyn std::error::Error>> {
+    let plant = build_plant(&mut config)?;
+    if config.bottleneck_window == Some(0) {
+        return Err("bottleneck_window must be at least 1 minute".into());
+    }
+    if config.observe_until == Some(0) {
+        return Err("observe_until must be at least 1 minute".into());
+    }
+    if let (Some(warmup), Some(until)) = (config.warmup, config.observe_until) {
+        if warmup >= until {
+            return Err(format!("warmup ({}) must end before observe_until ({})", warmup, until).into());
+        }
+    }
+    let sampler = match config.sampling.as_ref().map(|s| s.every) {
+        None => None,
+        Some(None) => Some(Sampler::new(SampleMode::EveryEvent)),
+        Some(Some(0)) => return Err("sampling.every must be at least 1 minute".into()),
+        Some(Some(every)) => Some(Sampler::new(SampleMode::Every(every))),
+    };
+    let samples_csv = config.sampling.as_ref().and_then(|s| s.csv.clone());
+
+    let mut production = ProductionSimulator::new();
+    let mut machine_buckets: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    let mut next_machine_id: u32 = 0;
+    let mut machine_to_bucket: HashMap<u32, u32> = HashMap::new();
+    let mut batch_policies: HashMap<u32, BatchPolicy> = HashMap::new();
+    let mut maintenance: HashMap<u32, MaintenanceState> = HashMap::new();
+    let mut energy: HashMap<u32, EnergyMeter> = HashMap::new();
+
+    for machine_cfg in &config.machines {
+        let plan = match &machine_cfg.maintenance {
+            Some(pm) => Some(build_maintenance_plan(machine_cfg.id, pm)?),
+            None => None,
+        };
+        let meter = build_energy_meter(machine_cfg)?;
+        if let Some(batch) = &machine_cfg.batch {
+            if batch.max_size == 0 {
+                return Err(format!("Batch machine {} needs max_size of at least 1", machine_cfg.id).into());
+            }
+            let compatibility = match batch.compatibility.unwrap_or(CompatibilityConfig::SameRecipe) {
+                CompatibilityConfig::Any => BatchCompatibility::Any,
+                CompatibilityConfig::SameRecipe => BatchCompatibility::SameRecipe,
+            };
+            batch_policies.insert(
+                machine_cfg.id,
+                BatchPolicy::new(
+                    batch.min_size.unwrap_or(1),
+                    batch.max_size,
+                    batch.max_wait,
+                    compatibility,
+                ),
+            );
+        }
+
+        let count = machine_cfg.count.unwrap_or(1);
+        for _ in 0..count {
+            let machine_id = next_machine_id;
+            next_machine_id += 1;
+
+            let machine = if machine_cfg.is_automated.unwrap_or(false) {
+                MachineType::automated(machine_id, &machine_cfg.name)
+            } else {
+                let staff_needed = machine_cfg.staff_required.unwrap_or(1);
+                MachineType::new(machine_id, &machine_cfg.name, staff_needed)
+            };
+
+            production.add_machine(machine);
+            if let Some(state) = production.machines.last_mut() {
+                state.bucket_id = machine_cfg.id;
+            }
+            if let Some(plan) = plan {
+                maintenance.insert(machine_id, MaintenanceState::new(plan));
+            }
+            if let Some(meter) = &meter {
+                energy.insert(machine_id, meter.clone());
+            }
+            machine_buckets
+                .entry(machine_cfg.id)
+                .or_default()
+                .push(machine_id);
+            machine_to_bucket.insert(machine_id, machine_cfg.id);
+        }
+    }
+
+    for machine_cfg in &config.machines {
+        if machine_cfg.prefer_experts {
+            for &machine_id in &machine_buckets[&machine_cfg.id] {
+                production.machines[machine_id as usize].prefer_experts = true;
+            }
+        }
+    }
+    production.pace = match config.crew_pace.unwrap_or(CrewPaceConfig::Slowest) {
+        CrewPaceConfig::Slowest => CrewPace::Slowest,
+        CrewPaceConfig::Average => CrewPace::Average,
+    };
+
+    for staff_cfg in &config.staff {
+        // Older configs listed physical machines here; fail rather than reinterpret them
+        if let Some(unknown) = staff_cfg.role.machine_ids.iter().find(|id| !machine_buckets.contains_key(id)) {
+            return Err(format!(
+                "Role {} of staff {} names unknown machine {}; machine_ids are bucket ids from machines",
+                staff_cfg.role.name, staff_cfg.id, unknown
+            )
+            .into());
+        }
+        // Configs name buckets; staff are matched against physical machines
+        let mut role = if staff_cfg.role.machine_ids.is_empty() {
+            Role::new(staff_cfg.role.id, &staff_cfg.role.name)
+        } else {
+            Role::specialist(
+                staff_cfg.role.id,
+                &staff_cfg.role.name,
+                bucket_machines(&machine_buckets, &staff_cfg.role.machine_ids),
+            )
+        };
+        if let Some(learning) = staff_cfg.role.learning {
+            if !(learning.rate > 0.0 && learning.rate <= 1.0) {
+                return Err(format!("Role {} has learning rate {}; it must be in (0, 1]", staff_cfg.role.name, learning.rate).into());
+            }
+            if !(learning.floor > 0.0 && learning.floor <= 1.0) {
+                return Err(format!("Role {} has learning floor {}; it must be in (0, 1]", staff_cfg.role.name, learning.floor).into());
+            }
+            role.learning = Some(LearningCurve {
+                rate: learning.rate,
+                floor: learning.floor,
+            });
+        }
+        if let Some(fatigue) = staff_cfg.role.fatigue {
+            if fatigue.per_hour < 0.0 {
+                return Err(format!("Role {} has a negative fatigue rate", staff_cfg.role.name).into());
+            }
+            if fatigue.break_after.is_some() && fatigue.break_minutes == 0 {
+                return Err(format!("Role {} takes breaks but has no break_minutes", staff_cfg.role.name).into());
+            }
+            role.fatigue = Some(FatigueModel {
+                per_hour: fatigue.per_hour,
+                max_factor: fatigue.max.unwrap_or(f64::INFINITY),
+                break_after: fatigue.break_after,
+                break_minutes: fatigue.break_minutes,
+            });
+        }
+        let mut staff = Staff::new(staff_cfg.id, &staff_cfg.name, role);
+        for skill in &staff_cfg.proficiency {
+            if !machine_buckets.contains_key(&skill.machine_id) {
+                return Err(format!(
+                    "Staff {} has a proficiency for unknown machine {}",
+                    staff_cfg.id, skill.machine_id
+                )
+                .into());
+            }
+            let proficiency = match skill.level {
+                ProficiencyLevelConfig::Named(NamedProficiencyConfig::Trainee) => Proficiency::Trainee,
+                ProficiencyLevelConfig::Named(NamedProficiencyConfig::Standard) => Proficiency::Standard,
+                ProficiencyLevelConfig::Named(NamedProficiencyConfig::Expert) => Proficiency::Expert,
+                ProficiencyLevelConfig::Factor(factor) if factor > 0.0 => Proficiency::Factor(factor),
+                ProficiencyLevelConfig::Factor(factor) => {
+                    return Err(format!("Staff {} has proficiency factor {}; it must be above 0", staff_cfg.id, factor).into());
+                }
+            };
+            staff.set_proficiency(skill.machine_id, proficiency);
+        }
+        production.add_staff(staff);
+    }
+
+    // A PM nobody can carry out would hold its machine forever
+    for (&bucket_id, machine_ids) in &machine_buckets {
+        let Some(plan) = maintenance.get(&machine_ids[0]).map(|state| &state.plan) else { continue };
+        if let Some(role_id) = plan.technician_role {
+            if !production.staff.iter().any(|s| s.role.id == role_id) {
+                return Err(format!("Maintenance for machine {} needs technician role {}, which no staff have", bucket_id, role_id).into());
+            }
+        }
+        let qualified = production
+            .staff
+            .iter()
+            .filter(|s| s.can_work_on(machine_ids[0]) && plan.technician_role.map(|role| s.role.id == role).unwrap_or(true))
+            .count();
+        if qualified < plan.technicians as usize {
+            return Err(format!(
+                "Maintenance for machine {} needs {} technician(s) but only {} staff qualify",
+                bucket_id, plan.technicians, qualified
+            )
+            .into());
+        }
+    }
+
+    if let Some(layout_cfg) = &config.layout {
+        production.layout = build_layout(layout_cfg, &machine_buckets)?;
+    }
+
+    let costs = build_cost_model(&config)?;
+    let step_phases = build_step_phases(&mut config.processes, &config.machines)?;
+    let step_processes = build_step_processes(&config.processes, &config.machines, &step_phases)?;
+    let yield_policies = build_yield_policies(&config.processes, &machine_buckets)?;
+    let resources = build_resources(&config.resources)?;
+    let step_resources = build_step_resources(&resources, &config.processes)?;
+    let materials = build_materials(&config.materials)?;
+    let step_materials = build_step_materials(&materials, &config.processes)?;
+    let transport = match config.transport {
+        Some(transport_cfg) => build_transport(transport_cfg, &machine_buckets)?,
+        None => TransportNetwork::new(),
+    };
+    // Transfer batches are loaded whole, so each must fit in a load
+    for line in &plant.lines {
+        for step in line.steps.clone() {
+            let bucket_id = config.processes[step].machine_id;
+            if let Some(policy) = batch_policies.get(&bucket_id) {
+                if line.lots.transfer_batch as usize > policy.max_size {
+                    return Err(format!(
+                        "Line {} moves transfer batches of {} but batch machine {} loads at most {}",
+                        line.name, line.lots.transfer_batch, bucket_id, policy.max_size
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+
+    Ok(LoadedSim {
+        production,
+        machine_buckets,
+        machine_to_bucket,
+        steps: config.processes,
+        items: config.items,
+        yield_policies,
+        batch_policies,
+        transport,
+        resources,
+        step_resources,
+        materials,
+        step_materials,
+        plant,
+        step_processes,
+        step_phases,
+        maintenance,
+        costs,
+        energy,
+        seed: config.seed.unwrap_or_else(default_seed),
+        bottleneck_window: config.bottleneck_window,
+        warmup: config.warmup.filter(|&w| w > 0),
+        observe_until: config.observe_until,
+        sampler,
+        samples_csv,
+    })
+}
+
+/// Move each line's machines, staff and steps into the plant-wide lists and link
+/// lines through stores. Without `lines`, the top-level steps form a single line.
+fn build_plant(config: &mut SimulationConfig) -> Result<Plant, Box<dyn std::error::Error>> {
+    let mut plant = Plant::new();
+    let plant_lots = match &config.lots {
+        Some(lots) => build_lot_policy("the plant", lots)?,
+        None => LotPolicy::default(),
+    };
+    if config.lines.is_empty() {
+        let mut line = ProductionLine::new("Main", 0..config.processes.len(), config.items);
+        line.lots = plant_lots;
+        plant.add_line(line);
+        return Ok(plant);
+    }
+    if !config.processes.is_empty() {
+        return Err("Use either processes or lines, not both".into());
+    }
+
+    for line_cfg in std::mem::take(&mut config.lines) {
+        if line_cfg.processes.is_empty() {
+            return Err(format!("Line {} has no steps", line_cfg.name).into());
+        }
+        let line_buckets: Vec<u32> = line_cfg.machines.iter().map(|m| m.id).collect();
+        for machine_cfg in line_cfg.machines {
+            if config.machines.iter().any(|m| m.id == machine_cfg.id) {
+                return Err(format!("Machine id {} is used twice in the plant", machine_cfg.id).into());
+            }
+            config.machines.push(machine_cfg);
+        }
+        for mut staff_cfg in line_cfg.staff {
+            if config.staff.iter().any(|s| s.id == staff_cfg.id) {
+                return Err(format!("Staff id {} is used twice in the plant", staff_cfg.id).into());
+            }
+            // Line staff stay on their own line's machines
+            if staff_cfg.role.machine_ids.is_empty() {
+                staff_cfg.role.machine_ids = line_buckets.clone();
+            }
+            config.staff.push(staff_cfg);
+        }
+        let first_step = config.processes.len();
+        for (idx, mut step) in line_cfg.processes.into_iter().enumerate() {
+            if let Some(&outside) = std::iter::once(&step.machine_id)
+                .chain(&step.also_uses)
+                .find(|bucket_id| !line_buckets.contains(bucket_id))
+            {
+                return Err(format!(
+                    "Line {} step {} uses machine {} from outside the line",
+                    line_cfg.name, idx, outside
+                )
+                .into());
+            }
+            // Lines number their steps from 0; the plant keeps one list
+            if let Some(FailureConfig::ReturnTo(target)) = &mut step.on_fail {
+                *target += first_step;
+            }
+            config.processes.push(step);
+        }
+        let items = line_cfg.items.unwrap_or(config.items);
+        let mut line = ProductionLine::new(&line_cfg.name, first_step..config.processes.len(), items);
+        line.start_material = line_cfg.start_material;
+        line.end_material = line_cfg.end_material;
+        line.lots = match &line_cfg.lots {
+            Some(lots) => build_lot_policy(&line_cfg.name, lots)?,
+            None => plant_lots,
+        };
+        plant.add_line(line);
+    }
+
+    let mut next_material_id = config.materials.iter().map(|m| m.id + 1).max().unwrap_or(0);
+    for idx in 0..plant.lines.len() {
+        let Some(name) = plant.lines[idx].start_material.clone() else { continue };
+        let feeders: Vec<usize> = (0..plant.lines.len())
+            .filter(|&other| plant.lines[other].end_material.as_deref() == Some(name.as_str()))
+            .collect();
+        if feeders.contains(&idx) {
+            return Err(format!("Line {} starts from its own output {}", plant.lines[idx].name, name).into());
+        }
+        let material_id = match config.materials.iter().find(|m| m.name == name) {
+            Some(material) => material.id,
+            None if !feeders.is_empty() => {
+                // A store between lines, empty until the feeding line delivers
+                let id = next_material_id;
+                next_material_id += 1;
+                config.materials.push(MaterialConfig {
+                    id,
+                    name: name.clone(),
+                    on_hand: 0,
+                    lead_time: 0,
+                    replenish: None,
+                });
+                id
+            }
+            None => {
+                return Err(format!(
+                    "Line {} starts from {} but no line makes it and no material has that name",
+                    plant.lines[idx].name, name
+                )
+                .into())
+            }
+        };
+        plant.lines[idx].input = Some(material_id);
+        for feeder in feeders {
+            plant.lines[feeder].output = Some(material_id);
+        }
+        let first_step = plant.lines[idx].steps.start;
+        config.processes[first_step].materials.push(MaterialNeedConfig {
+            id: material_id,
+            quantity: 1,
+        });
+    }
+    // A line can also fill a material that steps take through `materials`
+    for line in &mut plant.lines {
+        if line.output.is_some() {
+            continue;
+        }
+        let Some(name) = &line.end_material else { continue };
+        if let Some(material) = config.materials.iter().find(|m| &m.name == name) {
+            line.output = Some(material.id);
+        }
+    }
+    config.items = plant.lines.iter().map(|line| line.items).sum();
+    Ok(plant)
+}
+
+fn build_lot_policy(owner: &str, config: &LotConfig) -> Result<LotPolicy, Box<dyn std::error::Error>> {
+    if config.size == 0 {
+        return Err(format!("Lot size for {} must be at least 1", owner).into());
+    }
+    let transfer_batch = config.transfer_batch.unwrap_or(config.size);
+    if transfer_batch == 0 || transfer_batch > config.size {
+        return Err(format!(
+            "Transfer batch for {} must be between 1 and the lot size {}",
+            owner, config.size
+        )
+        .into());
+    }
+    Ok(LotPolicy {
+        size: config.size,
+        transfer_batch,
+        release_every: config.release_every,
+    })
+}
+
+/// Energy meter for each machine of a bucket with a power profile
+fn build_energy_meter(machine_cfg: &MachineConfig) -> Result<Option<EnergyMeter>, Box<dyn std::error::Error>> {
+    let Some(power) = &machine_cfg.power else {
+        if machine_cfg.idle_shutdown.is_some() {
+            return Err(format!("Machine {} has an idle_shutdown but no power profile", machine_cfg.id).into());
+        }
+        return Ok(None);
+    };
+    let profile = PowerProfile {
+        off: power.off,
+        idle: power.idle,
+        processing: power.processing,
+        setup: power.setup,
+        down: power.down,
+    };
+    if PowerState::ALL.iter().any(|&state| profile.draw(state) < 0.0) {
+        return Err(format!("Machine {} has a negative power draw", machine_cfg.id).into());
+    }
+    let shutdown = machine_cfg.idle_shutdown.as_ref().map(|policy| IdleShutdown {
+        after: policy.after,
+        restart: policy.restart,
+    });
+    Ok(Some(EnergyMeter::new(profile, shutdown)))
+}
+
+/// Prices from the config; None when nothing in it has a price
+fn build_cost_model(config: &SimulationConfig) -> Result<Option<CostModel>, Box<dyn std::error::Error>> {
+    let mut model = CostModel {
+        regular_minutes: 8 * 60,
+        ..CostModel::default()
+    };
+    let mut priced = false;
+    if let Some(cost) = &config.cost {
+        if cost.material_per_item < 0.0 || cost.scrap_cost < 0.0 || cost.regular_hours < 0.0 {
+            return Err("Cost settings must not be negative".into());
+        }
+        model.material_per_item = cost.material_per_item;
+        model.scrap_cost = cost.scrap_cost;
+        model.regular_minutes = (cost.regular_hours * 60.0).round() as u32;
+        priced = true;
+    }
+    for machine_cfg in &config.machines {
+        if machine_cfg.operating_cost_per_hour.is_none() && machine_cfg.fixed_cost_per_hour.is_none() {
+            continue;
+        }
+        model.machine_rates.insert(
+            machine_cfg.id,
+            MachineRates {
+                operating_per_hour: machine_cfg.operating_cost_per_hour.unwrap_or(0.0),
+                fixed_per_hour: machine_cfg.fixed_cost_per_hour.unwrap_or(0.0),
+            },
+        );
+        priced = true;
+    }
+    for staff_cfg in &config.staff {
+        let role = &staff_cfg.role;
+        let Some(hourly) = role.hourly_rate else { continue };
+        let rate = LaborRate {
+            hourly,
+            overtime_multiplier: role.overtime_multiplier.unwrap_or(1.5),
+        };
+        if let Some(existing) = model.labor_rates.get(&role.id) {
+            if *existing != rate {
+                return Err(format!("Role {} has different pay rates on different staff", role.id).into());
+            }
+        }
+        model.labor_rates.insert(role.id, rate);
+        priced = true;
+    }
+    Ok(if priced { Some(model) } else { None })
+}
+
+/// Check a bucket's PM config: exactly one trigger and a non-zero interval
+fn build_maintenance_plan(bucket_id: u32, config: &MaintenanceConfig) -> Result<MaintenancePlan, Box<dyn std::error::Error>> {
+    let trigger = match (config.every_minutes, config.every_cycles, config.every_run_hours) {
+        (Some(every), None, None) => MaintenanceTrigger::Calendar { every },
+        (None, Some(every), None) => MaintenanceTrigger::Cycles { every },
+        (None, None, Some(hours)) => MaintenanceTrigger::RunTime {
+            every: (hours * 60.0).round() as u32,
+        },
+        _ => {
+            return Err(format!(
+                "Maintenance for machine {} needs exactly one of every_minutes, every_cycles or every_run_hours",
+                bucket_id
+            )
+            .into())
+        }
+    };
+    let interval = match trigger {
+        MaintenanceTrigger::Calendar { every } | MaintenanceTrigger::Cycles { every } | MaintenanceTrigger::RunTime { every } => every,
+    };
+    if interval == 0 {
+        return Err(format!("Maintenance interval for machine {} must be above 0", bucket_id).into());
+    }
+    Ok(MaintenancePlan {
+        trigger,
+        duration: config.duration,
+        wait_for_job: config.wait_for_job,
+        technicians: config.technicians,
+        technician_role: config.technician_role,
+    })
+}
+
+/// Walking times between buckets for staff
+fn build_layout(
+    config: &LayoutConfig,
+    machine_buckets: &BTreeMap<u32, Vec<u32>>,
+) -> Result<PlantLayout, Box<dyn std::error::Error>> {
+    if config.walk_speed <= 0.0 {
+        return Err(format!("Layout walk_speed must be above 0, got {}", config.walk_speed).into());
+    }
+    let mut layout = PlantLayout::new(config.walk_speed);
+    for position in &config.positions {
+        if !machine_buckets.contains_key(&position.machine_id) {
+            return Err(format!("Layout places unknown machine {}", position.machine_id).into());
+        }
+        layout.set_position(position.machine_id, position.x, position.y);
+    }
+    for walk in &config.travel_times {
+        for bucket_id in [walk.from, walk.to] {
+            if !machine_buckets.contains_key(&bucket_id) {
+                return Err(format!("Layout travel time refers to unknown machine {}", bucket_id).into());
+            }
+        }
+        layout.set_travel_time(walk.from, walk.to, walk.minutes);
+    }
+    Ok(layout)
+}
+
+/// Physical machine ids for a list of bucket ids
+fn bucket_machines(machine_buckets: &BTreeMap<u32, Vec<u32>>, bucket_ids: &[u32]) -> Vec<u32> {
+    bucket_ids
+        .iter()
+        .filter_map(|bucket_id| machine_buckets.get(bucket_id))
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// Turn phase configs into phases; a step with phases lasts as long as its phases together
+fn build_step_phases(
+    steps: &mut [ProcessConfig],
+    machines: &[MachineConfig],
+) -> Result<Vec<Vec<Phase>>, Box<dyn std::error::Error>> {
+    let mut step_phases = Vec::with_capacity(steps.len());
+    for (idx, step) in steps.iter_mut().enumerate() {
+        let Some(machine_cfg) = machines.iter().find(|m| m.id == step.machine_id) else {
+            return Err(format!("Step {} uses unknown machine bucket {}", idx, step.machine_id).into());
+        };
+        let default_staff = if machine_cfg.is_automated.unwrap_or(false) {
+            0
+        } else {
+            machine_cfg.staff_required.unwrap_or(1)
+        };
+        let phases: Vec<Phase> = step
+            .phases
+            .iter()
+            .map(|phase| {
+                let kind = match phase.kind {
+                    PhaseKindConfig::Setup => PhaseKind::Setup,
+                    PhaseKindConfig::Load => PhaseKind::Load,
+                    PhaseKindConfig::Run => PhaseKind::Run,
+                    PhaseKindConfig::Unload => PhaseKind::Unload,
+                };
+                let staff = phase
+                    .staff
+                    .unwrap_or(if kind == PhaseKind::Run { 0 } else { default_staff });
+                Phase::new(kind, phase.duration, staff)
+            })
+            .collect();
+        if !phases.is_empty() {
+            step.duration = phases.iter().map(|p| p.duration).sum();
+        }
+        step_phases.push(phases);
+    }
+    Ok(step_phases)
+}
+
+/// Machines each step needs and how its time scales with quantity
+fn build_step_processes(
+    steps: &[ProcessConfig],
+    machines: &[MachineConfig],
+    step_phases: &[Vec<Phase>],
+) -> Result<Vec<Process>, Box<dyn std::error::Error>> {
+    let machine_type = |bucket_id: u32| {
+        machines.iter().find(|m| m.id == bucket_id).map(|cfg| {
+            if cfg.is_automated.unwrap_or(false) {
+                MachineType::automated(cfg.id, &cfg.name)
+            } else {
+                MachineType::new(cfg.id, &cfg.name, cfg.staff_required.unwrap_or(1))
+            }
+        })
+    };
+    let mut processes = Vec::with_capacity(steps.len());
+    for (idx, step) in steps.iter().enumerate() {
+        let mut required = Vec::with_capacity(1 + step.also_uses.len());
+        for &bucket_id in std::iter::once(&step.machine_id).chain(&step.also_uses) {
+            let Some(machine) = machine_type(bucket_id) else {
+                return Err(format!("Step {} uses unknown machine bucket {}", idx, bucket_id).into());
+            };
+            required.push(machine);
+        }
+        let phases = &step_phases[idx];
+        if step.time_per_unit > 0 && !phases.is_empty() && !phases.iter().any(|p| p.kind == PhaseKind::Run) {
+            return Err(format!("Step {} has a time_per_unit but no run phase to add it to", idx).into());
+        }
+        processes.push(Process::new(required, step.duration, step.time_per_unit));
+    }
+    Ok(processes)
+}
+
+fn build_resources(pools: &[ResourceConfig]) -> Result<ResourcePools, Box<dyn std::error::Error>> {
+    let mut resources = ResourcePools::new();
+    for pool in pools {
+        if resources.get(pool.id).is_some() {
+            return Err(format!("Resource pool id {} is defined twice", pool.id).into());
+        }
+        resources.add_pool(ResourcePool::new(pool.id, &pool.name, pool.capacity));
+    }
+    Ok(resources)
+}
+
+fn build_step_resources(
+    resources: &ResourcePools,
+    steps: &[ProcessConfig],
+) -> Result<Vec<Vec<ResourceNeed>>, Box<dyn std::error::Error>> {
+    let mut step_resources = Vec::with_capacity(steps.len());
+    for (idx, step) in steps.iter().enumerate() {
+        let mut needs = Vec::new();
+        for need in &step.resources {
+            let Some(pool) = resources.get(need.id) else {
+                return Err(format!("Step {} needs unknown resource pool {}", idx, need.id).into());
+            };
+            if need.quantity > pool.capacity {
+                return Err(format!(
+                    "Step {} needs {} x {} but the pool only has {}",
+                    idx, need.quantity, pool.name, pool.capacity
+                )
+                .into());
+            }
+            needs.push(ResourceNeed {
+                resource_id: need.id,
+                quantity: need.quantity,
+            });
+        }
+        step_resources.push(needs);
+    }
+    Ok(step_resources)
+}
+
+fn build_materials(configs: &[MaterialConfig]) -> Result<Stockroom, Box<dyn std::error::Error>> {
+    let mut stockroom = Stockroom::new();
+    for config in configs {
+        if stockroom.get(config.id).is_some() {
+            return Err(format!("Material id {} is defined twice", config.id).into());
+        }
+        let mut material = Material::new(config.id, &config.name, config.on_hand);
+        material.lead_time = config.lead_time;
+        if let Some(replenish) = &config.replenish {
+            material.policy = build_replenishment(&config.name, replenish)?;
+        }
+        stockroom.add_material(material);
+    }
+    Ok(stockroom)
+}
+
+/// Check a material's restocking config: one of the three policies, with sensible numbers
+fn build_replenishment(name: &str, config: &ReplenishConfig) -> Result<Replenishment, Box<dyn std::error::Error>> {
+    let policy = match (config.reorder_at, config.order_up_to, config.order_quantity, config.every, config.quantity) {
+        (Some(reorder_at), Some(order_up_to), None, None, None) => {
+            if order_up_to <= reorder_at {
+                return Err(format!("Material {} must order up to more than its reorder point", name).into());
+            }
+            Replenishment::MinMax {
+                reorder_at,
+                order_up_to,
+            }
+        }
+        (Some(reorder_at), None, Some(quantity), None, None) => {
+            if quantity == 0 {
+                return Err(format!("Material {} needs an order_quantity above 0", name).into());
+            }
+            Replenishment::ReorderPoint { reorder_at, quantity }
+        }
+        (None, None, None, Some(every), Some(quantity)) => {
+            if every == 0 || quantity == 0 {
+                return Err(format!("Material {} needs a delivery quantity and interval above 0", name).into());
+            }
+            Replenishment::Scheduled { every, quantity }
+        }
+        _ => {
+            return Err(format!(
+                "Material {} needs reorder_at with order_up_to or order_quantity, or every with quantity",
+                name
+            )
+            .into())
+        }
+    };
+    Ok(policy)
+}
+
+fn build_step_materials(
+    materials: &Stockroom,
+    steps: &[ProcessConfig],
+) -> Result<Vec<Vec<MaterialNeed>>, Box<dyn std::error::Error>> {
+    let mut step_materials = Vec::with_capacity(steps.len());
+    for (idx, step) in steps.iter().enumerate() {
+        let mut needs = Vec::new();
+        for need in &step.materials {
+            if materials.get(need.id).is_none() {
+                return Err(format!("Step {} needs unknown material {}", idx, need.id).into());
+            }
+            needs.push(MaterialNeed {
+                material_id: need.id,
+                quantity: need.quantity,
+            });
+        }
+        step_materials.push(needs);
+    }
+    Ok(step_materials)
+}
+
+fn build_transport(
+    config: TransportConfig,
+    machine_buckets: &BTreeMap<u32, Vec<u32>>,
+) -> Result<TransportNetwork, Box<dyn std::error::Error>> {
+    let has_pool = config.transporters.is_some();
+    let to_route = |leg: LegConfig, label: &str| -> Result<Route, Box<dyn std::error::Error>> {
+        if leg.transporter && !has_pool {
+            return Err(format!("Route {} needs a transporter but no transporters are configured", label).into());
+        }
+        let conveyor = match leg.conveyor {
+            Some(c) => {
+                if c.capacity == 0 {
+                    return Err(format!("Conveyor on route {} needs a capacity of at least 1", label).into());
+                }
+                if c.length < 0.0 {
+                    return Err(format!("Conveyor on route {} has length {}; it must not be negative", label, c.length).into());
+                }
+                if c.speed <= 0.0 {
+                    return Err(format!("Conveyor on route {} has speed {}; it must be above 0", label, c.speed).into());
+                }
+                Some(Conveyor::new(c.capacity, c.length, c.speed))
+            }
+            None => None,
+        };
+        let travel = match leg.time {
+            Some(time) => Distribution::try_from(time).map_err(|e| format!("Route {} travel time: {}", label, e))?,
+            None => Distribution::Fixed(0),
+        };
+        Ok(Route {
+            travel,
+            conveyor,
+            uses_transporter: leg.transporter,
+        })
+    };
+
+    let mut network = TransportNetwork::new();
+    if let Some(leg) = config.default {
+        network.default_route = Some(to_route(leg, "default")?);
+    }
+    for route_cfg in config.routes {
+        for bucket in [route_cfg.from, route_cfg.to] {
+            if !machine_buckets.contains_key(&bucket) {
+                return Err(format!("Transport route refers to unknown machine bucket {}", bucket).into());
+            }
+        }
+        let label = format!("{} -> {}", route_cfg.from, route_cfg.to);
+        network.add_route(route_cfg.from, route_cfg.to, to_route(route_cfg.leg, &label)?);
+    }
+    if let Some(pool) = config.transporters {
+        if pool.count == 0 {
+            return Err(format!("Transporter pool {} needs a count of at least 1", pool.name).into());
+        }
+        network.transporters = Some(TransporterPool::new(&pool.name, pool.count));
+    }
+    Ok(network)
+}
+
+fn build_yield_policies(
+    steps: &[ProcessConfig],
+    machine_buckets: &BTreeMap<u32, Vec<u32>>,
+) -> Result<Vec<Option<YieldPolicy>>, Box<dyn std::error::Error>> {
+    let mut policies = Vec::with_capacity(steps.len());
+    for (idx, step) in steps.iter().enumerate() {
+        let Some(yield_rate) = step.yield_rate else {
+            policies.push(None);
+            continue;
+        };
+        if !(0.0..=1.0).contains(&yield_rate) {
+            return Err(format!("Step {} has yield {} outside 0.0 - 1.0", idx, yield_rate).into());
+        }
+        let on_fail = match step.on_fail.clone().unwrap_or(FailureConfig::Scrap) {
+            FailureConfig::Scrap => FailureAction::Scrap,
+            FailureConfig::Rework { machine_id, duration } => {
+                if !machine_buckets.contains_key(&machine_id) {
+                    return Err(format!("Step {} reworks on unknown machine bucket {}", idx, machine_id).into());
+                }
+                FailureAction::Rework {
+                    bucket_id: machine_id,
+                    duration,
+                }
+            }
+            FailureConfig::ReturnTo(target) => {
+                if target >= idx {
+                    return Err(format!("Step {} can only return items to an earlier step, not {}", idx, target).into());
+                }
+                FailureAction::ReturnTo { step_index: target }
+            }
+        };
+        policies.push(Some(YieldPolicy::new(
+            yield_rate,
+            on_fail,
+            step.max_reworks.unwrap_or(1),
+        )));
+    }
+    Ok(policies)
+}
+
+fn run_app(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    app: &mut App,
+) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        terminal.draw(|f| draw_ui(f, app))?;
+
+        let timeout = app
+            .tick_rate
+            .checked_sub(app.last_tick.elapsed())
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if event::poll(timeout)? {
+            if let CEvent::Key(KeyEvent { code, kind: KeyEventKind::Press, .. }) = event::read()? {
+                match code {
+                    KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Char(' ') => app.playing = !app.playing,
+                    KeyCode::Char('n') => {
+                        step_simulation(app);
+                    }
+                    KeyCode::Up => {
+                        app.process_scroll = app.process_scroll.saturating_sub(1);
+                    }
+                    KeyCode::Down => {
+                        let max_scroll = app.steps.len().saturating_sub(1);
+                        if app.process_scroll < max_scroll {
+                            app.process_scroll += 1;
+                        }
+                    }
+                    KeyCode::Tab => {
+                        app.status_tab = (app.status_tab + 1) % STATUS_TABS.len();
+                    }
+                    KeyCode::BackTab => {
+                        app.status_tab = (app.status_tab + STATUS_TABS.len() - 1) % STATUS_TABS.len();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if app.last_tick.elapsed() >= app.tick_rate {
+            if app.playing {
+                step_simulation(app);
+                if sim_complete(app) {
+                    app.playing = false;
+                }
+            }
+            app.last_tick = Instant::now();
+        }
+    }
+}
+
+fn step_simulation(app: &mut App) {
+    let current = app.production.simulator.elapsed_time();
+    let mut target_time = current + 10;
+    if let Some(next_event) = app.production.simulator.peek_next_event() {
+        let next_time = next_event.time.as_minutes();
+        if next_time > current && next_time < target_time {
+            target_time = next_time;
+        }
+    }
+    // Stop at the end of the warm-up and of the observation window
+    if let Some(warmup) = app.warmup.filter(|&w| !app.stats_reset && w > current) {
+        target_time = target_time.min(warmup);
+    }
+    if let Some(until) = app.observe_until.filter(|&u| u > current) {
+        target_time = target_time.min(until);
+    }
+
+    // Interval samples up to target_time see the state left by the last event
+    let due = app.sampler.as_mut().map(|s| s.due_before(target_time)).unwrap_or_default();
+    for time in due {
+        take_sample(app, time);
+    }
+
+    // Rebalance stuck staff/machines before progressing time
+    rebalance(app, target_time);
+
+    // Process all events due up to target_time
+    loop {
+        if let Some(event) = app.production.simulator.peek_next_event() {
+            if event.time.as_minutes() <= target_time {
+                let evt = app.production.simulator.step().unwrap();
+                handle_event(app, evt);
+                continue;
+            }
+        }
+        break;
+    }
+
+    // Advance clock to target_time if nothing else happened
+    if app.production.simulator.elapsed_time() < target_time {
+        app.production
+            .simulator
+            .set_time(SimulationTime::new(target_time));
+    }
+
+    // Rebalance again after time advancement
+    rebalance(app, target_time);
+
+    // Due maintenance, then jobs part-way through their phases, get staff before new jobs start
+    start_due_maintenance(app, target_time);
+    resume_waiting_phases(app, target_time);
+
+    // Continuously attempt to start queued jobs on all buckets
+    let buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+    for bucket in buckets {
+        try_start_jobs(app, bucket, target_time);
+    }
+
+    app.production.finalize_idle_time(target_time);
+    app.resources.accumulate_until(target_time);
+    app.materials.accumulate_until(target_time);
+    app.lots.accumulate_until(target_time);
+    update_energy(app, target_time);
+    update_machine_status(app, target_time);
+    if app.warmup == Some(target_time) && !app.stats_reset {
+        reset_statistics(app, target_time);
+    }
+    if app.sampler.as_mut().map(|s| s.due_at(target_time)).unwrap_or(false) {
+        take_sample(app, target_time);
+    }
+}
+
+/// Restart every statistic at the end of the warm-up; the plant itself carries on as it is
+fn reset_statistics(app: &mut App, current_time: u32) {
+    app.stats_reset = true;
+    app.finished_before_warmup = app.finished_goods;
+    app.scrapped_before_warmup = app.scrapped;
+    app.production.reset_statistics(current_time);
+    app.resources.reset_statistics(current_time);
+    app.materials.reset_statistics(current_time);
+    app.lots.reset_statistics(current_time);
+    app.transport.reset_statistics();
+    for state in app.maintenance.values_mut() {
+        state.reset_statistics();
+    }
+    for meter in app.energy.values_mut() {
+        meter.reset_statistics(current_time);
+    }
+    for quality in &mut app.quality {
+        *quality = StepQuality::default();
+    }
+    app.oee.clear();
+    app.staff_waits.clear();
+    app.bottlenecks.reset(current_time);
+    app.plant_load.clear();
+    update_energy(app, current_time);
+}
+
+/// Warning when the run ended before its warm-up did, so nothing was reset
+fn warmup_unreached(app: &App) -> Option<String> {
+    let warmup = app.warmup.filter(|_| !app.stats_reset)?;
+    Some(format!(
+        "Warm-up of {} mins is longer than the {}-min run; statistics cover the whole run",
+        warmup,
+        app.production.simulator.elapsed_time()
+    ))
+}
+
+/// Minute the statistics count from (end of the warm-up, once reached)
+fn stats_start(app: &App) -> u32 {
+    if app.stats_reset {
+        app.warmup.unwrap_or(0)
+    } else {
+        0
+    }
+}
+
+/// Minutes the statistics cover
+fn observed_minutes(app: &App) -> u32 {
+    app.production.simulator.elapsed_time().saturating_sub(stats_start(app))
+}
+
+fn handle_event(app: &mut App, event: Event) {
+    let production = &mut app.production;
+    match event.event_type {
+        EventType::ProcessComplete {
+            machine_id,
+            process_id,
+        } => {
+            finish_process(app, machine_id, process_id, event.time.as_minutes());
+        }
+        EventType::PhaseComplete {
+            machine_id,
+            process_id,
+        } => {
+            let current_time = event.time.as_minutes();
+            if let Some(paused) = app.paused.get_mut(&machine_id) {
+                if paused.phase_running {
+                    // Maintenance stopped this phase; it finishes that much later
+                    paused.phase_running = false;
+                    let resume_at = current_time + paused.delay;
+                    if paused.staff.is_empty() {
+                        app.paused.remove(&machine_id);
+                    }
+                    app.production
+                        .simulator
+                        .schedule_event(SimulationTime::new(resume_at), event.event_type);
+                    return;
+                }
+            }
+            let Some(run) = app.phase_runs.get_mut(&process_id) else { return };
+            run.current += 1;
+            if run.current >= run.phases.len() {
+                app.phase_runs.remove(&process_id);
+                finish_process(app, machine_id, process_id, current_time);
+            } else {
+                app.phase_waiting.push(process_id);
+                resume_waiting_phases(app, current_time);
+            }
+        }
+        EventType::StaffReleased {
+            staff_id,
+            machine_id,
+        } => {
+            if let Some(paused) = app.paused.get_mut(&machine_id) {
+                if let Some(pos) = paused.staff.iter().position(|&id| id == staff_id) {
+                    // The crew of a paused phase waits out the maintenance
+                    paused.staff.remove(pos);
+                    let resume_at = event.time.as_minutes() + paused.delay;
+                    if paused.staff.is_empty() && !paused.phase_running {
+                        app.paused.remove(&machine_id);
+                    }
+                    app.production
+                        .simulator
+                        .schedule_event(SimulationTime::new(resume_at), event.event_type);
+                    return;
+                }
+            }
+            let production = &mut app.production;
+            if let Some(staff_member) = production.staff.iter_mut().find(|s| s.id == staff_id) {
+                staff_member.release_from_machine(production.simulator.elapsed_time());
+            }
+            // The machine stays busy until its process completes, even between staffed phases
+            if let Some(machine) = production.machines.get_mut(machine_id as usize) {
+                machine.assigned_staff.retain(|&id| id != staff_id);
+            }
+
+            // Due maintenance and machines part-way through a job get the freed staff first
+            start_due_maintenance(app, event.time.as_minutes());
+            resume_waiting_phases(app, event.time.as_minutes());
+            if let Some(bucket) = app.machine_to_bucket.get(&machine_id).cloned() {
+                try_start_jobs(app, bucket, event.time.as_minutes());
+            }
+        }
+        EventType::CrewArrived { .. } => {
+            // The machine's status moves from waiting for staff to working at the end of the step
+        }
+        EventType::StaffUnavailable { .. } => {
+            // Nothing to update in state, but could surface in UI later
+        }
+        EventType::StaffAvailable { staff_id } => {
+            // Back from a break
+            let current_time = event.time.as_minutes();
+            if let Some(staff_member) = production.staff.iter_mut().find(|s| s.id == staff_id) {
+                staff_member.release_from_machine(current_time);
+            }
+            start_due_maintenance(app, current_time);
+            resume_waiting_phases(app, current_time);
+            start_all_buckets(app, current_time);
+        }
+        EventType::MaintenanceDue { machine_id } => {
+            let current_time = event.time.as_minutes();
+            let Some(state) = app.maintenance.get_mut(&machine_id) else { return };
+            if let MaintenanceTrigger::Calendar { every } = state.plan.trigger {
+                state.mark_due(current_time);
+                app.production.simulator.schedule_event(
+                    SimulationTime::new(current_time + every),
+                    EventType::MaintenanceDue { machine_id },
+                );
+            }
+            start_due_maintenance(app, current_time);
+        }
+        EventType::MaintenanceComplete { machine_id } => {
+            let current_time = event.time.as_minutes();
+            if let Some(state) = app.maintenance.get_mut(&machine_id) {
+                state.finish(current_time);
+            }
+            if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+                machine.down = false;
+                if !machine.is_operating {
+                    machine.last_status_change = current_time;
+                    machine.waiting_for = Some(WaitReason::NextJob);
+                } else {
+                    machine.waiting_for = None;
+                }
+            }
+            resume_waiting_phases(app, current_time);
+            start_all_buckets(app, current_time);
+        }
+        EventType::TransportComplete { transit_id } => {
+            let current_time = event.time.as_minutes();
+            if let Some((trip, mut job)) = app.transit.remove(&transit_id) {
+                app.transport.finish_trip(&trip);
+                job.queued_at = current_time;
+                app.job_queues.entry(trip.to).or_default().push(job);
+                try_start_jobs(app, trip.to, current_time);
+            }
+            // A transporter or belt slot just freed up
+            start_waiting_moves(app, current_time);
+        }
+        EventType::MaterialArrival { material_id } => {
+            let current_time = event.time.as_minutes();
+            if let Some(material) = app.materials.get_mut(material_id) {
+                material.receive_due(current_time);
+            }
+            // Scheduled deliveries book the next one on arrival
+            reorder_material(app, material_id, current_time);
+            start_all_buckets(app, current_time);
+        }
+        EventType::LotRelease { lot_id } => {
+            if let Some(jobs) = app.unreleased.remove(&lot_id) {
+                release_lot(app, lot_id, jobs, event.time.as_minutes());
+            }
+        }
+        EventType::BatchTimeout { bucket_id } => {
+            if app.batch_wakeups.get(&bucket_id) == Some(&event.time.as_minutes()) {
+                app.batch_wakeups.remove(&bucket_id);
+            }
+            try_start_jobs(app, bucket_id, event.time.as_minutes());
+        }
+        _ => {}
+    }
+}
+
+/// A process moving through its phases on one machine
+struct PhaseRun {
+    machine_id: u32,
+    phases: Vec<Phase>,
+    /// Index of the phase running or waiting to run
+    current: usize,
+}
+
+/// Phases for a job: the step's own phases, or one run phase staffed like the machine,
+/// after an unattended warm-up if the machine was switched off
+fn job_phases(app: &App, job: &PendingJob, duration: u32, machine_id: u32, current_time: u32) -> Vec<Phase> {
+    let mut phases = Vec::new();
+    if let Some(meter) = app.energy.get(&machine_id) {
+        if let (PowerState::Off, Some(policy)) = (meter.state_at(current_time), meter.shutdown) {
+            if policy.restart > 0 {
+                phases.push(Phase::new(PhaseKind::Setup, policy.restart, 0));
+            }
+        }
+    }
+    match app.step_phases.get(job.step_index) {
+        Some(step) if !job.rework && !step.is_empty() => {
+            // Time per unit lengthens the run; later transfer batches of a lot skip setup
+            let fixed = if job.setup { app.step_processes[job.step_index].fixed_time } else { 0 };
+            let extra = duration.saturating_sub(fixed);
+            let mut step: Vec<Phase> = step
+                .iter()
+                .filter(|p| job.setup || p.kind != PhaseKind::Setup)
+                .cloned()
+                .collect();
+            if let Some(run) = step.iter_mut().find(|p| p.kind == PhaseKind::Run) {
+                run.duration += extra;
+            }
+            phases.extend(step);
+        }
+        _ => {
+            let staff = app
+                .production
+                .machines
+                .get(machine_id as usize)
+                .map(|m| if m.machine.needs_staff() { m.machine.staff_required } else { 0 })
+                .unwrap_or(0);
+            phases.push(Phase::new(PhaseKind::Run, duration, staff));
+        }
+    }
+    phases
+}
+
+/// Bring every energy meter up to `current_time` and record the plant load
+fn update_energy(app: &mut App, current_time: u32) {
+    if app.energy.is_empty() {
+        return;
+    }
+    let mut load = 0.0;
+    for (&machine_id, meter) in app.energy.iter_mut() {
+        let Some(machine) = app.production.machines.get(machine_id as usize) else { continue };
+        let state = if machine.down {
+            PowerState::Down
+        } else if machine.is_operating {
+            // A job waiting for staff between phases leaves the machine idling
+            match app
+                .phase_runs
+                .iter()
+                .find(|(_, run)| run.machine_id == machine_id)
+            {
+                Some((pid, _)) if app.phase_waiting.contains(pid) => PowerState::Idle,
+                Some((_, run)) if run.phases[run.current].kind == PhaseKind::Setup => PowerState::Setup,
+                _ => PowerState::Processing,
+            }
+        } else {
+            PowerState::Idle
+        };
+        meter.set_state(state, current_time);
+        load += meter.power_at(current_time);
+    }
+    if app.plant_load.last().map(|&(_, kw)| (kw - load).abs() > 1e-9).unwrap_or(true) {
+        app.plant_load.push((current_time, load));
+    }
+}
+
+/// Phase of the job running on a machine, and whether it is waiting for staff
+fn current_phase(app: &App, machine_id: u32) -> Option<(PhaseKind, bool)> {
+    app.phase_runs
+        .iter()
+        .find(|(_, run)| run.machine_id == machine_id)
+        .map(|(pid, run)| (run.phases[run.current].kind, app.phase_waiting.contains(pid)))
+}
+
+/// What a machine is doing now, for its time per status
+fn machine_status(app: &App, machine: &MachineState, current_time: u32) -> MachineStatus {
+    if machine.down {
+        return MachineStatus::Down;
+    }
+    if machine.is_operating {
+        return match current_phase(app, machine.machine.id) {
+            Some((_, true)) => MachineStatus::WaitingForStaff,
+            // The crew is still walking over
+            Some(_) if machine.crew_arrives_at > current_time => MachineStatus::WaitingForStaff,
+            Some((PhaseKind::Setup, _)) => MachineStatus::Setup,
+            _ => MachineStatus::Busy,
+        };
+    }
+    let queued = app
+        .job_queues
+        .get(&machine.bucket_id)
+        .map(|queue| !queue.is_empty())
+        .unwrap_or(false);
+    match &machine.waiting_for {
+        Some(WaitReason::Technician) => MachineStatus::Down,
+        // A reason left over from a job another machine has since taken
+        Some(reason) if queued => reason.status(),
+        _ => MachineStatus::Starved,
+    }
+}
+
+/// Bring every machine's status times up to `current_time` and note its status from now on
+fn update_machine_status(app: &mut App, current_time: u32) {
+    let statuses: Vec<MachineStatus> = app
+        .production
+        .machines
+        .iter()
+        .map(|machine| machine_status(app, machine, current_time))
+        .collect();
+    for (machine, status) in app.production.machines.iter_mut().zip(statuses) {
+        if status == MachineStatus::WaitingForStaff && machine.status_times.current != status {
+            *app.staff_waits.entry(machine.bucket_id).or_insert(0) += 1;
+        }
+        let log = app.status_log.entry(machine.machine.id).or_default();
+        if log.last().map(|&(_, last)| last != status).unwrap_or(true) {
+            log.push((current_time, status));
+        }
+        machine.status_times.set(status, current_time);
+    }
+    // A bucket has no spare capacity while every machine in it is active
+    for (&bucket_id, machine_ids) in &app.machine_buckets {
+        let active = machine_ids.iter().all(|&id| {
+            app.production
+                .machines
+                .get(id as usize)
+                .map(|m| m.status_times.current.is_active())
+                .unwrap_or(false)
+        });
+        app.bottlenecks.observe(bucket_id, active, current_time);
+    }
+}
+
+/// Time per status of a bucket's machines together
+fn bucket_status_times(app: &App, bucket_id: u32) -> StatusTimes {
+    let mut times = StatusTimes::new();
+    for machine_id in app.machine_buckets.get(&bucket_id).into_iter().flatten() {
+        if let Some(machine) = app.production.machines.get(*machine_id as usize) {
+            times.add(&machine.status_times);
+        }
+    }
+    times
+}
+
+/// OEE from a machine's (or bucket's) status times and work done
+fn oee_from(times: &StatusTimes, counters: &OeeCounters) -> Oee {
+    Oee::new(times.total(), times.minutes(MachineStatus::Down), counters)
+}
+
+fn machine_oee(app: &App, machine_id: u32) -> Oee {
+    let counters = app.oee.get(&machine_id).copied().unwrap_or_default();
+    match app.production.machines.get(machine_id as usize) {
+        Some(machine) => oee_from(&machine.status_times, &counters),
+        None => oee_from(&StatusTimes::new(), &counters),
+    }
+}
+
+fn bucket_oee(app: &App, bucket_id: u32) -> Oee {
+    let mut counters = OeeCounters::default();
+    for machine_id in app.machine_buckets.get(&bucket_id).into_iter().flatten() {
+        if let Some(machine_counters) = app.oee.get(machine_id) {
+            counters.add(machine_counters);
+        }
+    }
+    oee_from(&bucket_status_times(app, bucket_id), &counters)
+}
+
+/// "OEE 57.6% | availability 80.0% | performance 90.0% | quality 80.0%", with n/a for a factor without data
+fn oee_breakdown(oee: Oee) -> String {
+    let percent = |share: Option<f64>| share.map(|s| format!("{:.1}%", s * 100.0)).unwrap_or_else(|| "n/a".to_string());
+    format!(
+        "OEE {} | availability {} | performance {} | quality {}",
+        percent(oee.oee()),
+        percent(oee.availability),
+        percent(oee.performance),
+        percent(oee.quality)
+    )
+}
+
+/// "busy 60.0% | starved 40.0%", leaving out statuses never seen
+fn status_breakdown(times: &StatusTimes) -> String {
+    let parts: Vec<String> = MachineStatus::ALL
+        .iter()
+        .filter(|status| times.minutes(**status) > 0)
+        .map(|status| format!("{} {:.1}%", status.label(), times.share(*status) * 100.0))
+        .collect();
+    if parts.is_empty() {
+        "-".to_string()
+    } else {
+        parts.join(" | ")
+    }
+}
+
+/// Start the first phase of a process; false if its staff are not free
+fn start_process_phases(app: &mut App, machine_id: u32, process_id: u32, phases: Vec<Phase>, current_time: u32) -> bool {
+    let Some(first) = phases.first() else { return false };
+    if !app.production.try_start_phase(machine_id, process_id, first, current_time) {
+        return false;
+    }
+    app.phase_runs.insert(
+        process_id,
+        PhaseRun {
+            machine_id,
+            phases,
+            current: 0,
+        },
+    );
+    true
+}
+
+/// A job stopped part-way by maintenance that does not wait for it
+struct PausedJob {
+    /// Minutes the machine is offline; the job's pending events move back by this much
+    delay: u32,
+    /// Whether a phase was running (its completion still has to be pushed back)
+    phase_running: bool,
+    /// Crew of the running phase, held at the machine through the maintenance
+    staff: Vec<u32>,
+}
+
+/// A machine that can take a new job right now
+fn machine_free(app: &App, machine_id: u32) -> bool {
+    let blocked = app.maintenance.get(&machine_id).map(|pm| pm.blocks_jobs()).unwrap_or(false);
+    app.production
+        .machines
+        .get(machine_id as usize)
+        .map(|m| !m.is_operating && !m.down && !blocked)
+        .unwrap_or(false)
+}
+
+/// Start maintenance that is due, where the job and technicians allow it
+fn start_due_maintenance(app: &mut App, current_time: u32) {
+    let mut due: Vec<u32> = app
+        .maintenance
+        .iter()
+        .filter(|(_, pm)| pm.due_since.is_some())
+        .map(|(&machine_id, _)| machine_id)
+        .collect();
+    due.sort_unstable();
+    for machine_id in due {
+        let plan = app.maintenance[&machine_id].plan;
+        let Some(machine) = app.production.machines.get(machine_id as usize) else { continue };
+        let busy = machine.is_operating;
+        // A machine seized by another machine's job can't be paused on its own
+        if busy && (plan.wait_for_job || machine_seized(app, machine_id)) {
+            continue;
+        }
+        let crew = machine.assigned_staff.clone();
+        let Some(end_time) = app.production.try_start_maintenance(machine_id, &plan, current_time) else {
+            if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+                if !busy {
+                    machine.waiting_for = Some(WaitReason::Technician);
+                }
+            }
+            continue;
+        };
+        if let Some(state) = app.maintenance.get_mut(&machine_id) {
+            state.start(current_time);
+        }
+        if busy {
+            // Stop the running job: its phase and crew finish that much later
+            let delay = end_time - current_time;
+            let phase_running = app
+                .phase_runs
+                .iter()
+                .any(|(pid, run)| run.machine_id == machine_id && !app.phase_waiting.contains(pid));
+            for staff_id in &crew {
+                if let Some(staff) = app.production.staff.iter_mut().find(|s| s.id == *staff_id) {
+                    staff.available_at += delay;
+                }
+            }
+            if phase_running || !crew.is_empty() {
+                app.paused.insert(
+                    machine_id,
+                    PausedJob {
+                        delay,
+                        phase_running,
+                        staff: crew,
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Start next phases for machines that are holding a job and waiting for staff
+fn resume_waiting_phases(app: &mut App, current_time: u32) {
+    let waiting = std::mem::take(&mut app.phase_waiting);
+    for process_id in waiting {
+        let Some(run) = app.phase_runs.get(&process_id) else { continue };
+        let machine_id = run.machine_id;
+        let phase = run.phases[run.current];
+        if app.production.machines.get(machine_id as usize).map(|m| m.down).unwrap_or(false) {
+            app.phase_waiting.push(process_id);
+            continue;
+        }
+        if !app.production.try_start_phase(machine_id, process_id, &phase, current_time) {
+            if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+                machine.waiting_for = Some(WaitReason::Staff(Some(phase.kind.label())));
+            }
+            app.phase_waiting.push(process_id);
+        }
+    }
+}
+
+/// Free a machine whose process is done and route its items onwards
+fn finish_process(app: &mut App, machine_id: u32, process_id: u32, current_time: u32) {
+    let production = &mut app.production;
+    if let Some(machine) = production.machines.get_mut(machine_id as usize) {
+        // Immediately free any staff still marked on this machine
+        let releasing: Vec<u32> = machine.assigned_staff.clone();
+        for staff_id in releasing {
+            if let Some(staff_member) = production.staff.iter_mut().find(|s| s.id == staff_id) {
+                staff_member.release_from_machine(current_time);
+            }
+        }
+        if machine.is_operating {
+            if let Some(pm) = app.maintenance.get_mut(&machine_id) {
+                pm.record_process(current_time.saturating_sub(machine.last_status_change), current_time);
+            }
+            machine.is_operating = false;
+            machine.last_status_change = current_time;
+        }
+        machine.assigned_staff.clear();
+        machine.waiting_for = Some(WaitReason::NextJob);
+    }
+    let released = app.resource_holds.remove(&process_id);
+    if let Some(needs) = &released {
+        app.resources.release(needs, current_time);
+    }
+    let mut worked = vec![machine_id];
+    worked.extend(app.machine_holds.get(&process_id).into_iter().flatten());
+    let machines_released = release_machines(app, process_id, current_time);
+    // One completion releases every item loaded for this process
+    let jobs = app.process_meta.remove(&process_id).unwrap_or_default();
+    let ideal = load_duration(app, &jobs.iter().collect::<Vec<_>>());
+    let units = jobs.iter().map(|job| job.units()).sum();
+    let mut good = 0;
+    for job in jobs {
+        good += complete_job(app, job, current_time);
+    }
+    for machine_id in worked {
+        app.oee.entry(machine_id).or_default().record(ideal, units, good);
+    }
+    if released.is_some() || machines_released {
+        // Freed fixtures and machines may unblock other buckets
+        start_all_buckets(app, current_time);
+    }
+    start_due_maintenance(app, current_time);
+}
+
+/// Route a job's items onwards after it finishes; returns the units that passed
+fn complete_job(app: &mut App, job: PendingJob, current_time: u32) -> u32 {
+    let from_bucket = job_bucket(app, &job);
+    if job.rework {
+        // Reworked items repeat the step they failed
+        let units = job.units();
+        enqueue_step(app, from_bucket, job.step_index, job.items, job.lot_id, current_time);
+        return units;
+    }
+
+    // Items are inspected one by one; those that pass move on together
+    let mut passed = Vec::with_capacity(job.items.len());
+    let mut scrapped = false;
+    for &item_id in &job.items {
+        match inspect_item(app, item_id, job.step_index) {
+            Disposition::Pass => passed.push(item_id),
+            Disposition::Scrap => {
+                app.plant.record_scrap(job.step_index);
+                app.lots.exit(job.lot_id, 1, current_time);
+                app.scrapped += 1;
+                scrapped = true;
+            }
+            Disposition::Rework { bucket_id, duration } => {
+                let rework_job = PendingJob {
+                    duration,
+                    step_index: job.step_index,
+                    items: vec![item_id],
+                    lot_id: job.lot_id,
+                    setup: true,
+                    rework: true,
+                    queued_at: current_time,
+                };
+                dispatch_job(app, from_bucket, bucket_id, rework_job, current_time);
+            }
+            Disposition::ReturnTo { step_index } => {
+                enqueue_step(app, from_bucket, step_index, vec![item_id], job.lot_id, current_time);
+            }
+        }
+    }
+
+    let units = passed.len() as u32;
+    if !passed.is_empty() {
+        if let Some(next_step) = app.plant.next_step(job.step_index) {
+            enqueue_step(app, from_bucket, next_step, passed, job.lot_id, current_time);
+            return units;
+        }
+        app.lots.exit(job.lot_id, units, current_time);
+        match app.plant.record_completion(job.step_index, units) {
+            // Into the store feeding the next line
+            Some(store) => {
+                if let Some(material) = app.materials.get_mut(store) {
+                    material.put_away(units, current_time);
+                }
+            }
+            // Finished goods
+            None => {
+                app.finished_goods += units;
+                app.completion_times.extend(std::iter::repeat_n(current_time, units as usize));
+            }
+        }
+        start_all_buckets(app, current_time);
+    } else if scrapped {
+        start_all_buckets(app, current_time);
+    }
+    units
+}
+
+/// Send items from `from_bucket` to a step
+fn enqueue_step(app: &mut App, from_bucket: u32, step_index: usize, items: Vec<u32>, lot_id: u32, current_time: u32) {
+    let Some(step) = app.steps.get(step_index) else { return };
+    let bucket = step.machine_id;
+    let job = step_job(app, step_index, items, lot_id, current_time);
+    dispatch_job(app, from_bucket, bucket, job, current_time);
+}
+
+/// Job for items of a lot at a step; the lot's first transfer batch there takes the per-lot time
+fn step_job(app: &mut App, step_index: usize, items: Vec<u32>, lot_id: u32, current_time: u32) -> PendingJob {
+    let setup = app.lot_steps.insert((lot_id, step_index));
+    let process = &app.step_processes[step_index];
+    let units = items.len() as u32;
+    PendingJob {
+        duration: if setup { process.duration(units) } else { process.time_per_unit * units },
+        step_index,
+        items,
+        lot_id,
+        setup,
+        rework: false,
+        queued_at: current_time,
+    }
+}
+
+/// Queue a lot's transfer batches at the first step of its line
+fn release_lot(app: &mut App, lot_id: u32, jobs: Vec<PendingJob>, current_time: u32) {
+    let units = jobs.iter().map(|job| job.units()).sum();
+    app.lots.release(lot_id, units, current_time);
+    let Some(bucket) = jobs.first().map(|job| app.steps[job.step_index].machine_id) else { return };
+    app.job_queues.entry(bucket).or_default().extend(jobs);
+    try_start_jobs(app, bucket, current_time);
+}
+
+/// Bucket a job runs on
+fn job_bucket(app: &App, job: &PendingJob) -> u32 {
+    if job.rework {
+        if let Some(Some(YieldPolicy {
+            on_fail: FailureAction::Rework { bucket_id, .. },
+            ..
+        })) = app.yield_policies.get(job.step_index)
+        {
+            return *bucket_id;
+        }
+    }
+    app.steps[job.step_index].machine_id
+}
+
+/// Put a job into a bucket queue, moving it over the transport network first if needed
+fn dispatch_job(app: &mut App, from_bucket: u32, to_bucket: u32, job: PendingJob, current_time: u32) {
+    if !app.transport.has_route(from_bucket, to_bucket) {
+        app.job_queues.entry(to_bucket).or_default().push(job);
+        try_start_jobs(app, to_bucket, current_time);
+        return;
+    }
+    app.transport_waiting.push(WaitingMove {
+        from: from_bucket,
+        to: to_bucket,
+        job,
+        since: current_time,
+    });
+    start_waiting_moves(app, current_time);
+}
+
+/// Start every waiting move that has a free transporter / belt slot (oldest first)
+fn start_waiting_moves(app: &mut App, current_time: u32) {
+    let mut idx = 0;
+    while idx < app.transport_waiting.len() {
+        let (from, to) = (app.transport_waiting[idx].from, app.transport_waiting[idx].to);
+        let Some(trip) = app.transport.start_trip(from, to, &mut app.rng) else {
+            idx += 1;
+            continue;
+        };
+        let waiting = app.transport_waiting.remove(idx);
+        app.transport.stats.wait_minutes += current_time.saturating_sub(waiting.since);
+
+        let transit_id = app.next_transit_id;
+        app.next_transit_id += 1;
+        app.production.simulator.schedule_event(
+            SimulationTime::new(current_time + trip.duration),
+            EventType::TransportComplete { transit_id },
+        );
+        app.transit.insert(transit_id, (trip, waiting.job));
+    }
+}
+
+/// After an item leaves the line, try to start waiting work anywhere
+fn start_all_buckets(app: &mut App, current_time: u32) {
+    let buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+    for bucket in buckets {
+        try_start_jobs(app, bucket, current_time);
+    }
+}
+
+/// Apply the step's yield policy to an item that just completed it
+fn inspect_item(app: &mut App, item_id: u32, step_index: usize) -> Disposition {
+    let key = (item_id, step_index);
+    let visits = app.step_visits.entry(key).or_insert(0);
+    let first_attempt = *visits == 0;
+    *visits += 1;
+
+    let Some(Some(policy)) = app.yield_policies.get(step_index) else {
+        return Disposition::Pass;
+    };
+    let reworks = app.rework_counts.get(&key).copied().unwrap_or(0);
+    let disposition = policy.inspect(&mut app.rng, reworks);
+    if matches!(disposition, Disposition::Rework { .. } | Disposition::ReturnTo { .. }) {
+        *app.rework_counts.entry(key).or_insert(0) += 1;
+    }
+    if let Some(stats) = app.quality.get_mut(step_index) {
+        stats.record(first_attempt, &disposition);
+    }
+    disposition
+}
+
+fn rebalance(app: &mut App, current_time: u32) {
+    // Free staff whose availability time has passed or whose machine isn't running
+    for staff in &mut app.production.staff {
+        if !staff.is_available && current_time >= staff.available_at {
+            staff.release_from_machine(current_time);
+        }
+        if !staff.is_available {
+            if let Some(machine_id) = staff.current_machine {
+                let should_release = app
+                    .production
+                    .machines
+                    .get(machine_id as usize)
+                    .map(|m| !(m.is_operating || m.down) || !m.assigned_staff.contains(&staff.id))
+                    .unwrap_or(true);
+                if should_release {
+                    staff.release_from_machine(current_time);
+                }
+            }
+        }
+    }
+
+    // Clear any machines marked idle but still holding staff
+    for machine in &mut app.production.machines {
+        if !machine.is_operating && !machine.down && !machine.assigned_staff.is_empty() {
+            for staff_id in machine.assigned_staff.drain(..) {
+                if let Some(staff_member) = app.production.staff.iter_mut().find(|s| s.id == staff_id) {
+                    staff_member.release_from_machine(current_time);
+                }
+            }
+        }
+    }
+}
+
+/// Items that have left the plant or gone into a store for another line
+fn items_done(app: &App) -> u32 {
+    app.finished_goods + app.scrapped + app.plant.transferred()
+}
+
+fn sim_complete(app: &App) -> bool {
+    let observed = app
+        .observe_until
+        .map(|until| app.production.simulator.elapsed_time() >= until)
+        .unwrap_or(false);
+    observed
+        || items_done(app) >= app.items
+        || (app.unreleased.is_empty()
+            && app.job_queues.values().all(|q| q.is_empty())
+            && app.transit.is_empty()
+            && app.transport_waiting.is_empty()
+            && app
+                .production
+                .machines
+                .iter()
+                .all(|m| !m.is_operating && m.assigned_staff.is_empty())
+            && app
+                .production
+                .staff
+                .iter()
+                .all(|s| s.is_available))
+}
+
+/// Work in progress split by where the items are
+struct WipCounts {
+    queued: usize,
+    in_process: usize,
+    /// Moving between buckets or waiting for a transporter / belt space
+    in_transit: usize,
+}
+
+/// Record every bucket's queue, busy machines and busy staff, plus total WIP
+fn take_sample(app: &mut App, time: u32) {
+    let wip = wip_counts(app);
+    let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+    buckets.sort_unstable();
+    let buckets = buckets
+        .into_iter()
+        .map(|bucket_id| {
+            let machine_ids = &app.machine_buckets[&bucket_id];
+            BucketSample {
+                bucket_id,
+                queue: app
+                    .job_queues
+                    .get(&bucket_id)
+                    .map(|queue| queue.iter().map(|job| job.units()).sum())
+                    .unwrap_or(0),
+                busy_machines: machine_ids
+                    .iter()
+                    .filter(|&&id| app.production.machines.get(id as usize).map(|m| m.is_operating).unwrap_or(false))
+                    .count() as u32,
+                busy_staff: app
+                    .production
+                    .staff
+                    .iter()
+                    .filter(|s| !s.is_available && s.current_machine.map(|m| machine_ids.contains(&m)).unwrap_or(false))
+                    .count() as u32,
+            }
+        })
+        .collect();
+    let sample = Sample {
+        time,
+        wip: (wip.queued + wip.in_process + wip.in_transit) as u32,
+        buckets,
+    };
+    if let Some(sampler) = app.sampler.as_mut() {
+        sampler.record(sample);
+    }
+}
+
+/// The recorded samples as CSV, with bucket names as column labels
+fn samples_csv(app: &App) -> String {
+    let Some(sampler) = &app.sampler else { return String::new() };
+    let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+    buckets.sort_unstable();
+    let names: Vec<String> = buckets.iter().map(|&b| bucket_display_name(app, b)).collect();
+    sampler.to_csv(&names)
+}
+
+/// Minutes of machine activity shown in the report's Gantt chart
+const GANTT_MINUTES: u32 = 480;
+
+fn status_color(status: MachineStatus) -> &'static str {
+    match status {
+        MachineStatus::Busy => "#59a14f",
+        MachineStatus::Setup => "#edc948",
+        MachineStatus::Starved => "#bab0ac",
+        MachineStatus::Blocked => "#f28e2b",
+        MachineStatus::WaitingForStaff => "#b07aa1",
+        MachineStatus::Down => "#e15759",
+    }
+}
+
+/// The run as one HTML page: summary, KPI tables and inline SVG charts
+fn html_report(app: &App) -> String {
+    let elapsed = app.production.simulator.elapsed_time();
+    let start = stats_start(app);
+    let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+    buckets.sort_unstable();
+    let mut sections = Vec::new();
+
+    sections.push((
+        "Scenario".to_string(),
+        format!("<pre>{}</pre>\n", html::escape(&run_summary(app))),
+    ));
+
+    let shares = app.bottlenecks.analyze(start, elapsed, elapsed);
+    let mut headers = vec!["Bucket"];
+    headers.extend(MachineStatus::ALL.iter().map(|status| status.label()));
+    headers.extend(["OEE", "Availability", "Performance", "Quality", "Bottleneck mins"]);
+    let percent = |share: f64| format!("{:.1}%", share * 100.0);
+    let rows: Vec<Vec<String>> = buckets
+        .iter()
+        .map(|&bucket_id| {
+            let times = bucket_status_times(app, bucket_id);
+            let mut row = vec![bucket_display_name(app, bucket_id)];
+            row.extend(MachineStatus::ALL.iter().map(|&status| percent(times.share(status))));
+            let oee = bucket_oee(app, bucket_id);
+            row.extend(
+                [oee.oee(), oee.availability, oee.performance, oee.quality]
+                    .map(|share| share.map(percent).unwrap_or_else(|| "n/a".to_string())),
+            );
+            let bottleneck = shares.iter().find(|s| s.bucket_id == bucket_id).map(|s| s.total()).unwrap_or(0);
+            row.push(bottleneck.to_string());
+            row
+        })
+        .collect();
+    sections.push(("Key figures by bucket".to_string(), html::table(&headers, &rows)));
+
+    let mut bars: Vec<(String, f64)> = buckets
+        .iter()
+        .map(|&bucket_id| {
+            let times = bucket_status_times(app, bucket_id);
+            let used = times.share(MachineStatus::Busy) + times.share(MachineStatus::Setup);
+            (bucket_display_name(app, bucket_id), used)
+        })
+        .collect();
+    let mut body = String::from("<h3>Machines (busy and setup)</h3>\n");
+    body.push_str(&html::bar_chart(&bars));
+    let observed = observed_minutes(app);
+    if !app.production.staff.is_empty() && observed > 0 {
+        bars = app
+            .production
+            .staff
+            .iter()
+            .map(|s| {
+                (s.name.clone(), s.busy_time() as f64 / observed as f64)
+            })
+            .collect();
+        body.push_str("<h3>Staff (busy)</h3>\n");
+        body.push_str(&html::bar_chart(&bars));
+    }
+    sections.push(("Utilization".to_string(), body));
+
+    if let Some(sampler) = app.sampler.as_ref().filter(|s| !s.samples.is_empty()) {
+        let mut series: Vec<(String, Vec<(u32, f64)>)> = buckets
+            .iter()
+            .enumerate()
+            .map(|(i, &bucket_id)| {
+                let points = sampler
+                    .samples
+                    .iter()
+                    .map(|sample| (sample.time, sample.buckets[i].queue as f64))
+                    .collect();
+                (format!("{} queue", bucket_display_name(app, bucket_id)), points)
+            })
+            .collect();
+        series.push((
+            "WIP".to_string(),
+            sampler.samples.iter().map(|sample| (sample.time, sample.wip as f64)).collect(),
+        ));
+        sections.push(("Queue lengths".to_string(), html::step_chart(&series)));
+    }
+
+    if !app.lots.cycle_times.is_empty() {
+        sections.push((
+            "Cycle times (release to last unit out)".to_string(),
+            html::histogram(&app.lots.cycle_times, 12, "min"),
+        ));
+    }
+
+    let to = elapsed.min(start + GANTT_MINUTES);
+    if to > start {
+        let rows: Vec<(String, Vec<GanttSpan>)> = app
+            .production
+            .machines
+            .iter()
+            .map(|machine| {
+                let id = machine.machine.id;
+                let log = app.status_log.get(&id).map(Vec::as_slice).unwrap_or_default();
+                let spans = log
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(since, status))| {
+                        let until = log.get(i + 1).map(|&(t, _)| t).unwrap_or(elapsed);
+                        GanttSpan {
+                            start: since,
+                            end: until,
+                            color: status_color(status),
+                            title: format!("{} {}-{} min", status.label(), since, until),
+                        }
+                    })
+                    .collect();
+                let name = app
+                    .machine_to_bucket
+                    .get(&id)
+                    .map(|&b| bucket_display_name(app, b))
+                    .unwrap_or_else(|| format!("Machine {}", id));
+                (format!("{} #{}", name, id), spans)
+            })
+            .collect();
+        let legend: Vec<(&str, &str)> = MachineStatus::ALL
+            .iter()
+            .map(|&status| (status.label(), status_color(status)))
+            .collect();
+        sections.push((
+            format!("Machine states {}-{} mins", start, to),
+            html::gantt(&rows, start, to, &legend),
+        ));
+    }
+
+    html::page(&app.title, &sections)
+}
+
+fn wip_counts(app: &App) -> WipCounts {
+    WipCounts {
+        queued: app.job_queues.values().flatten().map(|job| job.units() as usize).sum(),
+        in_process: app.process_meta.values().flatten().map(|job| job.units() as usize).sum(),
+        in_transit: app.transit.values().map(|(_, job)| job.units() as usize).sum::<usize>()
+            + app.transport_waiting.iter().map(|w| w.job.units() as usize).sum::<usize>(),
+    }
+}
+
+/// End-of-run report printed after the UI closes
+fn run_summary(app: &App) -> String {
+    let mut report = format!(
+        "Run summary at {} mins\n  Items: {} | Finished goods: {} | Scrapped: {}\n",
+        app.production.simulator.elapsed_time(),
+        app.items,
+        app.finished_goods,
+        app.scrapped
+    );
+    if let Some(warning) = warmup_unreached(app) {
+        report.push_str(&format!("  {}\n", warning));
+    }
+    if app.warmup.is_some() || app.observe_until.is_some() {
+        let observed = observed_minutes(app);
+        let goods = observed_goods(app);
+        let rate = if observed > 0 { goods as f64 / (observed as f64 / 60.0) } else { 0.0 };
+        report.push_str(&format!(
+            "  Observed {}-{} mins: {} finished goods | {:.2}/hr\n",
+            stats_start(app),
+            app.production.simulator.elapsed_time(),
+            goods,
+            rate
+        ));
+    }
+    if let Some(minutes) = app.warmup_hint {
+        report.push_str(&format!(
+            "  Warm-up suggestion (Welch's method, hourly throughput over {} replications): {} mins\n",
+            WARMUP_REPLICATIONS, minutes
+        ));
+    }
+    let elapsed = observed_minutes(app);
+    if !app.production.staff.is_empty() && elapsed > 0 {
+        let available = app.production.staff.len() as f64 * elapsed as f64;
+        let idle: u32 = app.production.staff.iter().map(|s| s.idle_time).sum();
+        let breaks: u32 = app.production.staff.iter().map(|s| s.break_time).sum();
+        let travel: u32 = app.production.staff.iter().map(|s| s.travel_time).sum();
+        report.push_str(&format!(
+            "  Staff: {} | busy {:.1}% of staffed time\n",
+            app.production.staff.len(),
+            (available - idle as f64 - breaks as f64 - travel as f64).max(0.0) / available * 100.0
+        ));
+        if travel > 0 {
+            report.push_str(&format!(
+                "  Walking: {} mins ({:.1}% of staffed time)\n",
+                travel,
+                travel as f64 / available * 100.0
+            ));
+        }
+        if breaks > 0 {
+            report.push_str(&format!("  Breaks: {} mins in total\n", breaks));
+        }
+    }
+    if !app.production.staff.is_empty() && elapsed > 0 {
+        report.push_str(&staff_summary(app));
+    }
+    if elapsed > 0 {
+        report.push_str("Machine states:\n");
+        let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+        buckets.sort_unstable();
+        for bucket_id in buckets {
+            let machine_ids = &app.machine_buckets[&bucket_id];
+            report.push_str(&format!(
+                "  - {}: {}\n",
+                bucket_display_name(app, bucket_id),
+                status_breakdown(&bucket_status_times(app, bucket_id))
+            ));
+            if machine_ids.len() > 1 {
+                for machine_id in machine_ids {
+                    if let Some(machine) = app.production.machines.get(*machine_id as usize) {
+                        report.push_str(&format!(
+                            "      #{}: {}\n",
+                            machine_id,
+                            status_breakdown(&machine.status_times)
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    if elapsed > 0 {
+        report.push_str(&bottleneck_summary(app));
+        report.push_str("OEE:\n");
+        let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+        buckets.sort_unstable();
+        for bucket_id in buckets {
+            let machine_ids = &app.machine_buckets[&bucket_id];
+            report.push_str(&format!(
+                "  - {}: {}\n",
+                bucket_display_name(app, bucket_id),
+                oee_breakdown(bucket_oee(app, bucket_id))
+            ));
+            if machine_ids.len() > 1 {
+                for &machine_id in machine_ids {
+                    report.push_str(&format!(
+                        "      #{}: {}\n",
+                        machine_id,
+                        oee_breakdown(machine_oee(app, machine_id))
+                    ));
+                }
+            }
+        }
+    }
+    if app.plant.lines.len() > 1 {
+        report.push_str("Lines:\n");
+        for line in &app.plant.lines {
+            let mut entry = format!(
+                "  - {}: {}/{} out | scrapped {}",
+                line.name, line.completed, line.items, line.scrapped
+            );
+            if let Some(start) = &line.start_material {
+                entry.push_str(&format!(" | from {}", start));
+            }
+            if let Some(end) = &line.end_material {
+                let target = if line.output.is_some() { "store" } else { "finished goods" };
+                entry.push_str(&format!(" | makes {} ({})", end, target));
+            }
+            report.push_str(&entry);
+            report.push('\n');
+        }
+    }
+    if app.plant.lines.iter().any(|line| line.lots != LotPolicy::default()) {
+        let lots = &app.lots;
+        let cycle = lots
+            .average_cycle_time()
+            .map(|c| format!("{:.1} mins", c))
+            .unwrap_or_else(|| "n/a".to_string());
+        report.push_str(&format!(
+            "Lots: {} done | avg lot cycle time {} | WIP avg {:.1}, peak {} units\n",
+            lots.completed,
+            cycle,
+            lots.average_wip(observed_minutes(app)),
+            lots.peak_wip
+        ));
+        for line in &app.plant.lines {
+            let policy = &line.lots;
+            let mut entry = format!(
+                "  - {}: lot {} | transfer batch {}",
+                line.name, policy.size, policy.transfer_batch
+            );
+            if policy.release_every > 0 {
+                entry.push_str(&format!(" | released every {} mins", policy.release_every));
+            }
+            report.push_str(&entry);
+            report.push('\n');
+        }
+    }
+    let transport = &app.transport;
+    if transport.stats.moves > 0 {
+        report.push_str(&format!(
+            "Transport: {} moves | avg travel {:.1} mins | waited for handling {} mins\n",
+            transport.stats.moves,
+            transport.stats.travel_minutes as f64 / transport.stats.moves as f64,
+            transport.stats.wait_minutes
+        ));
+        if let Some(pool) = &transport.transporters {
+            let capacity = pool.total as f64 * observed_minutes(app) as f64;
+            let utilization = if capacity > 0.0 { pool.busy_minutes as f64 / capacity } else { 0.0 };
+            report.push_str(&format!(
+                "  {} x{}: {:.1}% utilized\n",
+                pool.name,
+                pool.total,
+                utilization * 100.0
+            ));
+        }
+    }
+    if !app.resources.pools.is_empty() {
+        report.push_str("Resource pools:\n");
+        let elapsed = observed_minutes(app);
+        for pool in &app.resources.pools {
+            report.push_str(&format!(
+                "  - {}: capacity {} | peak {} | {:.1}% utilized | {} waits\n",
+                pool.name,
+                pool.capacity,
+                pool.peak_in_use,
+                pool.utilization(elapsed) * 100.0,
+                pool.wait_count
+            ));
+        }
+    }
+    if !app.materials.materials.is_empty() {
+        report.push_str("Raw materials:\n");
+        let elapsed = observed_minutes(app);
+        for material in &app.materials.materials {
+            report.push_str(&format!(
+                "  - {}: on hand {} (avg {:.1}, low {}) | used {} | received {} | {} orders | out of stock {} mins | {} waits\n",
+                material.name,
+                material.on_hand,
+                material.average_on_hand(elapsed),
+                material.lowest,
+                material.consumed,
+                material.received,
+                material.orders,
+                material.stockout_minutes,
+                material.wait_count
+            ));
+        }
+    }
+    if let Some(cost) = run_costs(app) {
+        let per_good = cost
+            .per_good(observed_goods(app))
+            .map(|c| format!("{:.2}", c))
+            .unwrap_or_else(|| "-".to_string());
+        report.push_str(&format!("Cost: {:.2} total | {} per finished good\n", cost.total(), per_good));
+        report.push_str(&format!(
+            "  Labor {:.2} (overtime {:.2}) | Machines {:.2} operating + {:.2} fixed | Material {:.2} | Scrap {:.2}\n",
+            cost.labor, cost.overtime, cost.machine_operating, cost.machine_fixed, cost.material, cost.scrap
+        ));
+        report.push_str(&format!(
+            "  Labor + machines by time: busy {:.2} | setup {:.2} | idle {:.2}\n",
+            cost.busy, cost.setup, cost.idle
+        ));
+    }
+    if !app.energy.is_empty() {
+        report.push_str(&energy_summary(app));
+    }
+    if !app.maintenance.is_empty() {
+        report.push_str("Preventive maintenance:\n");
+        let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+        buckets.sort_unstable();
+        for bucket_id in buckets {
+            let states: Vec<&MaintenanceState> = app.machine_buckets[&bucket_id]
+                .iter()
+                .filter_map(|machine_id| app.maintenance.get(machine_id))
+                .collect();
+            if states.is_empty() {
+                continue;
+            }
+            report.push_str(&format!(
+                "  - {}: {} PMs | offline {} mins | waited {} mins to start\n",
+                bucket_display_name(app, bucket_id),
+                states.iter().map(|pm| pm.completed).sum::<u32>(),
+                states.iter().map(|pm| pm.down_minutes).sum::<u32>(),
+                states.iter().map(|pm| pm.delay_minutes).sum::<u32>()
+            ));
+        }
+    }
+    if app.yield_policies.iter().any(|p| p.is_some()) {
+        report.push_str("Quality by step:\n");
+        for (idx, step) in app.steps.iter().enumerate() {
+            if app.yield_policies[idx].is_none() {
+                continue;
+            }
+            let stats = &app.quality[idx];
+            let fpy = stats
+                .first_pass_yield()
+                .map(|y| format!("{:.1}%", y * 100.0))
+                .unwrap_or_else(|| "-".to_string());
+            report.push_str(&format!(
+                "  - Step {} {}: FPY {} | Scrap: {} | Rework: {}\n",
+                idx + 1,
+                bucket_display_name(app, step.machine_id),
+                fpy,
+                stats.scrapped,
+                stats.reworked
+            ));
+        }
+    }
+    report
+}
+
+/// "Press sole 75.0% shifting 10.0%; Oven shifting 10.0%" for a span of `minutes`
+fn bottleneck_shares(app: &App, shares: &[BottleneckShare], minutes: u32) -> String {
+    let percent = |m: u32| m as f64 / minutes.max(1) as f64 * 100.0;
+    let parts: Vec<String> = shares
+        .iter()
+        .map(|share| {
+            let mut part = bucket_display_name(app, share.bucket_id);
+            if share.sole > 0 {
+                part.push_str(&format!(" sole {:.1}%", percent(share.sole)));
+            }
+            if share.shifting > 0 {
+                part.push_str(&format!(" shifting {:.1}%", percent(share.shifting)));
+            }
+            part
+        })
+        .collect();
+    if parts.is_empty() {
+        "none".to_string()
+    } else {
+        parts.join("; ")
+    }
+}
+
+/// "busy 62.5% (Weld 50.0%, Paint 12.5%) | idle 30.0% | walking 5.0% | breaks 2.5%" over the observed time
+fn staff_breakdown(app: &App, members: &[&Staff]) -> String {
+    let available = members.len() as f64 * observed_minutes(app) as f64;
+    if available == 0.0 {
+        return "-".to_string();
+    }
+    let percent = |minutes: u32| minutes as f64 / available * 100.0;
+    let mut by_bucket: HashMap<u32, u32> = HashMap::new();
+    for member in members {
+        for (machine_id, &minutes) in &member.busy_by_machine {
+            let bucket_id = app.machine_to_bucket.get(machine_id).copied().unwrap_or(*machine_id);
+            *by_bucket.entry(bucket_id).or_insert(0) += minutes;
+        }
+    }
+    let mut buckets: Vec<(u32, u32)> = by_bucket.into_iter().filter(|&(_, minutes)| minutes > 0).collect();
+    buckets.sort_by_key(|&(bucket_id, minutes)| (std::cmp::Reverse(minutes), bucket_id));
+    let busy: u32 = buckets.iter().map(|&(_, minutes)| minutes).sum();
+    let mut text = format!("busy {:.1}%", percent(busy));
+    if !buckets.is_empty() {
+        let parts: Vec<String> = buckets
+            .iter()
+            .map(|&(bucket_id, minutes)| format!("{} {:.1}%", bucket_display_name(app, bucket_id), percent(minutes)))
+            .collect();
+        text.push_str(&format!(" ({})", parts.join(", ")));
+    }
+    let sum = |f: fn(&Staff) -> u32| members.iter().map(|m| f(m)).sum::<u32>();
+    text.push_str(&format!(" | idle {:.1}%", percent(sum(|m| m.idle_time))));
+    let travel = sum(|m| m.travel_time);
+    if travel > 0 {
+        text.push_str(&format!(" | walking {:.1}%", percent(travel)));
+    }
+    let breaks = sum(|m| m.break_time);
+    if breaks > 0 {
+        text.push_str(&format!(" | breaks {:.1}%", percent(breaks)));
+    }
+    text
+}
+
+/// Busy time by bucket, idle, walking and breaks per role and person, plus waits for staff per bucket
+fn staff_summary(app: &App) -> String {
+    let mut roles: Vec<&str> = Vec::new();
+    for member in &app.production.staff {
+        if !roles.contains(&member.role.name.as_str()) {
+            roles.push(&member.role.name);
+        }
+    }
+    let mut report = String::from("Staff utilization:\n");
+    for role in roles {
+        let members: Vec<&Staff> = app.production.staff.iter().filter(|m| m.role.name == role).collect();
+        report.push_str(&format!(
+            "  - {} ({}): {}\n",
+            role,
+            members.len(),
+            staff_breakdown(app, &members)
+        ));
+        if members.len() > 1 {
+            for member in members {
+                report.push_str(&format!("      {}: {}\n", member.name, staff_breakdown(app, &[member])));
+            }
+        }
+    }
+    let mut waits: Vec<(u32, u32)> = app.staff_waits.iter().map(|(&b, &n)| (b, n)).filter(|&(_, n)| n > 0).collect();
+    if !waits.is_empty() {
+        waits.sort_by_key(|&(bucket_id, count)| (std::cmp::Reverse(count), bucket_id));
+        let parts: Vec<String> = waits
+            .iter()
+            .map(|&(bucket_id, count)| format!("{} {}", bucket_display_name(app, bucket_id), count))
+            .collect();
+        report.push_str(&format!("  Waits for staff: {}\n", parts.join(" | ")));
+    }
+    report
+}
+
+/// Sole and shifting bottlenecks over the run and per window, plus the utilization ranking
+fn bottleneck_summary(app: &App) -> String {
+    let elapsed = app.production.simulator.elapsed_time();
+    let start = stats_start(app);
+    let shares = app.bottlenecks.analyze(start, elapsed, elapsed);
+    let mut report = String::from("Bottlenecks (active period method):\n");
+    report.push_str(&format!("  Run: {}\n", bottleneck_shares(app, &shares, elapsed - start)));
+
+    let mut ranking: Vec<(u32, f64)> = app
+        .machine_buckets
+        .keys()
+        .map(|&bucket_id| {
+            let times = bucket_status_times(app, bucket_id);
+            let used = times.share(MachineStatus::Busy) + times.share(MachineStatus::Setup);
+            (bucket_id, used)
+        })
+        .collect();
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    let ranking: Vec<String> = ranking
+        .iter()
+        .map(|&(bucket_id, used)| format!("{} {:.1}%", bucket_display_name(app, bucket_id), used * 100.0))
+        .collect();
+    report.push_str(&format!("  Utilization: {}\n", ranking.join(" > ")));
+
+    if let Some(window) = app.bottleneck_window {
+        let mut from = start;
+        while from < elapsed {
+            let to = (from + window).min(elapsed);
+            let shares = app.bottlenecks.analyze(from, to, elapsed);
+            report.push_str(&format!(
+                "  {}-{} mins: {}\n",
+                from,
+                to,
+                bottleneck_shares(app, &shares, to - from)
+            ));
+            from = to;
+        }
+    }
+    report
+}
+
+/// kWh in total, per finished good and per bucket / machine, plus the plant load
+fn energy_summary(app: &App) -> String {
+    let elapsed = observed_minutes(app);
+    let total: f64 = app.energy.values().map(|m| m.kwh()).sum();
+    let goods = observed_goods(app);
+    let per_good = if goods > 0 {
+        format!("{:.2}", total / goods as f64)
+    } else {
+        "-".to_string()
+    };
+    let peak = app.plant_load.iter().map(|&(_, kw)| kw).fold(0.0, f64::max);
+    let average = if elapsed > 0 { total / (elapsed as f64 / 60.0) } else { 0.0 };
+    let mut report = format!(
+        "Energy: {:.1} kWh | {} kWh per finished good | peak load {:.1} kW | average {:.1} kW\n",
+        total, per_good, peak, average
+    );
+
+    let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+    buckets.sort_unstable();
+    for bucket_id in buckets {
+        let meters: Vec<(u32, &EnergyMeter)> = app.machine_buckets[&bucket_id]
+            .iter()
+            .filter_map(|id| app.energy.get(id).map(|meter| (*id, meter)))
+            .collect();
+        if meters.is_empty() {
+            continue;
+        }
+        let by_state: Vec<String> = PowerState::ALL
+            .iter()
+            .map(|state| {
+                let kwh: f64 = meters.iter().map(|(_, m)| m.kwh_in(*state)).sum();
+                format!("{} {:.1}", state.label(), kwh)
+            })
+            .collect();
+        let per_machine: Vec<String> = meters
+            .iter()
+            .map(|(id, m)| format!("#{} {:.1}", id, m.kwh()))
+            .collect();
+        let shutdowns: u32 = meters.iter().map(|(_, m)| m.shutdowns).sum();
+        report.push_str(&format!(
+            "  - {}: {:.1} kWh ({}) | per machine: {}{}\n",
+            bucket_display_name(app, bucket_id),
+            meters.iter().map(|(_, m)| m.kwh()).sum::<f64>(),
+            by_state.join(", "),
+            per_machine.join(", "),
+            if shutdowns > 0 { format!(" | {} idle shutdowns", shutdowns) } else { String::new() }
+        ));
+    }
+    report
+}
+
+/// Cost of the run so far, if the config has prices
+fn run_costs(app: &App) -> Option<CostBreakdown> {
+    let model = app.costs.as_ref()?;
+    let elapsed = observed_minutes(app);
+    let machines: Vec<MachineUsage> = app
+        .production
+        .machines
+        .iter()
+        .map(|m| {
+            let down = app.maintenance.get(&m.machine.id).map(|pm| pm.down_minutes).unwrap_or(0);
+            let idle = m.idle_time + down;
+            MachineUsage {
+                bucket_id: m.bucket_id,
+                busy: elapsed.saturating_sub(idle + m.setup_time),
+                setup: m.setup_time,
+                idle,
+            }
+        })
+        .collect();
+    let staff: Vec<StaffUsage> = app
+        .production
+        .staff
+        .iter()
+        .map(|s| {
+            let idle = s.idle_time + s.break_time;
+            StaffUsage {
+                role_id: s.role.id,
+                busy: elapsed.saturating_sub(idle + s.setup_time),
+                setup: s.setup_time,
+                idle,
+            }
+        })
+        .collect();
+    let scrapped = app.scrapped - app.scrapped_before_warmup;
+    Some(model.breakdown(&machines, &staff, observed_goods(app) + scrapped, scrapped, elapsed))
+}
+
+/// Finished goods since the statistics restarted
+fn observed_goods(app: &App) -> u32 {
+    app.finished_goods - app.finished_before_warmup
+}
+
+/// Replications that Welch's method averages for the warm-up suggestion
+const WARMUP_REPLICATIONS: u32 = 5;
+
+/// Warm-up length by Welch's method on hourly throughput over replications of a config
+///
+/// Each run's series ends where the last item entered its line, so a finite run's
+/// drain-down is left out. There is no suggestion when a run stalls, with under 8
+/// hours before the drain-down, or under 5 goods an hour, where hourly counts are
+/// mostly noise.
+fn warmup_suggestion(config: &serde_json::Value) -> Result<Option<u32>, String> {
+    const PERIOD: u32 = 60;
+    const MIN_PER_PERIOD: f64 = 5.0;
+    let mut runs = Vec::with_capacity(WARMUP_REPLICATIONS as usize);
+    for rep in 0..WARMUP_REPLICATIONS {
+        let parsed = serde_json::from_value(config.clone()).map_err(|e| e.to_string())?;
+        let mut loaded = load_simulation(parsed).map_err(|e| e.to_string())?;
+        loaded.seed = loaded.seed.wrapping_add(rep as u64);
+        // The series covers the whole run, whatever window the config observes
+        loaded.warmup = None;
+        loaded.observe_until = None;
+        loaded.sampler = None;
+        let mut app = build_app(loaded, String::new());
+        if !run_headless(&mut app) {
+            return Ok(None);
+        }
+        runs.push(throughput_series(&app.completion_times, PERIOD, app.last_release));
+    }
+    let periods = runs.iter().map(Vec::len).min().unwrap_or(0);
+    if periods < 8 {
+        return Ok(None);
+    }
+    let window = (periods / 10).max(1);
+    let smoothed = welch_average(&runs, window);
+    let level = smoothed.iter().sum::<f64>() / smoothed.len() as f64;
+    if level < MIN_PER_PERIOD {
+        return Ok(None);
+    }
+    Ok(suggest_warmup(&smoothed, 0.1).map(|periods| periods as u32 * PERIOD))
+}
+
+fn bucket_display_name(app: &App, bucket_id: u32) -> String {
+    if let Some(list) = app.machine_buckets.get(&bucket_id) {
+        if let Some(first) = list.first() {
+            if let Some(machine) = app.production.machines.get(*first as usize) {
+                let base = if machine.machine.name.trim().is_empty() {
+                    format!("Bucket {}", bucket_id)
+                } else {
+                    machine.machine.name.clone()
+                };
+                if list.len() > 1 {
+                    return format!("{} (x{})", base, list.len());
+                }
+                return base;
+            }
+        }
+    }
+    format!("Bucket {}", bucket_id)
+}
+
+fn draw_process_queues(f: &mut ratatui::Frame, area: Rect, app: &App) {
+    if app.steps.is_empty() {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Process Queues");
+        let para = Paragraph::new(Line::from("No process steps defined"))
+            .block(block)
+            .wrap(Wrap { trim: true });
+        f.render_widget(para, area);
+        return;
+    }
+
+    let card_height: u16 = 5;
+    let visible_slots = std::cmp::max(1, (area.height / card_height) as usize);
+    let max_scroll = app.steps.len().saturating_sub(visible_slots);
+    let start = std::cmp::min(app.process_scroll, max_scroll);
+    let end = std::cmp::min(app.steps.len(), start + visible_slots);
+    let slice = &app.steps[start..end];
+
+    let constraints: Vec<Constraint> = slice.iter().map(|_| Constraint::Length(card_height)).collect();
+    let areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+
+    for (idx, step) in slice.iter().enumerate() {
+        let bucket = step.machine_id;
+        let name = bucket_display_name(app, bucket);
+        let queue_len = app.job_queues.get(&bucket).map(|q| q.len()).unwrap_or(0);
+        let busy_machines = app
+            .machine_buckets
+            .get(&bucket)
+            .map(|ids| {
+                ids.iter()
+                    .filter(|&&id| app.production.machines.get(id as usize).map(|m| m.is_operating).unwrap_or(false))
+                    .count()
+            })
+            .unwrap_or(0);
+        let bucket_size = app
+            .machine_buckets
+            .get(&bucket)
+            .map(|list| list.len())
+            .unwrap_or(1);
+
+        let process = &app.step_processes[start + idx];
+        let mut duration_line = format!("Duration: {} mins", step.duration);
+        if process.time_per_unit > 0 {
+            duration_line.push_str(&format!(" + {}/unit", process.time_per_unit));
+        }
+        for bucket_id in process.secondary_buckets() {
+            duration_line.push_str(&format!(" | + {}", bucket_display_name(app, bucket_id)));
+        }
+        if let Some(Some(_)) = app.yield_policies.get(start + idx) {
+            let stats = &app.quality[start + idx];
+            let fpy = stats
+                .first_pass_yield()
+                .map(|y| format!("{:.1}%", y * 100.0))
+                .unwrap_or_else(|| "-".to_string());
+            duration_line.push_str(&format!(
+                " | FPY: {} | Scrap: {} | Rework: {}",
+                fpy, stats.scrapped, stats.reworked
+            ));
+        }
+
+        let mut machines_line = format!("Machines busy: {} / {}", busy_machines, bucket_size);
+        if let Some(policy) = app.batch_policies.get(&bucket) {
+            machines_line.push_str(&format!(" | Batch {}-{}", policy.min_size, policy.max_size));
+        }
+
+        let inbound = app.transit.values().filter(|(trip, _)| trip.to == bucket).count()
+            + app.transport_waiting.iter().filter(|m| m.to == bucket).count();
+        let mut queue_line = format!("Queue: {}", queue_len);
+        if inbound > 0 {
+            queue_line.push_str(&format!(" | Inbound: {}", inbound));
+        }
+
+        let text = vec![
+            Line::from(queue_line),
+            Line::from(machines_line),
+            Line::from(duration_line),
+        ];
+        let bottleneck = Some(bucket) == app.bottlenecks.current(app.production.simulator.elapsed_time());
+        let mut title = step_title(app, &name, start + idx);
+        if bottleneck {
+            title.push_str(" - bottleneck");
+        }
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .style(Style::default().fg(if bottleneck { Color::Red } else { Color::White }));
+        let para = Paragraph::new(text)
+            .style(Style::default().fg(Color::White))
+            .block(block);
+        f.render_widget(para, areas[idx]);
+    }
+}
+
+/// Card title for a step, numbered within its line when the plant has several
+fn step_title(app: &App, name: &str, step: usize) -> String {
+    match app.plant.line_of_step(step).map(|idx| &app.plant.lines[idx]) {
+        Some(line) if app.plant.lines.len() > 1 => {
+            format!("{}: {} (step {})", line.name, name, step - line.steps.start + 1)
+        }
+        _ => format!("{} (step {})", name, step + 1),
+    }
+}
+
+const STATUS_TABS: [&str; 4] = ["Machines", "Staff", "Resources", "OEE"];
+
+fn draw_status_tabs(f: &mut ratatui::Frame, area: Rect, app: &App) {
+    let tabs_area = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+        .split(area);
+
+    let titles: Vec<Line> = STATUS_TABS.iter().map(|t| Line::from(*t)).collect();
+    let tabs = Tabs::new(titles)
+        .select(app.status_tab)
+        .block(Block::default().borders(Borders::ALL).title("Status"))
+        .highlight_style(Style::default().fg(Color::Yellow));
+    f.render_widget(tabs, tabs_area[0]);
+
+    match app.status_tab {
+        0 => {
+            let mut machine_lines = Vec::new();
+            for machine in &app.production.machines {
+                let waiting = match &machine.waiting_for {
+                    Some(reason) if !machine.is_operating || machine.down => reason.to_string(),
+                    _ => "-".to_string(),
+                };
+                let name = app
+                    .machine_to_bucket
+                    .get(&machine.machine.id)
+                    .map(|b| bucket_display_name(app, *b))
+                    .unwrap_or_else(|| format!("Machine {}", machine.machine.id));
+                machine_lines.push(Line::from(format!(
+                    "{} (ID {}): {} | Waiting: {} | {}",
+                    name,
+                    machine.machine.id,
+                    machine.status_times.current.label(),
+                    waiting,
+                    status_breakdown(&machine.status_times)
+                )));
+            }
+            let para = Paragraph::new(machine_lines)
+                .block(Block::default().borders(Borders::ALL))
+                .wrap(Wrap { trim: true });
+            f.render_widget(para, tabs_area[1]);
+        }
+        2 => {
+            let elapsed = observed_minutes(app);
+            let mut resource_lines = Vec::new();
+            for pool in &app.resources.pools {
+                resource_lines.push(Line::from(format!(
+                    "{} (ID {}): {} / {} in use | Peak: {} | Util: {:.1}% | Waits: {}",
+                    pool.name,
+                    pool.id,
+                    pool.in_use,
+                    pool.capacity,
+                    pool.peak_in_use,
+                    pool.utilization(elapsed) * 100.0,
+                    pool.wait_count
+                )));
+            }
+            for material in &app.materials.materials {
+                resource_lines.push(Line::from(format!(
+                    "{} (material {}): {} on hand | {} on order | Used: {} | Waits: {}",
+                    material.name,
+                    material.id,
+                    material.on_hand,
+                    material.on_order(),
+                    material.consumed,
+                    material.wait_count
+                )));
+            }
+            if resource_lines.is_empty() {
+                resource_lines.push(Line::from("No resource pools or materials defined"));
+            }
+            let para = Paragraph::new(resource_lines)
+                .block(Block::default().borders(Borders::ALL))
+                .wrap(Wrap { trim: true });
+            f.render_widget(para, tabs_area[1]);
+        }
+        3 => {
+            let mut oee_lines = Vec::new();
+            let mut buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
+            buckets.sort_unstable();
+            for bucket_id in buckets {
+                oee_lines.push(Line::from(format!(
+                    "{}: {}",
+                    bucket_display_name(app, bucket_id),
+                    oee_breakdown(bucket_oee(app, bucket_id))
+                )));
+                let machine_ids = &app.machine_buckets[&bucket_id];
+                if machine_ids.len() > 1 {
+                    for &machine_id in machine_ids {
+                        oee_lines.push(Line::from(format!(
+                            "  #{}: {}",
+                            machine_id,
+                            oee_breakdown(machine_oee(app, machine_id))
+                        )));
+                    }
+                }
+            }
+            let para = Paragraph::new(oee_lines)
+                .block(Block::default().borders(Borders::ALL))
+                .wrap(Wrap { trim: true });
+            f.render_widget(para, tabs_area[1]);
+        }
+        _ => {
+            let now = app.production.simulator.elapsed_time();
+            let mut staff_lines = Vec::new();
+            for staff in &app.production.staff {
+                let status = if staff.on_break {
+                    "On break"
+                } else if !staff.is_available && staff.walking_until > now {
+                    "Walking"
+                } else if staff.is_available {
+                    "Available"
+                } else {
+                    "Busy"
+                };
+                let waiting = if staff.on_break {
+                    format!("Back at {}", staff.available_at)
+                } else if staff.is_available {
+                    "Assignment".to_string()
+                } else {
+                    staff
+                        .current_machine
+                        .map(|m| format!("Machine {}", m))
+                        .unwrap_or_else(|| "Task".to_string())
+                };
+                staff_lines.push(Line::from(format!(
+                    "{} - {}: {} | Waiting: {} | {}",
+                    staff.id,
+                    staff.name,
+                    status,
+                    waiting,
+                    staff_breakdown(app, &[staff])
+                )));
+            }
+            let para = Paragraph::new(staff_lines)
+                .block(Block::default().borders(Borders::ALL))
+                .wrap(Wrap { trim: true });
+            f.render_widget(para, tabs_area[1]);
+        }
+    }
+}
+fn draw_ui(f: &mut ratatui::Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+        .split(f.size());
+
+    let left = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(17), Constraint::Min(0)].as_ref())
+        .split(chunks[0]);
+
+    draw_metrics(f, left[0], app);
+    draw_status_tabs(f, left[1], app);
+    draw_process_queues(f, chunks[1], app);
+}
+
+fn draw_metrics(f: &mut ratatui::Frame, area: Rect, app: &App) {
+    let elapsed = app.production.simulator.elapsed_time();
+    let operating = app
+        .production
+        .machines
+        .iter()
+        .filter(|m| m.is_operating)
+        .count();
+    let total_idle: u32 = app.production.staff.iter().map(|s| s.idle_time).sum();
+    let playing_text = if app.playing { "Playing" } else { "Paused" };
+    let wip = wip_counts(app);
+
+    let lines = vec![
+        Line::from(app.title.clone()),
+        Line::from(format!("Mode: {}", playing_text)),
+        Line::from(format!("Elapsed: {} mins", elapsed)),
+        Line::from(format!(
+            "Machines: {} total | {} active",
+            app.production.machines.len(),
+            operating
+        )),
+        Line::from(format!("Staff: {}", app.production.staff.len())),
+        Line::from(format!("Total idle mins: {}", total_idle)),
+        Line::from(format!(
+            "Finished goods: {} | Scrapped: {}",
+            app.finished_goods, app.scrapped
+        )),
+        Line::from(format!(
+            "WIP: {} queued | {} in process | {} in transit",
+            wip.queued, wip.in_process, wip.in_transit
+        )),
+        Line::from(match app.plant_load.last() {
+            Some(&(_, kw)) => format!(
+                "Plant load: {:.1} kW | {:.1} kWh used",
+                kw,
+                app.energy.values().map(|m| m.kwh()).sum::<f64>()
+            ),
+            None => "Plant load: n/a".to_string(),
+        }),
+        Line::from("Controls:"),
+        Line::from("  space - play/pause"),
+        Line::from("  n     - step once"),
+        Line::from("  up/down - scroll process list"),
+        Line::from("  tab   - switch status tab"),
+        Line::from("  q     - quit"),
+    ];
+
+    let metrics = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Metrics"))
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(metrics, area);
+}
+
+
+/// Item waiting at `from` for a transporter or belt space
+struct WaitingMove {
+    from: u32,
+    to: u32,
+    job: PendingJob,
+    since: u32,
+}
+
+#[derive(Clone)]
+struct PendingJob {
+    duration: u32,
+    step_index: usize,
+    /// Items moving together (one transfer batch of a lot)
+    items: Vec<u32>,
+    lot_id: u32,
+    /// Carries the step's per-lot time (the lot's first transfer batch at this step)
+    setup: bool,
+    /// Rework operation for `step_index`; the items repeat that step afterwards
+    rework: bool,
+    /// When the job entered its bucket queue
+    queued_at: u32,
+}
+
+impl PendingJob {
+    fn units(&self) -> u32 {
+        self.items.len() as u32
+    }
+}
+
+fn try_start_jobs(app: &mut App, bucket_id: u32, current_time: u32) {
+    if app.batch_policies.contains_key(&bucket_id) {
+        try_start_batches(app, bucket_id, current_time);
+        return;
+    }
+
+    let Some(machine_ids) = app.machine_buckets.get(&bucket_id).cloned() else { return };
+
+    // Try to start as many queued jobs as there are free machines and staff
+    loop {
+        let Some(queue) = app.job_queues.get(&bucket_id) else { return };
+        if queue.is_empty() {
+            return;
+        }
+        // pick the job furthest along in the process (highest step_index)
+        let best_idx = queue
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, job)| (job.step_index, std::cmp::Reverse(job.items.first().copied().unwrap_or(0))))
+            .map(|(idx, _)| idx)
+            .unwrap();
+
+        // find an idle machine in this bucket
+        let Some(&machine_id) = machine_ids
+            .iter()
+            .find(|&&m_id| machine_free(app, m_id))
+        else {
+            break; // no idle machines
+        };
+
+        // Fixtures etc. are checked before staff so nothing is held while waiting
+        let needs = job_resources(&app.step_resources, &queue[best_idx]);
+        if let Some(short) = app.resources.first_shortage(&needs) {
+            mark_resource_wait(&mut app.resources, app.production.machines.get_mut(machine_id as usize), short);
+            break;
+        }
+        let materials = job_materials(&app.step_materials, &queue[best_idx], queue[best_idx].units());
+        if let Some(short) = app.materials.first_shortage(&materials) {
+            mark_material_wait(&mut app.materials, app.production.machines.get_mut(machine_id as usize), short);
+            break;
+        }
+        let seized = match secondary_machines(app, &queue[best_idx], machine_id) {
+            Ok(seized) => seized,
+            Err(bucket) => {
+                mark_machine_wait(app, machine_id, bucket);
+                break;
+            }
+        };
+
+        let job = queue[best_idx].clone();
+        let pid = app.next_pid;
+        app.next_pid += 1;
+
+        let phases = job_phases(app, &job, job.duration, machine_id, current_time);
+        if start_process_phases(app, machine_id, pid, phases, current_time) {
+            if let Some(queue) = app.job_queues.get_mut(&bucket_id) {
+                queue.remove(best_idx);
+            }
+            if !needs.is_empty() {
+                app.resources.try_acquire(&needs, current_time);
+                app.resource_holds.insert(pid, needs);
+            }
+            consume_materials(app, &materials, current_time);
+            seize_machines(app, pid, seized, current_time);
+            note_release(app, &job, current_time);
+            app.process_meta.insert(pid, vec![job]);
+        } else {
+            // Could not start (likely staff unavailable) — mark machine as waiting for staff
+            if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+                machine.waiting_for = Some(WaitReason::Staff(None));
+            }
+            break;
+        }
+    }
+}
+
+/// Note when work last entered a line; once every line's input has stopped, the plant only drains
+fn note_release(app: &mut App, job: &PendingJob, current_time: u32) {
+    if !job.rework && app.plant.lines.iter().any(|line| line.steps.start == job.step_index) {
+        app.last_release = current_time;
+    }
+}
+
+/// Resource units a job holds while it runs (rework uses none)
+fn job_resources(step_resources: &[Vec<ResourceNeed>], job: &PendingJob) -> Vec<ResourceNeed> {
+    if job.rework {
+        return Vec::new();
+    }
+    step_resources.get(job.step_index).cloned().unwrap_or_default()
+}
+
+/// Mark a machine as waiting for a resource pool, counting each new wait once
+fn mark_resource_wait(resources: &mut ResourcePools, machine: Option<&mut MachineState>, resource_id: u32) {
+    let name = resources
+        .get(resource_id)
+        .map(|pool| pool.name.clone())
+        .unwrap_or_else(|| format!("Resource {}", resource_id));
+    let reason = WaitReason::Resource(name);
+    if let Some(machine) = machine {
+        if machine.waiting_for.as_ref() != Some(&reason) {
+            resources.note_wait(resource_id);
+            machine.waiting_for = Some(reason);
+        }
+    }
+}
+
+/// Material a job takes from stock for `items` items (rework uses none)
+fn job_materials(step_materials: &[Vec<MaterialNeed>], job: &PendingJob, items: u32) -> Vec<MaterialNeed> {
+    if job.rework {
+        return Vec::new();
+    }
+    step_materials
+        .get(job.step_index)
+        .map(|needs| {
+            needs
+                .iter()
+                .map(|need| MaterialNeed {
+                    material_id: need.material_id,
+                    quantity: need.quantity * items,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Mark a machine as waiting for a material, counting each new wait once
+fn mark_material_wait(materials: &mut Stockroom, machine: Option<&mut MachineState>, material_id: u32) {
+    let name = materials
+        .get(material_id)
+        .map(|material| material.name.clone())
+        .unwrap_or_else(|| format!("Material {}", material_id));
+    let reason = WaitReason::Material(name);
+    if let Some(machine) = machine {
+        if machine.waiting_for.as_ref() != Some(&reason) {
+            materials.note_wait(material_id);
+            machine.waiting_for = Some(reason);
+        }
+    }
+}
+
+/// Take a started job's material from stock and reorder what fell low
+fn consume_materials(app: &mut App, needs: &[MaterialNeed], current_time: u32) {
+    app.materials.try_consume(needs, current_time);
+    for need in needs {
+        reorder_material(app, need.material_id, current_time);
+    }
+}
+
+fn reorder_material(app: &mut App, material_id: u32, current_time: u32) {
+    let Some(material) = app.materials.get_mut(material_id) else { return };
+    if let Some(arrives_at) = material.replenish(current_time) {
+        app.production.simulator.schedule_event(
+            SimulationTime::new(arrives_at),
+            EventType::MaterialArrival { material_id },
+        );
+    }
+}
+
+/// Per-unit part of a job's time
+fn unit_time(app: &App, job: &PendingJob) -> u32 {
+    if job.rework {
+        return 0;
+    }
+    app.step_processes[job.step_index].time_per_unit * job.units()
+}
+
+/// A load runs as long as its longest fixed part plus every job's per-unit time
+fn load_duration(app: &App, jobs: &[&PendingJob]) -> u32 {
+    let fixed = jobs
+        .iter()
+        .map(|job| job.duration.saturating_sub(unit_time(app, job)))
+        .max()
+        .unwrap_or(0);
+    fixed + jobs.iter().map(|job| unit_time(app, job)).sum::<u32>()
+}
+
+/// A free machine from every other bucket the job's step needs,
+/// or the first bucket with none free
+fn secondary_machines(app: &App, job: &PendingJob, machine_id: u32) -> Result<Vec<u32>, u32> {
+    if job.rework {
+        return Ok(Vec::new());
+    }
+    let mut seized = Vec::new();
+    for bucket_id in app.step_processes[job.step_index].secondary_buckets() {
+        let free = app.machine_buckets.get(&bucket_id).and_then(|ids| {
+            ids.iter()
+                .copied()
+                .find(|&id| id != machine_id && !seized.contains(&id) && machine_free(app, id))
+        });
+        match free {
+            Some(id) => seized.push(id),
+            None => return Err(bucket_id),
+        }
+    }
+    Ok(seized)
+}
+
+fn mark_machine_wait(app: &mut App, machine_id: u32, bucket_id: u32) {
+    let reason = WaitReason::Machine(bucket_display_name(app, bucket_id));
+    if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+        machine.waiting_for = Some(reason);
+    }
+}
+
+/// Hold extra machines for a process; they count as busy until it completes
+fn seize_machines(app: &mut App, process_id: u32, machine_ids: Vec<u32>, current_time: u32) {
+    if machine_ids.is_empty() {
+        return;
+    }
+    for &machine_id in &machine_ids {
+        if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+            machine.idle_time += current_time.saturating_sub(machine.last_status_change);
+            machine.last_status_change = current_time;
+            machine.is_operating = true;
+            machine.waiting_for = None;
+        }
+    }
+    app.machine_holds.insert(process_id, machine_ids);
+}
+
+/// Give back machines seized for a process; true if there were any
+fn release_machines(app: &mut App, process_id: u32, current_time: u32) -> bool {
+    let Some(machine_ids) = app.machine_holds.remove(&process_id) else { return false };
+    for machine_id in machine_ids {
+        if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+            machine.is_operating = false;
+            machine.last_status_change = current_time;
+            machine.waiting_for = Some(WaitReason::NextJob);
+        }
+    }
+    true
+}
+
+/// Machine held by another machine's process
+fn machine_seized(app: &App, machine_id: u32) -> bool {
+    app.machine_holds.values().any(|ids| ids.contains(&machine_id))
+}
+
+/// Recipe key used to decide which jobs can share a batch load
+fn batch_recipe(app: &App, job: &PendingJob) -> String {
+    if job.rework {
+        return format!("rework:{}", job.step_index);
+    }
+    app.steps
+        .get(job.step_index)
+        .and_then(|step| step.recipe.clone())
+        .unwrap_or_else(|| format!("step:{}", job.step_index))
+}
+
+/// Start loads on idle batch machines in a bucket
+fn try_start_batches(app: &mut App, bucket_id: u32, current_time: u32) {
+    let Some(policy) = app.batch_policies.get(&bucket_id).cloned() else { return };
+    let Some(machine_ids) = app.machine_buckets.get(&bucket_id).cloned() else { return };
+
+    loop {
+        let queue_len = app.job_queues.get(&bucket_id).map(|q| q.len()).unwrap_or(0);
+        if queue_len == 0 {
+            return;
+        }
+        let Some(machine_id) = machine_ids
+            .iter()
+            .copied()
+            .find(|&m_id| machine_free(app, m_id))
+        else {
+            return; // no idle machines
+        };
+
+        // Once every remaining item is waiting here, nothing more can arrive: run short loads
+        let in_system = app.items.saturating_sub(items_done(app)) as usize;
+        let queued_units: usize = app.job_queues[&bucket_id].iter().map(|job| job.units() as usize).sum();
+        let flush = queued_units >= in_system;
+
+        let queue = &app.job_queues[&bucket_id];
+        let recipes: Vec<String> = queue.iter().map(|job| batch_recipe(app, job)).collect();
+        let candidates: Vec<BatchCandidate> = queue
+            .iter()
+            .zip(&recipes)
+            .map(|(job, recipe)| BatchCandidate {
+                recipe,
+                queued_at: job.queued_at,
+                units: job.units() as usize,
+            })
+            .collect();
+
+        let picked = match policy.decide(&candidates, current_time, flush) {
+            BatchDecision::Start(picked) => picked,
+            BatchDecision::WaitUntil(time) => {
+                if app.batch_wakeups.get(&bucket_id) != Some(&time) {
+                    app.batch_wakeups.insert(bucket_id, time);
+                    app.production
+                        .simulator
+                        .schedule_event(SimulationTime::new(time), EventType::BatchTimeout { bucket_id });
+                }
+                set_batch_waiting(app, machine_id, queued_units, policy.max_size);
+                return;
+            }
+            BatchDecision::Wait => {
+                set_batch_waiting(app, machine_id, queued_units, policy.max_size);
+                return;
+            }
+        };
+
+        let jobs: Vec<&PendingJob> = picked.iter().map(|&idx| &app.job_queues[&bucket_id][idx]).collect();
+        let duration = load_duration(app, &jobs);
+        // A load takes the step's resources once, like a single job
+        let needs = job_resources(&app.step_resources, &app.job_queues[&bucket_id][picked[0]]);
+        if let Some(short) = app.resources.first_shortage(&needs) {
+            mark_resource_wait(&mut app.resources, app.production.machines.get_mut(machine_id as usize), short);
+            return;
+        }
+        // Material is taken per item in the load
+        let units = jobs.iter().map(|job| job.units()).sum();
+        let materials = job_materials(&app.step_materials, &app.job_queues[&bucket_id][picked[0]], units);
+        if let Some(short) = app.materials.first_shortage(&materials) {
+            mark_material_wait(&mut app.materials, app.production.machines.get_mut(machine_id as usize), short);
+            return;
+        }
+        let seized = match secondary_machines(app, &app.job_queues[&bucket_id][picked[0]], machine_id) {
+            Ok(seized) => seized,
+            Err(bucket) => {
+                mark_machine_wait(app, machine_id, bucket);
+                return;
+            }
+        };
+        let pid = app.next_pid;
+        app.next_pid += 1;
+
+        let phases = job_phases(app, &app.job_queues[&bucket_id][picked[0]], duration, machine_id, current_time);
+        if !start_process_phases(app, machine_id, pid, phases, current_time) {
+            if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+                machine.waiting_for = Some(WaitReason::Staff(None));
+            }
+            return;
+        }
+        if !needs.is_empty() {
+            app.resources.try_acquire(&needs, current_time);
+            app.resource_holds.insert(pid, needs);
+        }
+        consume_materials(app, &materials, current_time);
+        seize_machines(app, pid, seized, current_time);
+
+        let queue = app.job_queues.get_mut(&bucket_id).unwrap();
+        let mut sorted = picked;
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+        let mut load: Vec<PendingJob> = sorted.into_iter().map(|idx| queue.remove(idx)).collect();
+        load.reverse();
+        if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+            machine.waiting_for = None;
+        }
+        for job in &load {
+            note_release(app, job, current_time);
+        }
+        app.process_meta.insert(pid, load);
+    }
+}
+
+fn set_batch_waiting(app: &mut App, machine_id: u32, waiting: usize, max_size: usize) {
+    if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
+        machine.waiting_for = Some(WaitReason::BatchFill { waiting, max_size });
+    }
+}
+
+fn run_examples(logger: &Logger) {
+    logger.debug("Application started");
+    logger.info("System initialized");
+
+    // ============================================================
+    // Example 1: Simple simulation without staff
+    // ============================================================
+    logger.info("\n=== Example 1: Event Scheduling (without staff) ===");
+    {
+        let mut sim = Simulator::new();
+
+        sim.schedule_event(
+            SimulationTime::new(10),
+            EventType::ProcessStart {
+                machine_id: 0,
+                process_id: 1,
+            },
+        );
+        sim.schedule_event(
+            SimulationTime::new(25),
+            EventType::ProcessComplete {
+                machine_id: 0,
+                process_id: 1,
+            },
+        );
+        sim.schedule_event(
+            SimulationTime::new(5),
+            EventType::MaterialArrival { material_id: 1 },
+        );
+
+        logger.info("Events scheduled, starting simulation...");
+        sim.run_all(|sim, event| {
+            logger.info(&format!("Time {}: {:?}", sim.elapsed_time(), event.event_type));
+        });
+    }
+
+    // ============================================================
+    // Example 2: Production with staff scheduling
+    // ============================================================
+    logger.info("\n=== Example 2: Staff Scheduling ===");
+    {
+        let mut prod = ProductionSimulator::new();
+
+        // Create machines
+        logger.info("Setting up production line...");
+        let cnc_machine = MachineType::new(0, "CNC Machine", 1); // Needs 1 staff
+        let assembly = MachineType::new(1, "Assembly Station", 2); // Needs 2 staff
+        let conveyor = MachineType::automated(2, "Conveyor Belt"); // Automated, no staff
+
+        prod.add_machine(cnc_machine);
+        prod.add_machine(assembly);
+        prod.add_machine(conveyor);
+
+        // Create staff
+        logger.info("Hiring staff...");
+
+        // General operator (can work anywhere)
+        let operator_role = Role::new(0, "General Operator");
+        let john = Staff::new(0, "John", operator_role.clone());
+        prod.add_staff(john);
+
+        // CNC specialist (can only work on CNC)
+        let cnc_specialist_role = Role::specialist(1, "CNC Specialist", vec![0]);
+        let jane = Staff::new(1, "Jane", cnc_specialist_role);
+        prod.add_staff(jane);
+
+        // Assembly workers
+        let assembler_role = Role::new(2, "Assembler");
+        let bob = Staff::new(2, "Bob", assembler_role.clone());
+        let alice = Staff::new(3, "Alice", assembler_role);
+        prod.add_staff(bob);
+        prod.add_staff(alice);
+
+        logger.info(&prod.get_status());
+
+        // ============================================================
+        // Schedule production: Item 0 through CNC -> Assembly -> Conveyor
+        // ============================================================
+        logger.info("\n--- Scheduling Item 0 ---");
+
+        // Item 0: CNC (needs Jane)
+        let success = prod.try_start_process(0, 0, 15, 0);
+        if success {
+            logger.info("Item 0: CNC process started (Jane assigned)");
+        } else {
+            logger.warning("Item 0: CNC process failed - staff unavailable");
+        }
+
+        // After CNC completes (time 15), try assembly
+        // But we need to manually release Jane and assign Bob+Alice
+        prod.staff[1].release_from_machine(15); // Release Jane
+
+        // ============================================================
+        // Schedule production: Item 1 - parallel processing
+        // ============================================================
+        logger.info("\n--- Scheduling Item 1 (Parallel) ---");
+
+        // Item 1 on CNC at time 15 (Jane just became available)
+        let success = prod.try_start_process(0, 1, 15, 15);
+        if success {
+            logger.info("Item 1: CNC process started at time 15 (Jane assigned)");
+        }
+
+        // Item 0 on Assembly at time 15 (needs Bob and Alice)
+        let success = prod.try_start_process(1, 0, 20, 15);
+        if success {
+            logger.info("Item 0: Assembly started at time 15 (Bob & Alice assigned)");
+        } else {
+            logger.warning("Item 0: Assembly failed - staff unavailable");
+        }
+
+        // Run the simulation
+        logger.info("\n--- Running Simulation ---");
+        prod.simulator.run_all(|sim, event| match &event.event_type {
+            EventType::ProcessStart {
+                machine_id,
+                process_id,
+            } => {
+                logger.info(&format!(
+                    "Time {}: Process {} started on machine {}",
+                    sim.elapsed_time(),
+                    process_id,
+                    machine_id
+                ));
+            }
+            EventType::ProcessComplete {
+                machine_id,
+                process_id,
+            } => {
+                logger.info(&format!(
+                    "Time {}: Process {} completed on machine {}",
+                    sim.elapsed_time(),
+                    process_id,
+                    machine_id
+                ));
+            }
+            EventType::StaffAssigned {
+                staff_id,
+                machine_id,
+                ..
+            } => {
+                logger.info(&format!(
+                    "Time {}: Staff {} assigned to machine {}",
+                    sim.elapsed_time(),
+                    staff_id,
+                    machine_id
+                ));
+            }
+            EventType::StaffReleased {
+                staff_id,
+                machine_id,
+            } => {
+                logger.info(&format!(
+                    "Time {}: Staff {} released from machine {}",
+                    sim.elapsed_time(),
+                    staff_id,
+                    machine_id
+                ));
+            }
+            EventType::StaffUnavailable {
+                machine_id,
+                process_id,
+            } => {
+                logger.warning(&format!(
+                    "Time {}: Process {} DELAYED - no staff available for machine {}",
+                    sim.elapsed_time(),
+                    process_id,
+                    machine_id
+                ));
+            }
+            _ => {
+                logger.debug(&format!(
+                    "Time {}: {:?}",
+                    sim.elapsed_time(),
+                    event.event_type
+                ));
+            }
+        });
+
+        prod.finalize_idle_time(prod.simulator.elapsed_time());
+        logger.info("\n--- Final Status ---");
+        logger.info(&prod.get_status());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    #[test]
+    fn parse_args_supports_flags_and_positionals() {
+        let args = vec![
+            "assemblysim".to_string(),
+            "--config".to_string(),
+            "path/a.json".to_string(),
+        ];
+        assert_eq!(parse_args(&args).config, Some("path/a.json".to_string()));
+
+        let args = vec![
+            "assemblysim".to_string(),
+            "-c".to_string(),
+            "path/b.json".to_string(),
+        ];
+        assert_eq!(parse_args(&args).config, Some("path/b.json".to_string()));
+
+        let args = vec!["assemblysim".to_string(), "path/c.json".to_string()];
+        assert_eq!(parse_args(&args).config, Some("path/c.json".to_string()));
+
+        let args = vec!["assemblysim".to_string()];
+        assert_eq!(parse_args(&args).config, None);
+
+        let args: Vec<String> = ["assemblysim", "--report", "out.html", "path/d.json"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            parse_args(&args),
+            CliArgs {
+                config: Some("path/d.json".to_string()),
+                report: Some("out.html".to_string()),
+                suggest_warmup: false,
+            }
+        );
+
+        let args = vec!["assemblysim".to_string(), "--suggest-warmup".to_string(), "path/e.json".to_string()];
+        assert!(parse_args(&args).suggest_warmup);
+    }
+
+    #[test]
+    fn load_simulation_from_config_builds_production_state() {
+        let logger = Logger::new(LogLevel::Error);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("assemblysim_test_{}.json", timestamp));
+
+        let config = serde_json::json!({
+            "machines": [{
+                "id": 0,
+                "name": "Cutter",
+                "staff_required": 1,
+                "count": 2
+            }],
+            "staff": [{
+                "id": 0,
+                "name": "Alex",
+                "role": {
+                    "id": 0,
+                    "name": "Operator",
+                    "machine_ids": []
                 }
-            }
-            app.last_tick = Instant::now();
-        }
+            }],
+            "processes": [{
+                "machine_id": 0,
+                "process_id": 5,
+                "duration": 12
+            }],
+            "items": 3
+        });
+
+        std::fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+
+        let loaded = load_simulation_from_config(path.to_str().unwrap(), &logger).unwrap();
+        assert_eq!(loaded.production.machines.len(), 2);
+        assert_eq!(loaded.machine_buckets.get(&0).unwrap().len(), 2);
+        assert_eq!(loaded.machine_to_bucket.get(&0), Some(&0));
+        assert_eq!(loaded.machine_to_bucket.get(&1), Some(&0));
+        assert_eq!(loaded.production.staff.len(), 1);
+        assert_eq!(loaded.steps.len(), 1);
+        assert_eq!(loaded.items, 3);
+
+        let _ = std::fs::remove_file(path);
     }
-}
 
-fn step_simulation(app: &mut App) {
-    let current = app.production.simulator.elapsed_time();
-    let mut target_time = current + 10;
-    if let Some(next_event) = app.production.simulator.peek_next_event() {
-        let next_time = next_event.time.as_minutes();
-        if next_time > current && next_time < target_time {
-            target_time = next_time;
-        }
+    /// Build a simulation straight from a JSON config, without a file
+    fn load_json(config: &serde_json::Value) -> Result<LoadedSim, Box<dyn std::error::Error>> {
+        load_simulation(serde_json::from_value(config.clone())?)
     }
 
-    // Rebalance stuck staff/machines before progressing time
-    rebalance(app, target_time);
+    fn write_temp_config(tag: &str, config: &serde_json::Value) -> std::path::PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("assemblysim_{}_{}.json", tag, timestamp));
+        std::fs::write(&path, serde_json::to_string(config).unwrap()).unwrap();
+        path
+    }
 
-    // Process all events due up to target_time
-    loop {
-        if let Some(event) = app.production.simulator.peek_next_event() {
-            if event.time.as_minutes() <= target_time {
-                let evt = app.production.simulator.step().unwrap();
-                handle_event(app, evt);
-                continue;
+    fn run_to_completion(app: &mut App) {
+        for _ in 0..10_000 {
+            if sim_complete(app) {
+                return;
             }
+            step_simulation(app);
         }
-        break;
+        panic!("simulation did not complete");
     }
 
-    // Advance clock to target_time if nothing else happened
-    if app.production.simulator.elapsed_time() < target_time {
-        app.production
-            .simulator
-            .set_time(SimulationTime::new(target_time));
+    fn quality_config(processes: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "items": 4,
+            "machines": [
+                { "id": 0, "name": "Build", "is_automated": true },
+                { "id": 1, "name": "Inspect", "is_automated": true },
+                { "id": 2, "name": "Repair", "is_automated": true }
+            ],
+            "staff": [],
+            "processes": processes
+        })
     }
 
-    // Rebalance again after time advancement
-    rebalance(app, target_time);
+    #[test]
+    fn yield_config_builds_failure_actions() {
+        let config = quality_config(serde_json::json!([
+            { "machine_id": 0, "duration": 5, "yield": 0.9 },
+            { "machine_id": 1, "duration": 5, "yield": 0.8, "on_fail": { "rework": { "machine_id": 2, "duration": 7 } }, "max_reworks": 3 },
+            { "machine_id": 1, "duration": 5, "yield": 0.7, "on_fail": { "return_to": 0 } },
+            { "machine_id": 1, "duration": 5 }
+        ]));
 
-    // Continuously attempt to start queued jobs on all buckets
-    let buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
-    for bucket in buckets {
-        try_start_jobs(app, bucket, target_time);
+        let loaded = load_json(&config).unwrap();
+        let policies = &loaded.yield_policies;
+        assert_eq!(policies[0].as_ref().unwrap().on_fail, FailureAction::Scrap);
+        assert_eq!(
+            policies[1].as_ref().unwrap().on_fail,
+            FailureAction::Rework { bucket_id: 2, duration: 7 }
+        );
+        assert_eq!(policies[1].as_ref().unwrap().max_reworks, 3);
+        assert_eq!(
+            policies[2].as_ref().unwrap().on_fail,
+            FailureAction::ReturnTo { step_index: 0 }
+        );
+        assert!(policies[3].is_none());
     }
 
-    app.production.finalize_idle_time(target_time);
-}
+    #[test]
+    fn empty_transporter_pool_is_rejected() {
+        let config = serde_json::json!({
+            "machines": [{ "id": 0, "name": "Weld", "is_automated": true }],
+            "staff": [],
+            "processes": [{ "machine_id": 0, "duration": 10 }],
+            "transport": { "transporters": { "count": 0 } }
+        });
 
-fn handle_event(app: &mut App, event: Event) {
-    let production = &mut app.production;
-    match event.event_type {
-        EventType::ProcessComplete {
-            machine_id,
-            process_id,
-        } => {
-            if let Some(machine) = production.machines.get_mut(machine_id as usize) {
-                // Immediately free any staff still marked on this machine
-                let current_time = event.time.as_minutes();
-                let releasing: Vec<u32> = machine.assigned_staff.clone();
-                for staff_id in releasing {
-                    if let Some(staff_member) = production.staff.iter_mut().find(|s| s.id == staff_id)
-                    {
-                        staff_member.release_from_machine(current_time);
-                    }
-                }
-                machine.is_operating = false;
-                machine.assigned_staff.clear();
-                machine.waiting_for = Some("Next process".to_string());
-            }
-            if let Some((step_idx, item_id)) = app.process_meta.remove(&process_id) {
-                let next_step = step_idx + 1;
-                if let Some(step) = app.steps.get(next_step) {
-                    let bucket = step.machine_id;
-                    let duration = step.duration;
-                    let queue = app.job_queues.entry(bucket).or_default();
-                    queue.push(PendingJob {
-                        duration,
-                        step_index: next_step,
-                        item_id,
-                    });
-                    try_start_jobs(app, bucket, event.time.as_minutes());
-                } else {
-                    // Finished goods
-                    app.finished_goods += 1;
-                    // After freeing staff, try to start waiting work anywhere
-                    let current_time = event.time.as_minutes();
-                    let buckets: Vec<u32> = app.machine_buckets.keys().cloned().collect();
-                    for bucket in buckets {
-                        try_start_jobs(app, bucket, current_time);
-                    }
-                }
-            }
-        }
-        EventType::StaffReleased {
-            staff_id,
-            machine_id,
-        } => {
-            if let Some(staff_member) = production.staff.iter_mut().find(|s| s.id == staff_id) {
-                staff_member.release_from_machine(production.simulator.elapsed_time());
-            }
-            if let Some(machine) = production.machines.get_mut(machine_id as usize) {
-                machine.assigned_staff.retain(|&id| id != staff_id);
-                if machine.assigned_staff.is_empty() {
-                    machine.is_operating = false;
-                    machine.waiting_for = Some("Next process".to_string());
-                }
-            }
+        let err = load_json(&config).err().unwrap();
+        assert!(err.to_string().contains("count of at least 1"));
+    }
 
-            if let Some(bucket) = app.machine_to_bucket.get(&machine_id).cloned() {
-                try_start_jobs(app, bucket, event.time.as_minutes());
-            }
-        }
-        EventType::StaffUnavailable { .. } => {
-            // Nothing to update in state, but could surface in UI later
-        }
-        _ => {}
+    #[test]
+    fn bad_conveyors_and_travel_times_are_rejected() {
+        let with_route = |route: serde_json::Value| {
+            serde_json::json!({
+                "machines": [
+                    { "id": 0, "name": "Cut", "is_automated": true },
+                    { "id": 1, "name": "Weld", "is_automated": true }
+                ],
+                "staff": [],
+                "processes": [{ "machine_id": 0, "duration": 5 }, { "machine_id": 1, "duration": 5 }],
+                "transport": { "routes": [route] }
+            })
+        };
+        let conveyor = |capacity: i64, length: f64, speed: f64| {
+            with_route(serde_json::json!({
+                "from": 0, "to": 1,
+                "conveyor": { "capacity": capacity, "length": length, "speed": speed }
+            }))
+        };
+        let timed = |time: serde_json::Value| with_route(serde_json::json!({ "from": 0, "to": 1, "time": time }));
+
+        assert!(load_json(&conveyor(2, 10.0, 5.0)).is_ok());
+        assert!(load_json(&conveyor(0, 10.0, 5.0)).is_err());
+        assert!(load_json(&conveyor(2, -10.0, 5.0)).is_err());
+        assert!(load_json(&conveyor(2, 10.0, 0.0)).is_err());
+
+        assert!(load_json(&timed(serde_json::json!({ "triangular": { "min": 2, "mode": 4, "max": 10 } }))).is_ok());
+        assert!(load_json(&timed(serde_json::json!({ "uniform": { "min": 9, "max": 3 } }))).is_err());
+        assert!(load_json(&timed(serde_json::json!({ "triangular": { "min": 2, "mode": 12, "max": 10 } }))).is_err());
+        assert!(load_json(&timed(serde_json::json!({ "exponential": { "mean": -5.0 } }))).is_err());
     }
-}
 
-fn rebalance(app: &mut App, current_time: u32) {
-    // Free staff whose availability time has passed or whose machine isn't running
-    for staff in &mut app.production.staff {
-        if !staff.is_available && current_time >= staff.available_at {
-            staff.release_from_machine(current_time);
-        }
-        if !staff.is_available {
-            if let Some(machine_id) = staff.current_machine {
-                let should_release = app
-                    .production
-                    .machines
-                    .get(machine_id as usize)
-                    .map(|m| !m.is_operating || !m.assigned_staff.contains(&staff.id))
-                    .unwrap_or(true);
-                if should_release {
-                    staff.release_from_machine(current_time);
-                }
-            }
-        }
+    #[test]
+    fn return_to_must_target_an_earlier_step() {
+        let config = quality_config(serde_json::json!([
+            { "machine_id": 0, "duration": 5, "yield": 0.9, "on_fail": { "return_to": 1 } },
+            { "machine_id": 1, "duration": 5 }
+        ]));
+
+        assert!(load_json(&config).is_err());
     }
 
-    // Clear any machines marked idle but still holding staff
-    for machine in &mut app.production.machines {
-        if !machine.is_operating && !machine.assigned_staff.is_empty() {
-            for staff_id in machine.assigned_staff.drain(..) {
-                if let Some(staff_member) = app.production.staff.iter_mut().find(|s| s.id == staff_id) {
-                    staff_member.release_from_machine(current_time);
-                }
-            }
-        }
+    #[test]
+    fn failing_items_are_reworked_then_scrapped() {
+        let config = quality_config(serde_json::json!([
+            { "machine_id": 0, "duration": 5 },
+            { "machine_id": 1, "duration": 5, "yield": 0.0, "on_fail": { "rework": { "machine_id": 2, "duration": 7 } }, "max_reworks": 2 }
+        ]));
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        assert_eq!(app.finished_goods, 0);
+        assert_eq!(app.scrapped, 4);
+        assert_eq!(app.quality[1].reworked, 8);
+        assert_eq!(app.quality[1].scrapped, 4);
+        assert_eq!(app.quality[1].first_attempts, 4);
+        assert_eq!(app.quality[1].first_pass_yield(), Some(0.0));
     }
-}
 
-fn sim_complete(app: &App) -> bool {
-    app.finished_goods >= app.items
-        || (app.job_queues.values().all(|q| q.is_empty())
-            && app
-                .production
-                .machines
-                .iter()
-                .all(|m| !m.is_operating && m.assigned_staff.is_empty())
-            && app
-                .production
-                .staff
-                .iter()
-                .all(|s| s.is_available))
-}
+    #[test]
+    fn batch_machine_loads_many_items_per_cycle() {
+        let config = serde_json::json!({
+            "items": 5,
+            "machines": [
+                { "id": 0, "name": "Load", "is_automated": true, "count": 5 },
+                { "id": 1, "name": "Furnace", "is_automated": true,
+                  "batch": { "min_size": 2, "max_size": 3, "max_wait": 10 } }
+            ],
+            "staff": [],
+            "processes": [
+                { "machine_id": 0, "duration": 5 },
+                { "machine_id": 1, "duration": 60 }
+            ]
+        });
 
-fn bucket_display_name(app: &App, bucket_id: u32) -> String {
-    if let Some(list) = app.machine_buckets.get(&bucket_id) {
-        if let Some(first) = list.first() {
-            if let Some(machine) = app.production.machines.get(*first as usize) {
-                let base = if machine.machine.name.trim().is_empty() {
-                    format!("Bucket {}", bucket_id)
-                } else {
-                    machine.machine.name.clone()
-                };
-                if list.len() > 1 {
-                    return format!("{} (x{})", base, list.len());
-                }
-                return base;
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // A full load of 3 runs 5-65, the remaining 2 run 65-125
+        assert_eq!(app.finished_goods, 5);
+        assert_eq!(app.production.simulator.elapsed_time(), 125);
+    }
+
+    #[test]
+    fn batch_loads_count_the_items_in_each_transfer_batch() {
+        let config = serde_json::json!({
+            "items": 6,
+            "lots": { "size": 2 },
+            "machines": [
+                { "id": 0, "name": "Load", "is_automated": true, "count": 3 },
+                { "id": 1, "name": "Furnace", "is_automated": true,
+                  "batch": { "min_size": 2, "max_size": 3 } }
+            ],
+            "staff": [],
+            "processes": [
+                { "machine_id": 0, "duration": 5 },
+                { "machine_id": 1, "duration": 60 }
+            ]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // Two lots of 2 would make 4 in a load of 3: each lot runs alone, 5-65, 65-125, 125-185
+        assert_eq!(app.finished_goods, 6);
+        assert_eq!(app.production.simulator.elapsed_time(), 185);
+
+        let mut config = config;
+        config["lots"]["size"] = 4.into();
+        assert!(load_json(&config).is_err());
+    }
+
+    #[test]
+    fn transport_delays_arrival_at_next_step() {
+        let config = serde_json::json!({
+            "items": 2,
+            "machines": [
+                { "id": 0, "name": "Cut", "is_automated": true, "count": 2 },
+                { "id": 1, "name": "Weld", "is_automated": true, "count": 2 }
+            ],
+            "staff": [],
+            "processes": [
+                { "machine_id": 0, "duration": 5 },
+                { "machine_id": 1, "duration": 5 }
+            ],
+            "transport": {
+                "routes": [{ "from": 0, "to": 1, "time": 7, "transporter": true }],
+                "transporters": { "name": "Forklift", "count": 1 }
             }
-        }
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // One forklift: moves run 5-12 and 12-19, welding ends at 24
+        assert_eq!(app.finished_goods, 2);
+        assert_eq!(app.production.simulator.elapsed_time(), 24);
+        assert_eq!(app.transport.stats.moves, 2);
+        assert_eq!(app.transport.stats.wait_minutes, 7);
     }
-    format!("Bucket {}", bucket_id)
-}
 
-fn draw_process_queues(f: &mut ratatui::Frame, area: Rect, app: &App) {
-    if app.steps.is_empty() {
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title("Process Queues");
-        let para = Paragraph::new(Line::from("No process steps defined"))
-            .block(block)
-            .wrap(Wrap { trim: true });
-        f.render_widget(para, area);
-        return;
+    #[test]
+    fn steps_wait_for_shared_fixtures() {
+        let config = serde_json::json!({
+            "items": 3,
+            "machines": [
+                { "id": 0, "name": "Weld", "is_automated": true, "count": 3 },
+                { "id": 1, "name": "Test", "is_automated": true, "count": 3 }
+            ],
+            "staff": [],
+            "resources": [{ "id": 0, "name": "Weld Jig", "capacity": 1 }],
+            "processes": [
+                { "machine_id": 0, "duration": 10, "resources": [{ "id": 0 }] },
+                { "machine_id": 1, "duration": 5 }
+            ]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // Three welders but one jig: welding is serialized
+        assert_eq!(app.finished_goods, 3);
+        assert_eq!(app.production.simulator.elapsed_time(), 35);
+        let jig = app.resources.get(0).unwrap();
+        assert_eq!(jig.in_use, 0);
+        assert_eq!(jig.peak_in_use, 1);
+        assert_eq!(jig.busy_minutes, 30);
+    }
+
+    #[test]
+    fn resource_need_larger_than_pool_is_rejected() {
+        let config = serde_json::json!({
+            "machines": [{ "id": 0, "name": "Weld", "is_automated": true }],
+            "staff": [],
+            "resources": [{ "id": 0, "name": "Pallet", "capacity": 1 }],
+            "processes": [{ "machine_id": 0, "duration": 10, "resources": [{ "id": 0, "quantity": 2 }] }]
+        });
+
+        assert!(load_json(&config).is_err());
+    }
+
+    #[test]
+    fn steps_wait_for_material_deliveries() {
+        let config = serde_json::json!({
+            "items": 4,
+            "machines": [{ "id": 0, "name": "Cut", "is_automated": true, "count": 2 }],
+            "staff": [],
+            "materials": [{
+                "id": 0,
+                "name": "Sheet",
+                "on_hand": 1,
+                "lead_time": 15,
+                "replenish": { "reorder_at": 0, "order_quantity": 2 }
+            }],
+            "processes": [{ "machine_id": 0, "duration": 10, "materials": [{ "id": 0 }] }]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // Deliveries of 2 at 15 and 30: cuts run 0-10, 15-25 (x2) and 30-40
+        assert_eq!(app.finished_goods, 4);
+        assert_eq!(app.production.simulator.elapsed_time(), 40);
+        let sheet = app.materials.get(0).unwrap();
+        assert_eq!(sheet.consumed, 4);
+        assert_eq!(sheet.received, 4);
+        assert_eq!(sheet.orders, 2);
+        assert_eq!(sheet.on_hand, 1);
+        assert_eq!(sheet.stockout_minutes, 30);
+        // A cutter found the rack empty at 0, 10 and 25
+        assert_eq!(sheet.wait_count, 3);
+        assert!(run_summary(&app).contains("Sheet: on hand 1"));
+    }
+
+    #[test]
+    fn replenishment_needs_one_policy() {
+        let config = serde_json::json!({
+            "machines": [{ "id": 0, "name": "Cut", "is_automated": true }],
+            "staff": [],
+            "materials": [{
+                "id": 0,
+                "name": "Sheet",
+                "replenish": { "reorder_at": 5, "order_up_to": 20, "every": 60, "quantity": 10 }
+            }],
+            "processes": [{ "machine_id": 0, "duration": 10 }]
+        });
+
+        assert!(load_json(&config).is_err());
+    }
+
+    #[test]
+    fn step_seizes_every_machine_it_uses() {
+        let config = serde_json::json!({
+            "items": 3,
+            "machines": [
+                { "id": 0, "name": "Robot", "is_automated": true, "count": 2 },
+                { "id": 1, "name": "Fixture Table", "is_automated": true }
+            ],
+            "staff": [],
+            "processes": [{ "machine_id": 0, "time_per_unit": 6, "also_uses": [1] }]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        assert_eq!(app.production.machines[1].waiting_for, Some(WaitReason::Machine("Fixture Table".to_string())));
+        run_to_completion(&mut app);
+
+        // Two robots but one table: the welds run one after another
+        assert_eq!(app.finished_goods, 3);
+        assert_eq!(app.production.simulator.elapsed_time(), 18);
+        assert!(app.machine_holds.is_empty());
+        let table = &app.production.machines[2];
+        assert!(!table.is_operating);
+        assert_eq!(table.idle_time, 0);
+    }
+
+    #[test]
+    fn batch_load_time_grows_per_unit() {
+        let config = serde_json::json!({
+            "items": 4,
+            "machines": [{ "id": 0, "name": "Oven", "is_automated": true, "batch": { "min_size": 4, "max_size": 4 } }],
+            "staff": [],
+            "processes": [{ "machine_id": 0, "duration": 20, "time_per_unit": 2 }]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        assert_eq!(app.production.simulator.elapsed_time(), 28);
+    }
+
+    #[test]
+    fn idle_time_is_split_into_starved_and_waiting_for_staff() {
+        // Two presses, one operator: the second press waits for staff, then runs out of work
+        let config = serde_json::json!({
+            "items": 2,
+            "machines": [{ "id": 0, "name": "Press", "staff_required": 1, "count": 2 }],
+            "staff": [{ "id": 0, "name": "Operator", "role": { "id": 0, "name": "Operator" } }],
+            "processes": [{ "machine_id": 0, "duration": 10 }]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        assert_eq!(app.production.simulator.elapsed_time(), 20);
+        let times = bucket_status_times(&app, 0);
+        assert_eq!(times.minutes(MachineStatus::Busy), 20);
+        assert_eq!(times.minutes(MachineStatus::WaitingForStaff), 10);
+        assert_eq!(times.minutes(MachineStatus::Starved), 10);
+        assert!(run_summary(&app).contains("Press (x2): busy 50.0% | starved 25.0% | waiting for staff 25.0%"));
+    }
+
+    #[test]
+    fn bottleneck_is_found_by_active_periods() {
+        // Cut runs 0-30 without a break; Paint only works 5 of every 10 minutes
+        let config = serde_json::json!({
+            "items": 3,
+            "bottleneck_window": 20,
+            "machines": [
+                { "id": 0, "name": "Cut", "is_automated": true },
+                { "id": 1, "name": "Paint", "is_automated": true }
+            ],
+            "staff": [],
+            "processes": [
+                { "machine_id": 0, "duration": 10 },
+                { "machine_id": 1, "duration": 5 }
+            ]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        assert_eq!(app.production.simulator.elapsed_time(), 35);
+        let shares = app.bottlenecks.analyze(0, 35, 35);
+        assert_eq!(shares[0].bucket_id, 0);
+        assert_eq!((shares[0].sole, shares[0].shifting), (30, 0));
+        let summary = run_summary(&app);
+        assert!(summary.contains("Run: Cut sole 85.7%; Paint sole 14.3%"));
+        assert!(summary.contains("Utilization: Cut 85.7% > Paint 42.9%"));
+        assert!(summary.contains("20-35 mins: Cut sole 66.7%; Paint sole 33.3%"));
+    }
+
+    #[test]
+    fn oee_factors_come_from_states_and_work_done() {
+        // A slow operator takes 25 minutes for a 20-minute step
+        let config = serde_json::json!({
+            "items": 2,
+            "machines": [
+                { "id": 0, "name": "Press", "staff_required": 1 },
+                { "id": 1, "name": "Check", "is_automated": true }
+            ],
+            "staff": [{
+                "id": 0,
+                "name": "Trainee",
+                "role": { "id": 0, "name": "Operator", "machine_ids": [0] },
+                "proficiency": [{ "machine_id": 0, "level": 1.25 }]
+            }],
+            "processes": [
+                { "machine_id": 0, "duration": 20 },
+                { "machine_id": 1, "duration": 5 }
+            ]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        assert_eq!(app.production.simulator.elapsed_time(), 55);
+        // Idle minutes after the last job lower performance, not availability
+        let press = bucket_oee(&app, 0);
+        assert_eq!(press.availability, Some(1.0));
+        assert!((press.performance.unwrap() - 40.0 / 55.0).abs() < 1e-9);
+        assert_eq!(press.quality, Some(1.0));
+        assert!(run_summary(&app).contains(
+            "Press: OEE 72.7% | availability 100.0% | performance 72.7% | quality 100.0%"
+        ));
     }
 
-    let card_height: u16 = 5;
-    let visible_slots = std::cmp::max(1, (area.height / card_height) as usize);
-    let max_scroll = app.steps.len().saturating_sub(visible_slots);
-    let start = std::cmp::min(app.process_scroll, max_scroll);
-    let end = std::cmp::min(app.steps.len(), start + visible_slots);
-    let slice = &app.steps[start..end];
+    #[test]
+    fn statistics_restart_after_the_warmup() {
+        let mut config = serde_json::json!({
+            "items": 6,
+            "warmup": 20,
+            "observe_until": 50,
+            "machines": [{ "id": 0, "name": "Mill", "is_automated": true }],
+            "staff": [],
+            "processes": [{ "machine_id": 0, "duration": 10 }]
+        });
 
-    let constraints: Vec<Constraint> = slice.iter().map(|_| Constraint::Length(card_height)).collect();
-    let areas = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(constraints)
-        .split(area);
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
 
-    for (idx, step) in slice.iter().enumerate() {
-        let bucket = step.machine_id;
-        let name = bucket_display_name(app, bucket);
-        let queue_len = app.job_queues.get(&bucket).map(|q| q.len()).unwrap_or(0);
-        let busy_machines = app
-            .machine_buckets
-            .get(&bucket)
-            .map(|ids| {
-                ids.iter()
-                    .filter(|&&id| app.production.machines.get(id as usize).map(|m| m.is_operating).unwrap_or(false))
-                    .count()
-            })
-            .unwrap_or(0);
-        let bucket_size = app
-            .machine_buckets
-            .get(&bucket)
-            .map(|list| list.len())
-            .unwrap_or(1);
+        // Stopped at the window's end with work left; two goods fell in the warm-up
+        assert_eq!(app.production.simulator.elapsed_time(), 50);
+        assert_eq!(app.finished_goods, 5);
+        assert_eq!(observed_goods(&app), 3);
+        let times = &app.production.machines[0].status_times;
+        assert_eq!((times.minutes(MachineStatus::Busy), times.total()), (30, 30));
+        assert!(run_summary(&app).contains("Observed 20-50 mins: 3 finished goods | 6.00/hr"));
 
-        let text = vec![
-            Line::from(format!("Queue: {}", queue_len)),
-            Line::from(format!("Machines busy: {} / {}", busy_machines, bucket_size)),
-            Line::from(format!("Duration: {} mins", step.duration)),
-        ];
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .title(format!("{} (step {})", name, start + idx + 1))
-            .style(Style::default().fg(Color::White));
-        let para = Paragraph::new(text)
-            .style(Style::default().fg(Color::White))
-            .block(block);
-        f.render_widget(para, areas[idx]);
+        config["warmup"] = serde_json::json!(50);
+        assert!(load_json(&config).is_err());
+
+        // A warm-up the run never reaches is reported, not silently dropped
+        config["warmup"] = serde_json::json!(100);
+        config["observe_until"] = serde_json::Value::Null;
+        let mut app = build_app(load_json(&config).unwrap(), "test".to_string());
+        run_to_completion(&mut app);
+        assert!(run_summary(&app).contains("Warm-up of 100 mins is longer than the 60-min run"));
     }
-}
 
-fn draw_status_tabs(f: &mut ratatui::Frame, area: Rect, app: &App) {
-    let tabs_area = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
-        .split(area);
+    #[test]
+    fn warmup_is_suggested_from_replications_without_the_drain_down() {
+        // The curing step fills over two hours, then the line makes 6 an hour until its input stops
+        let config = serde_json::json!({
+            "items": 120,
+            "machines": [
+                { "id": 0, "name": "Mix", "is_automated": true },
+                { "id": 1, "name": "Cure", "is_automated": true, "count": 12 },
+                { "id": 2, "name": "Pack", "is_automated": true }
+            ],
+            "staff": [],
+            "processes": [
+                { "machine_id": 0, "duration": 10 },
+                { "machine_id": 1, "duration": 120 },
+                { "machine_id": 2, "duration": 10 }
+            ]
+        });
+        assert_eq!(warmup_suggestion(&config), Ok(Some(240)));
 
-    let titles = vec![Line::from("Machines"), Line::from("Staff")];
-    let tabs = Tabs::new(titles)
-        .select(app.status_tab)
-        .block(Block::default().borders(Borders::ALL).title("Status"))
-        .highlight_style(Style::default().fg(Color::Yellow));
-    f.render_widget(tabs, tabs_area[0]);
+        // Too few goods an hour to read a warm-up from
+        let mut slow = config.clone();
+        slow["processes"][0]["duration"] = serde_json::json!(20);
+        slow["processes"][1]["duration"] = serde_json::json!(240);
+        assert_eq!(warmup_suggestion(&slow), Ok(None));
+    }
 
-    match app.status_tab {
-        0 => {
-            let mut machine_lines = Vec::new();
-            for machine in &app.production.machines {
-                let status = if machine.is_operating { "Busy" } else { "Idle" };
-                let waiting = machine
-                    .waiting_for
-                    .as_deref()
-                    .unwrap_or(if machine.is_operating { "" } else { "Next task" });
-                let name = app
-                    .machine_to_bucket
-                    .get(&machine.machine.id)
-                    .map(|b| bucket_display_name(app, *b))
-                    .unwrap_or_else(|| format!("Machine {}", machine.machine.id));
-                machine_lines.push(Line::from(format!(
-                    "{} (ID {}): {} | Waiting: {}",
-                    name,
-                    machine.machine.id,
-                    status,
-                    if waiting.is_empty() { "-" } else { waiting }
-                )));
-            }
-            let para = Paragraph::new(machine_lines)
-                .block(Block::default().borders(Borders::ALL))
-                .wrap(Wrap { trim: true });
-            f.render_widget(para, tabs_area[1]);
-        }
-        _ => {
-            let mut staff_lines = Vec::new();
-            for staff in &app.production.staff {
-                let status = if staff.is_available { "Available" } else { "Busy" };
-                let waiting = if staff.is_available {
-                    "Assignment".to_string()
-                } else {
-                    staff
-                        .current_machine
-                        .map(|m| format!("Machine {}", m))
-                        .unwrap_or_else(|| "Task".to_string())
-                };
-                staff_lines.push(Line::from(format!(
-                    "{} - {}: {} | Idle: {} | Waiting: {}",
-                    staff.id,
-                    staff.name,
-                    status,
-                    staff.idle_time,
-                    waiting
-                )));
-            }
-            let para = Paragraph::new(staff_lines)
-                .block(Block::default().borders(Borders::ALL))
-                .wrap(Wrap { trim: true });
-            f.render_widget(para, tabs_area[1]);
+    #[test]
+    fn html_report_has_charts_for_the_run() {
+        let config = serde_json::json!({
+            "items": 3,
+            "machines": [
+                { "id": 0, "name": "Cut", "staff_required": 1 },
+                { "id": 1, "name": "Paint", "is_automated": true }
+            ],
+            "staff": [{ "id": 0, "name": "Ada", "role": { "id": 0, "name": "Operator", "machine_ids": [0] } }],
+            "processes": [
+                { "machine_id": 0, "duration": 10 },
+                { "machine_id": 1, "duration": 15 }
+            ]
+        });
+
+        let mut loaded = load_json(&config).unwrap();
+        loaded.sampler = Some(Sampler::new(SampleMode::EveryEvent));
+        let mut app = build_app(loaded, "Report <test>".to_string());
+        assert!(run_headless(&mut app));
+        assert_eq!(app.production.simulator.elapsed_time(), 55);
+        assert_eq!(app.lots.cycle_times, vec![25, 40, 55]);
+
+        let report = html_report(&app);
+        assert!(report.contains("<title>Report &lt;test&gt;</title>"));
+        for heading in ["Key figures by bucket", "Utilization", "Queue lengths", "Cycle times", "Machine states 0-55 mins"] {
+            assert!(report.contains(heading), "missing {}", heading);
         }
+        assert!(report.contains("<h3>Staff (busy)</h3>"));
+        assert_eq!(report.matches("<svg").count(), 5);
+        // Paint runs back to back from the first unit on
+        assert!(report.contains("<title>busy 10-55 min</title>"));
     }
-}
-fn draw_ui(f: &mut ratatui::Frame, app: &App) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
-        .split(f.size());
 
-    let left = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(12), Constraint::Min(0)].as_ref())
-        .split(chunks[0]);
+    #[test]
+    fn compare_runs_each_scenario_with_replications() {
+        let logger = Logger::new(LogLevel::Error);
+        let config = |ovens: u32| {
+            serde_json::json!({
+                "items": 4,
+                "machines": [{ "id": 0, "name": "Oven", "is_automated": true, "count": ovens }],
+                "staff": [],
+                "processes": [{ "machine_id": 0, "duration": 30 }]
+            })
+        };
+        let one = write_temp_config("compare_one", &config(1));
+        let two = write_temp_config("compare_two", &config(2));
+        let args: Vec<String> = [one.to_str().unwrap(), two.to_str().unwrap(), "-n", "2"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let compare = parse_compare_args(&args).unwrap();
+        assert_eq!(compare.replications, 2);
 
-    draw_metrics(f, left[0], app);
-    draw_status_tabs(f, left[1], app);
-    draw_process_queues(f, chunks[1], app);
-}
+        let comparison = compare_scenarios(&compare, &logger).unwrap();
+        let makespan = &comparison.kpis[0];
+        assert_eq!(makespan.name, "Makespan (mins)");
+        let means: Vec<f64> = makespan.estimates.iter().flatten().map(|e| e.mean).collect();
+        assert_eq!(means, vec![120.0, 60.0]);
+        assert_eq!(makespan.delta(1), Some((-60.0, true)));
+        let json = comparison_json(&comparison, 2);
+        assert_eq!(json["kpis"][0]["scenarios"][1]["delta"], -60.0);
+        assert_eq!(json["kpis"][0]["scenarios"][1]["significant"], true);
 
-fn draw_metrics(f: &mut ratatui::Frame, area: Rect, app: &App) {
-    let elapsed = app.production.simulator.elapsed_time();
-    let operating = app
-        .production
-        .machines
-        .iter()
-        .filter(|m| m.is_operating)
-        .count();
-    let total_idle: u32 = app.production.staff.iter().map(|s| s.idle_time).sum();
-    let playing_text = if app.playing { "Playing" } else { "Paused" };
+        // Only the costed scenario has a Cost row, and a plant with no one to run it stalls
+        let mut costed = config(1);
+        costed["cost"] = serde_json::json!({ "material_per_item": 10 });
+        let costed = write_temp_config("compare_costed", &costed);
+        let mut unstaffed = config(1);
+        unstaffed["machines"][0]["is_automated"] = false.into();
+        let unstaffed = write_temp_config("compare_unstaffed", &unstaffed);
+        let compare = CompareArgs {
+            configs: vec![one.to_str().unwrap().to_string(), costed.to_str().unwrap().to_string(), unstaffed.to_str().unwrap().to_string()],
+            replications: 2,
+            json: None,
+        };
+        let comparison = compare_scenarios(&compare, &logger).unwrap();
+        let cost = comparison.kpis.iter().find(|kpi| kpi.name == "Cost").unwrap();
+        assert_eq!(cost.estimates[0], None);
+        assert_eq!(cost.estimates[1].map(|e| e.mean), Some(40.0));
+        assert_eq!(comparison.stalled, vec![0, 0, 2]);
+        assert!(comparison.kpis.iter().all(|kpi| kpi.estimates[2].is_none()));
+        assert!(comparison.to_table().contains("2 replication(s) stalled"));
+        let _ = std::fs::remove_file(costed);
+        let _ = std::fs::remove_file(unstaffed);
 
-    let lines = vec![
-        Line::from(app.title.clone()),
-        Line::from(format!("Mode: {}", playing_text)),
-        Line::from(format!("Elapsed: {} mins", elapsed)),
-        Line::from(format!(
-            "Machines: {} total | {} active",
-            app.production.machines.len(),
-            operating
-        )),
-        Line::from(format!("Staff: {}", app.production.staff.len())),
-        Line::from(format!("Total idle mins: {}", total_idle)),
-        Line::from(format!("Finished goods: {}", app.finished_goods)),
-        Line::from("Controls:"),
-        Line::from("  space - play/pause"),
-        Line::from("  n     - step once"),
-        Line::from("  up/down - scroll process list"),
-        Line::from("  tab   - switch status tab"),
-        Line::from("  q     - quit"),
-    ];
+        assert!(parse_compare_args(&args[..1]).is_err());
+        let _ = std::fs::remove_file(one);
+        let _ = std::fs::remove_file(two);
+    }
 
-    let metrics = Paragraph::new(lines)
-        .block(Block::default().borders(Borders::ALL).title("Metrics"))
-        .wrap(Wrap { trim: true });
+    #[test]
+    fn sweep_runs_every_combination_of_counts_and_headcounts() {
+        let logger = Logger::new(LogLevel::Error);
+        let base = serde_json::json!({
+            "items": 4,
+            "machines": [{ "id": 0, "name": "Weld", "staff_required": 1 }],
+            "staff": [{ "id": 0, "name": "Ann", "role": { "id": 0, "name": "Welder", "machine_ids": [0] } }],
+            "processes": [{ "machine_id": 0, "duration": 30 }]
+        });
+        let sweep: SweepConfig = serde_json::from_value(serde_json::json!({
+            "base": "line.json",
+            "factors": [
+                { "path": "machines[id=0].count", "range": [1, 2] },
+                { "role": "Welder", "values": [0, 1, 2] }
+            ],
+            "threads": 2,
+            "output": "results.csv"
+        }))
+        .unwrap();
 
-    f.render_widget(metrics, area);
-}
+        let results = sweep_scenarios(&sweep, &base, &logger).unwrap();
+        assert_eq!(results.factors, vec!["machines[id=0].count", "Welder staff"]);
+        let makespans: Vec<(Vec<f64>, u32, Option<f64>)> =
+            results.rows.iter().map(|row| (row.factors.clone(), row.completed, row.kpis[0])).collect();
+        // With no welders the plant stalls, so its makespan is left blank
+        assert_eq!(
+            makespans,
+            vec![
+                (vec![1.0, 0.0], 0, None),
+                (vec![1.0, 1.0], 1, Some(120.0)),
+                (vec![1.0, 2.0], 1, Some(120.0)),
+                (vec![2.0, 0.0], 0, None),
+                (vec![2.0, 1.0], 1, Some(120.0)),
+                (vec![2.0, 2.0], 1, Some(60.0)),
+            ]
+        );
+        let csv = results.to_csv();
+        assert!(csv.starts_with("scenario,machines[id=0].count,Welder staff,completed,Makespan (mins)"));
+        assert!(csv.contains("\n1,1,0,0,,"));
 
+        // No factors: just the base scenario
+        let sweep: SweepConfig =
+            serde_json::from_value(serde_json::json!({ "base": "line.json", "factors": [], "output": "results.csv" }))
+                .unwrap();
+        let results = sweep_scenarios(&sweep, &base, &logger).unwrap();
+        assert_eq!(results.rows.len(), 1);
+        assert_eq!(results.rows[0].kpis[0], Some(120.0));
 
-#[derive(Clone)]
-struct PendingJob {
-    duration: u32,
-    step_index: usize,
-    item_id: u32,
-}
+        // Headcounts are whole people
+        let sweep: SweepConfig = serde_json::from_value(serde_json::json!({
+            "base": "line.json",
+            "factors": [{ "role": "Welder", "values": [1.5] }],
+            "output": "results.csv"
+        }))
+        .unwrap();
+        assert!(sweep_scenarios(&sweep, &base, &logger).is_err());
 
-fn try_start_jobs(app: &mut App, bucket_id: u32, current_time: u32) {
-    let Some(queue) = app.job_queues.get_mut(&bucket_id) else { return };
-    if queue.is_empty() {
-        return;
+        let mut config = base.clone();
+        set_headcount(&mut config, "Welder", 3).unwrap();
+        assert_eq!(config["staff"][2]["id"], 2);
+        assert_eq!(config["staff"][2]["name"], "Welder 3");
+        // A clone never takes a name someone already has
+        let mut renamed = base.clone();
+        renamed["staff"][0]["name"] = "Welder 2".into();
+        set_headcount(&mut renamed, "Welder", 3).unwrap();
+        let names: Vec<&str> = renamed["staff"].as_array().unwrap().iter().map(|s| s["name"].as_str().unwrap()).collect();
+        assert_eq!(names.iter().collect::<HashSet<_>>().len(), names.len());
+        set_config_field(&mut config, "processes[0].duration", 12.5).unwrap();
+        assert_eq!(config["processes"][0]["duration"], 12.5);
+        assert!(set_config_field(&mut config, "machines[id=9].count", 2.0).is_err());
+        assert!(set_headcount(&mut config, "Painter", 1).is_err());
     }
 
-    let Some(machine_ids) = app.machine_buckets.get(&bucket_id) else { return };
+    #[test]
+    fn optimize_finds_the_cheapest_counts_for_a_makespan() {
+        let logger = Logger::new(LogLevel::Error);
+        let base = serde_json::json!({
+            "items": 4,
+            "machines": [{ "id": 0, "name": "Weld", "staff_required": 1 }],
+            "staff": [{ "id": 0, "name": "Ann", "role": { "id": 0, "name": "Welder", "machine_ids": [0] } }],
+            "processes": [{ "machine_id": 0, "duration": 30 }]
+        });
+        // A second welder alone, or a second machine alone, does not help; both together halve the makespan
+        let mut optimize: OptimizeConfig = serde_json::from_value(serde_json::json!({
+            "base": "line.json",
+            "target": { "makespan": 60 },
+            "machines": [{ "id": 0, "max": 4, "cost": 100 }],
+            "roles": [{ "role": "Welder", "max": 4, "cost": 50 }],
+            "output": "best.json"
+        }))
+        .unwrap();
 
-    // Try to start as many queued jobs as there are free machines and staff
-    while !queue.is_empty() {
-        // pick the job furthest along in the process (highest step_index)
-        let best_idx = queue
-            .iter()
-            .enumerate()
-            .max_by_key(|(_, job)| (job.step_index, std::cmp::Reverse(job.item_id)))
-            .map(|(idx, _)| idx)
-            .unwrap();
+        let found = optimize_counts(&optimize, &base, &logger).unwrap().unwrap();
+        assert_eq!(found.counts, vec![("Weld".to_string(), 2), ("Welder".to_string(), 2)]);
+        assert_eq!(found.cost, 300.0);
+        assert_eq!(found.achieved, "makespan 60 mins");
+        assert_eq!(found.config["machines"][0]["count"], 2);
+        assert_eq!(found.config["staff"].as_array().unwrap().len(), 2);
 
-        // find an idle machine in this bucket
-        let Some(&machine_id) = machine_ids
-            .iter()
-            .find(|&&m_id| app.production.machines.get(m_id as usize).map(|m| !m.is_operating).unwrap_or(false))
-        else {
-            break; // no idle machines
-        };
+        optimize.method = Some(SearchMethodConfig::Annealing);
+        let annealed = optimize_counts(&optimize, &base, &logger).unwrap().unwrap();
+        assert_eq!(annealed.cost, 300.0);
 
-        let job = queue.remove(best_idx);
-        let pid = app.next_pid;
-        app.next_pid += 1;
-        app.process_meta.insert(pid, (job.step_index, job.item_id));
+        optimize.target = TargetConfig::Makespan(20.0);
+        assert!(optimize_counts(&optimize, &base, &logger).unwrap().is_none());
+    }
 
-        let started = app
-            .production
-            .try_start_process(machine_id, pid, job.duration, current_time);
+    #[test]
+    fn optimize_never_picks_a_plant_that_stalls() {
+        let logger = Logger::new(LogLevel::Error);
+        let base = serde_json::json!({
+            "items": 4,
+            "machines": [{ "id": 0, "name": "Weld", "staff_required": 1 }],
+            "staff": [{ "id": 0, "name": "Ann", "role": { "id": 0, "name": "Welder", "machine_ids": [0] } }],
+            "processes": [{ "machine_id": 0, "duration": 30 }]
+        });
+        // With no welders nothing gets done, so the stalled run's short makespan must not count
+        let optimize: OptimizeConfig = serde_json::from_value(serde_json::json!({
+            "base": "line.json",
+            "target": { "makespan": 200 },
+            "machines": [{ "id": 0, "max": 2, "cost": 100 }],
+            "roles": [{ "role": "Welder", "min": 0, "max": 2, "cost": 50 }],
+            "output": "best.json"
+        }))
+        .unwrap();
 
-        if started {
-            if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
-                machine.waiting_for = None;
-            }
-        } else {
-            // Could not start (likely staff unavailable) — mark machine as waiting for staff and requeue
-            if let Some(machine) = app.production.machines.get_mut(machine_id as usize) {
-                machine.waiting_for = Some("Staff".to_string());
-            }
-            queue.push(job);
-            break;
-        }
+        let found = optimize_counts(&optimize, &base, &logger).unwrap().unwrap();
+        assert_eq!(found.counts, vec![("Weld".to_string(), 1), ("Welder".to_string(), 1)]);
+        assert_eq!(found.achieved, "makespan 120 mins");
     }
-}
 
-fn run_examples(logger: &Logger) {
-    logger.debug("Application started");
-    logger.info("System initialized");
+    #[test]
+    fn staff_time_is_split_by_bucket_and_rolled_up_per_role() {
+        let operator = serde_json::json!({ "id": 0, "name": "Operator", "machine_ids": [0, 1] });
+        let config = serde_json::json!({
+            "items": 2,
+            "machines": [
+                { "id": 0, "name": "Cut", "staff_required": 1 },
+                { "id": 1, "name": "Paint", "staff_required": 1 }
+            ],
+            "staff": [
+                { "id": 0, "name": "Ann", "role": operator },
+                { "id": 1, "name": "Bob", "role": operator }
+            ],
+            "processes": [
+                { "machine_id": 0, "duration": 10 },
+                { "machine_id": 1, "duration": 5 }
+            ]
+        });
 
-    // ============================================================
-    // Example 1: Simple simulation without staff
-    // ============================================================
-    logger.info("\n=== Example 1: Event Scheduling (without staff) ===");
-    {
-        let mut sim = Simulator::new();
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
 
-        sim.schedule_event(
-            SimulationTime::new(10),
-            EventType::ProcessStart {
-                machine_id: 0,
-                process_id: 1,
-            },
-        );
-        sim.schedule_event(
-            SimulationTime::new(25),
-            EventType::ProcessComplete {
-                machine_id: 0,
-                process_id: 1,
-            },
-        );
-        sim.schedule_event(
-            SimulationTime::new(5),
-            EventType::MaterialArrival { material_id: 1 },
+        let elapsed = app.production.simulator.elapsed_time();
+        let busy: u32 = app.production.staff.iter().map(|s| s.busy_time()).sum();
+        let idle: u32 = app.production.staff.iter().map(|s| s.idle_time).sum();
+        assert_eq!(busy, 30);
+        assert_eq!(busy + idle, 2 * elapsed);
+        let summary = run_summary(&app);
+        assert!(summary.contains("  - Operator (2): busy 60.0% (Cut 40.0%, Paint 20.0%) | idle 40.0%"));
+        assert!(summary.contains("      Ann: busy 80.0% (Cut 40.0%, Paint 40.0%) | idle 20.0%"));
+
+        // With Ann alone, Cut waits for her while she paints the first item
+        let mut config = config;
+        config["staff"].as_array_mut().unwrap().pop();
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+        assert_eq!(app.production.staff[0].busy_time(), 30);
+        assert_eq!(app.staff_waits.get(&0), Some(&1));
+        assert!(run_summary(&app).contains("  Waits for staff: Cut 1\n"));
+    }
+
+    #[test]
+    fn sampler_records_queues_at_an_interval() {
+        let config = serde_json::json!({
+            "items": 2,
+            "sampling": { "every": 10 },
+            "machines": [{ "id": 0, "name": "Oven", "is_automated": true }],
+            "staff": [],
+            "processes": [{ "machine_id": 0, "duration": 15 }]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        assert_eq!(app.production.simulator.elapsed_time(), 30);
+        assert_eq!(
+            samples_csv(&app),
+            "time,wip,Oven queue,Oven busy machines,Oven busy staff\n\
+             0,2,1,1,0\n\
+             10,2,1,1,0\n\
+             20,1,0,1,0\n\
+             30,0,0,0,0\n"
         );
+    }
 
-        logger.info("Events scheduled, starting simulation...");
-        sim.run_all(|sim, event| {
-            logger.info(&format!("Time {}: {:?}", sim.elapsed_time(), event.event_type));
+    fn lot_config(transfer_batch: u32) -> serde_json::Value {
+        serde_json::json!({
+            "items": 4,
+            "lots": { "size": 4, "transfer_batch": transfer_batch },
+            "machines": [
+                { "id": 0, "name": "Mill", "is_automated": true },
+                { "id": 1, "name": "Deburr", "is_automated": true }
+            ],
+            "staff": [],
+            "processes": [
+                { "machine_id": 0, "duration": 5, "time_per_unit": 2 },
+                { "machine_id": 1, "duration": 3, "time_per_unit": 1 }
+            ]
+        })
+    }
+
+    #[test]
+    fn transfer_batches_overlap_steps() {
+        // Whole lot: Mill 5 + 4*2, then Deburr 3 + 4*1
+        let config = lot_config(4);
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+        assert_eq!(app.production.simulator.elapsed_time(), 20);
+
+        // Batches of 2: Mill 0-9 and 9-13, Deburr 9-14 and 14-16 (setup only once per lot)
+        let config = lot_config(2);
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+        assert_eq!(app.production.simulator.elapsed_time(), 16);
+        assert_eq!(app.finished_goods, 4);
+        assert_eq!(app.lots.completed, 1);
+        assert_eq!(app.lots.average_cycle_time(), Some(16.0));
+        assert_eq!(app.lots.peak_wip, 4);
+    }
+
+    #[test]
+    fn lots_are_released_on_schedule() {
+        let config = serde_json::json!({
+            "items": 4,
+            "lots": { "size": 2, "release_every": 30 },
+            "machines": [{ "id": 0, "name": "Lathe", "is_automated": true }],
+            "staff": [],
+            "processes": [{ "machine_id": 0, "duration": 5, "time_per_unit": 1 }]
         });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        assert_eq!(app.production.simulator.elapsed_time(), 37);
+        assert_eq!(app.lots.completed, 2);
+        assert_eq!(app.lots.average_cycle_time(), Some(7.0));
+        assert_eq!(app.lots.peak_wip, 2);
     }
 
-    // ============================================================
-    // Example 2: Production with staff scheduling
-    // ============================================================
-    logger.info("\n=== Example 2: Staff Scheduling ===");
-    {
-        let mut prod = ProductionSimulator::new();
+    #[test]
+    fn transfer_batch_cannot_exceed_lot() {
+        let config = lot_config(5);
+        let result = load_json(&config);
+        assert!(result.is_err());
+    }
 
-        // Create machines
-        logger.info("Setting up production line...");
-        let cnc_machine = MachineType::new(0, "CNC Machine", 1); // Needs 1 staff
-        let assembly = MachineType::new(1, "Assembly Station", 2); // Needs 2 staff
-        let conveyor = MachineType::automated(2, "Conveyor Belt"); // Automated, no staff
+    fn two_line_config() -> serde_json::Value {
+        serde_json::json!({
+            "items": 3,
+            "lines": [
+                {
+                    "name": "Blank Line",
+                    "end_material": "Blank",
+                    "machines": [{ "id": 0, "name": "Cut", "staff_required": 1 }],
+                    "staff": [{ "id": 0, "name": "Cutter", "role": { "id": 0, "name": "Operator" } }],
+                    "processes": [{ "machine_id": 0, "duration": 10 }]
+                },
+                {
+                    "name": "Press Line",
+                    "start_material": "Blank",
+                    "end_material": "Bracket",
+                    "machines": [{ "id": 1, "name": "Press", "is_automated": true }],
+                    "processes": [{ "machine_id": 1, "duration": 4 }]
+                }
+            ]
+        })
+    }
 
-        prod.add_machine(cnc_machine);
-        prod.add_machine(assembly);
-        prod.add_machine(conveyor);
+    #[test]
+    fn lines_are_fed_through_a_store() {
+        let config = two_line_config();
 
-        // Create staff
-        logger.info("Hiring staff...");
+        let loaded = load_json(&config).unwrap();
+        // Line staff only work their own line's machines
+        assert!(loaded.production.staff[0].can_work_on(0));
+        assert!(!loaded.production.staff[0].can_work_on(1));
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
 
-        // General operator (can work anywhere)
-        let operator_role = Role::new(0, "General Operator");
-        let john = Staff::new(0, "John", operator_role.clone());
-        prod.add_staff(john);
+        // Blanks reach the store at 10, 20 and 30; each press takes 4 minutes
+        assert_eq!(app.items, 6);
+        assert_eq!(app.finished_goods, 3);
+        assert_eq!(app.production.simulator.elapsed_time(), 34);
+        assert_eq!(app.plant.transferred(), 3);
+        let store = app.materials.get(app.plant.lines[1].input.unwrap()).unwrap();
+        assert_eq!(store.name, "Blank");
+        assert_eq!((store.received, store.consumed, store.on_hand), (3, 3, 0));
+        assert!(run_summary(&app).contains("Blank Line: 3/3 out | scrapped 0 | makes Blank (store)"));
+    }
 
-        // CNC specialist (can only work on CNC)
-        let cnc_specialist_role = Role::specialist(1, "CNC Specialist", vec![0]);
-        let jane = Staff::new(1, "Jane", cnc_specialist_role);
-        prod.add_staff(jane);
+    #[test]
+    fn line_steps_must_use_their_own_machines() {
+        let mut config = two_line_config();
+        config["lines"][1]["processes"][0]["machine_id"] = serde_json::json!(0);
 
-        // Assembly workers
-        let assembler_role = Role::new(2, "Assembler");
-        let bob = Staff::new(2, "Bob", assembler_role.clone());
-        let alice = Staff::new(3, "Alice", assembler_role);
-        prod.add_staff(bob);
-        prod.add_staff(alice);
+        assert!(load_json(&config).is_err());
+    }
 
-        logger.info(&prod.get_status());
+    #[test]
+    fn one_operator_tends_two_machines() {
+        let config = serde_json::json!({
+            "items": 2,
+            "machines": [{ "id": 0, "name": "Lathe", "staff_required": 1, "count": 2 }],
+            "staff": [{ "id": 0, "name": "Tender", "role": { "id": 0, "name": "Operator" } }],
+            "processes": [{
+                "machine_id": 0,
+                "phases": [
+                    { "kind": "load", "duration": 2 },
+                    { "kind": "run", "duration": 30 },
+                    { "kind": "unload", "duration": 2, "staff": 1 }
+                ]
+            }]
+        });
 
-        // ============================================================
-        // Schedule production: Item 0 through CNC -> Assembly -> Conveyor
-        // ============================================================
-        logger.info("\n--- Scheduling Item 0 ---");
+        let loaded = load_json(&config).unwrap();
+        assert_eq!(loaded.steps[0].duration, 34);
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
 
-        // Item 0: CNC (needs Jane)
-        let success = prod.try_start_process(0, 0, 15, 0);
-        if success {
-            logger.info("Item 0: CNC process started (Jane assigned)");
-        } else {
-            logger.warning("Item 0: CNC process failed - staff unavailable");
-        }
+        // A: load 0-2, run 2-32, unload 32-34. B: load 2-4, run 4-34, unload 34-36.
+        // Holding the operator for the whole step would take 68 minutes.
+        assert_eq!(app.finished_goods, 2);
+        assert_eq!(app.production.simulator.elapsed_time(), 36);
+    }
 
-        // After CNC completes (time 15), try assembly
-        // But we need to manually release Jane and assign Bob+Alice
-        prod.staff[1].release_from_machine(15); // Release Jane
+    #[test]
+    fn bottleneck_bucket_is_run_by_its_expert() {
+        let config = serde_json::json!({
+            "items": 1,
+            "machines": [{ "id": 5, "name": "Press", "staff_required": 1, "prefer_experts": true }],
+            "staff": [
+                { "id": 0, "name": "Standard", "role": { "id": 0, "name": "Operator" } },
+                {
+                    "id": 1,
+                    "name": "Expert",
+                    "role": { "id": 1, "name": "Press Operator", "machine_ids": [5] },
+                    "proficiency": [{ "machine_id": 5, "level": 0.5 }]
+                }
+            ],
+            "processes": [{ "machine_id": 5, "duration": 20 }]
+        });
 
-        // ============================================================
-        // Schedule production: Item 1 - parallel processing
-        // ============================================================
-        logger.info("\n--- Scheduling Item 1 (Parallel) ---");
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+        assert_eq!(app.production.simulator.elapsed_time(), 10);
+    }
 
-        // Item 1 on CNC at time 15 (Jane just became available)
-        let success = prod.try_start_process(0, 1, 15, 15);
-        if success {
-            logger.info("Item 1: CNC process started at time 15 (Jane assigned)");
-        }
+    #[test]
+    fn proficiency_for_unknown_bucket_is_rejected() {
+        let config = serde_json::json!({
+            "machines": [{ "id": 0, "name": "Press" }],
+            "staff": [{
+                "id": 0,
+                "name": "Op",
+                "role": { "id": 0, "name": "Operator" },
+                "proficiency": [{ "machine_id": 3, "level": "expert" }]
+            }],
+            "processes": [{ "machine_id": 0, "duration": 5 }]
+        });
 
-        // Item 0 on Assembly at time 15 (needs Bob and Alice)
-        let success = prod.try_start_process(1, 0, 20, 15);
-        if success {
-            logger.info("Item 0: Assembly started at time 15 (Bob & Alice assigned)");
-        } else {
-            logger.warning("Item 0: Assembly failed - staff unavailable");
+        let err = load_json(&config).err().unwrap();
+        assert!(err.to_string().contains("unknown machine 3"));
+
+        let mut config = config;
+        config["staff"][0]["proficiency"] = serde_json::json!([]);
+        config["staff"][0]["role"]["machine_ids"] = serde_json::json!([0, 1]);
+        let err = load_json(&config).err().unwrap();
+        assert!(err.to_string().contains("Role Operator of staff 0 names unknown machine 1"));
+    }
+
+    #[test]
+    fn operators_learn_and_take_breaks() {
+        let config = serde_json::json!({
+            "items": 3,
+            "machines": [{ "id": 0, "name": "Press", "staff_required": 1 }],
+            "staff": [{
+                "id": 0,
+                "name": "New Hire",
+                "role": {
+                    "id": 0,
+                    "name": "Operator",
+                    "learning": { "rate": 0.5, "floor": 0.25 },
+                    "fatigue": { "per_hour": 0.0, "break_after": 30, "break_minutes": 10 }
+                }
+            }],
+            "processes": [{ "machine_id": 0, "duration": 20 }]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // 0-20 first item, 20-30 second (50% curve), break 30-40, 40-47 third (20 / 3 rounded)
+        assert_eq!(app.finished_goods, 3);
+        assert_eq!(app.production.staff[0].break_time, 10);
+        assert_eq!(app.production.simulator.elapsed_time(), 47);
+
+        // A floor of 0 would let durations shrink to nothing; above 1 practice would slow staff down
+        for floor in [0.0, 1.5] {
+            let mut config = config.clone();
+            config["staff"][0]["role"]["learning"]["floor"] = floor.into();
+            assert!(load_json(&config).err().unwrap().to_string().contains("learning floor"));
         }
+    }
 
-        // Run the simulation
-        logger.info("\n--- Running Simulation ---");
-        prod.simulator.run_all(|sim, event| match &event.event_type {
-            EventType::ProcessStart {
-                machine_id,
-                process_id,
-            } => {
-                logger.info(&format!(
-                    "Time {}: Process {} started on machine {}",
-                    sim.elapsed_time(),
-                    process_id,
-                    machine_id
-                ));
-            }
-            EventType::ProcessComplete {
-                machine_id,
-                process_id,
-            } => {
-                logger.info(&format!(
-                    "Time {}: Process {} completed on machine {}",
-                    sim.elapsed_time(),
-                    process_id,
-                    machine_id
-                ));
-            }
-            EventType::StaffAssigned {
-                staff_id,
-                machine_id,
-                ..
-            } => {
-                logger.info(&format!(
-                    "Time {}: Staff {} assigned to machine {}",
-                    sim.elapsed_time(),
-                    staff_id,
-                    machine_id
-                ));
-            }
-            EventType::StaffReleased {
-                staff_id,
-                machine_id,
-            } => {
-                logger.info(&format!(
-                    "Time {}: Staff {} released from machine {}",
-                    sim.elapsed_time(),
-                    staff_id,
-                    machine_id
-                ));
-            }
-            EventType::StaffUnavailable {
-                machine_id,
-                process_id,
-            } => {
-                logger.warning(&format!(
-                    "Time {}: Process {} DELAYED - no staff available for machine {}",
-                    sim.elapsed_time(),
-                    process_id,
-                    machine_id
-                ));
-            }
-            _ => {
-                logger.debug(&format!(
-                    "Time {}: {:?}",
-                    sim.elapsed_time(),
-                    event.event_type
-                ));
+    #[test]
+    fn floater_walks_between_buckets() {
+        let config = serde_json::json!({
+            "items": 1,
+            "machines": [
+                { "id": 0, "name": "Lathe", "staff_required": 1 },
+                { "id": 1, "name": "Mill", "staff_required": 1 }
+            ],
+            "staff": [{ "id": 0, "name": "Floater", "role": { "id": 0, "name": "Operator" } }],
+            "processes": [
+                { "machine_id": 0, "duration": 10 },
+                { "machine_id": 1, "duration": 10 }
+            ],
+            "layout": {
+                "walk_speed": 20,
+                "positions": [
+                    { "machine_id": 0, "x": 0, "y": 0 },
+                    { "machine_id": 1, "x": 30, "y": 40 }
+                ]
             }
         });
 
-        prod.finalize_idle_time(prod.simulator.elapsed_time());
-        logger.info("\n--- Final Status ---");
-        logger.info(&prod.get_status());
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // 50 units at 20 per minute rounds up to a 3 minute walk
+        assert_eq!(app.production.simulator.elapsed_time(), 23);
+        assert_eq!(app.production.staff[0].travel_time, 3);
+        assert!(run_summary(&app).contains("Walking: 3 mins"));
+        // The mill waits for the operator's walk and is busy only for the run
+        let mill = &app.production.machines[1].status_times;
+        assert_eq!(mill.minutes(MachineStatus::WaitingForStaff), 3);
+        assert_eq!(mill.minutes(MachineStatus::Busy), 10);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    #[test]
+    fn machine_goes_offline_after_cycle_count() {
+        let config = serde_json::json!({
+            "items": 4,
+            "machines": [{
+                "id": 0,
+                "name": "Oven",
+                "is_automated": true,
+                "maintenance": { "every_cycles": 2, "duration": 10 }
+            }],
+            "staff": [],
+            "processes": [{ "machine_id": 0, "duration": 10 }]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // Two items, a 10 minute PM, two more items
+        assert_eq!(app.production.simulator.elapsed_time(), 50);
+        let pm = &app.maintenance[&0];
+        assert_eq!(pm.completed, 1);
+        assert_eq!(pm.down_minutes, 10);
+        assert!(run_summary(&app).contains("Oven: 1 PMs | offline 10 mins"));
+    }
 
     #[test]
-    fn parse_config_path_supports_flags_and_positionals() {
-        let args = vec![
-            "assemblysim".to_string(),
-            "--config".to_string(),
-            "path/a.json".to_string(),
-        ];
-        assert_eq!(parse_config_path(&args), Some("path/a.json".to_string()));
+    fn calendar_maintenance_pauses_the_running_job() {
+        let config = serde_json::json!({
+            "items": 1,
+            "machines": [{
+                "id": 0,
+                "name": "Press",
+                "staff_required": 1,
+                "maintenance": {
+                    "every_minutes": 15,
+                    "duration": 5,
+                    "wait_for_job": false,
+                    "technicians": 1,
+                    "technician_role": 5
+                }
+            }],
+            "staff": [
+                { "id": 0, "name": "Operator", "role": { "id": 0, "name": "Operator" } },
+                { "id": 1, "name": "Tech", "role": { "id": 5, "name": "Maintenance Tech" } }
+            ],
+            "processes": [{ "machine_id": 0, "duration": 20 }]
+        });
 
-        let args = vec![
-            "assemblysim".to_string(),
-            "-c".to_string(),
-            "path/b.json".to_string(),
-        ];
-        assert_eq!(parse_config_path(&args), Some("path/b.json".to_string()));
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
 
-        let args = vec!["assemblysim".to_string(), "path/c.json".to_string()];
-        assert_eq!(parse_config_path(&args), Some("path/c.json".to_string()));
+        // 0-15 running, 15-20 maintenance, 20-25 the rest of the job
+        assert_eq!(app.finished_goods, 1);
+        assert_eq!(app.production.simulator.elapsed_time(), 25);
+        assert_eq!(app.maintenance[&0].down_minutes, 5);
+        assert_eq!(app.production.staff[0].idle_time, 0);
 
-        let args = vec!["assemblysim".to_string()];
-        assert_eq!(parse_config_path(&args), None);
+        // A role nobody has, or too few technicians, is a load error rather than a stall
+        let mut config = config;
+        config["machines"][0]["maintenance"]["technician_role"] = 6.into();
+        assert!(load_json(&config).err().unwrap().to_string().contains("no staff have"));
+        config["machines"][0]["maintenance"]["technician_role"] = 5.into();
+        config["machines"][0]["maintenance"]["technicians"] = 2.into();
+        assert!(load_json(&config).err().unwrap().to_string().contains("only 1 staff qualify"));
     }
 
     #[test]
-    fn load_simulation_from_config_builds_production_state() {
-        let logger = Logger::new(LogLevel::Error);
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
-        let path = std::env::temp_dir().join(format!("assemblysim_test_{}.json", timestamp));
+    fn maintenance_needs_exactly_one_trigger() {
+        let config = MaintenanceConfig {
+            every_minutes: Some(60),
+            every_cycles: Some(10),
+            every_run_hours: None,
+            duration: 5,
+            wait_for_job: true,
+            technicians: 0,
+            technician_role: None,
+        };
+        assert!(build_maintenance_plan(0, &config).is_err());
+    }
 
+    #[test]
+    fn run_is_costed_per_finished_good() {
         let config = serde_json::json!({
+            "items": 2,
             "machines": [{
                 "id": 0,
-                "name": "Cutter",
+                "name": "Press",
                 "staff_required": 1,
-                "count": 2
+                "operating_cost_per_hour": 60,
+                "fixed_cost_per_hour": 30
             }],
             "staff": [{
                 "id": 0,
-                "name": "Alex",
-                "role": {
-                    "id": 0,
-                    "name": "Operator",
-                    "machine_ids": []
-                }
+                "name": "Op",
+                "role": { "id": 0, "name": "Operator", "hourly_rate": 30, "overtime_multiplier": 2 }
             }],
             "processes": [{
                 "machine_id": 0,
-                "process_id": 5,
-                "duration": 12
+                "phases": [{ "kind": "setup", "duration": 10 }, { "kind": "run", "duration": 20, "staff": 1 }]
             }],
-            "items": 3
+            "cost": { "material_per_item": 5, "regular_hours": 0.5 }
         });
 
-        std::fs::write(&path, serde_json::to_string(&config).unwrap()).unwrap();
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+        assert_eq!(app.production.simulator.elapsed_time(), 60);
 
-        let loaded = load_simulation_from_config(path.to_str().unwrap(), &logger).unwrap();
-        assert_eq!(loaded.production.machines.len(), 2);
-        assert_eq!(loaded.machine_buckets.get(&0).unwrap().len(), 2);
-        assert_eq!(loaded.machine_to_bucket.get(&0), Some(&0));
-        assert_eq!(loaded.machine_to_bucket.get(&1), Some(&0));
-        assert_eq!(loaded.production.staff.len(), 1);
-        assert_eq!(loaded.steps.len(), 1);
-        assert_eq!(loaded.items, 3);
+        let cost = run_costs(&app).unwrap();
+        // Labor: 30 mins regular (15) + 30 mins at double rate (30)
+        assert!((cost.labor - 45.0).abs() < 1e-6);
+        assert!((cost.overtime - 15.0).abs() < 1e-6);
+        // Machine: running the whole hour (60) + fixed (30)
+        assert!((cost.machine_operating - 60.0).abs() < 1e-6);
+        assert!((cost.machine_fixed - 30.0).abs() < 1e-6);
+        assert!((cost.material - 10.0).abs() < 1e-6);
+        assert!((cost.per_good(2).unwrap() - 72.5).abs() < 1e-6);
+        // Two 10 minute setups: a third of machine and operator time
+        assert!((cost.setup - 45.0).abs() < 1e-6);
+    }
 
-        let _ = std::fs::remove_file(path);
+    #[test]
+    fn idle_shutdown_saves_energy_but_needs_warm_up() {
+        let config = serde_json::json!({
+            "items": 2,
+            "machines": [
+                { "id": 0, "name": "Cutter", "is_automated": true },
+                {
+                    "id": 1,
+                    "name": "Furnace",
+                    "is_automated": true,
+                    "power": { "idle": 30, "processing": 90, "setup": 60 },
+                    "idle_shutdown": { "after": 20, "restart": 15 }
+                }
+            ],
+            "staff": [],
+            "processes": [
+                { "machine_id": 0, "duration": 60 },
+                { "machine_id": 1, "duration": 20 }
+            ]
+        });
+
+        let loaded = load_json(&config).unwrap();
+        let mut app = build_app(loaded, "test".to_string());
+        run_to_completion(&mut app);
+
+        // Furnace: idle 0-20, off 20-60, warm-up 60-75, process 75-95, idle 95-115, off 115-120,
+        // warm-up 120-135, process 135-155
+        assert_eq!(app.production.simulator.elapsed_time(), 155);
+        let meter = &app.energy[&1];
+        assert_eq!(meter.shutdowns, 2);
+        assert!((meter.kwh_in(PowerState::Idle) - 20.0).abs() < 1e-9);
+        assert!((meter.kwh_in(PowerState::Setup) - 30.0).abs() < 1e-9);
+        assert!((meter.kwh_in(PowerState::Processing) - 60.0).abs() < 1e-9);
+        assert!(run_summary(&app).contains("Energy: 110.0 kWh | 55.00 kWh per finished good"));
+        assert_eq!(app.plant_load.iter().map(|&(_, kw)| kw).fold(0.0, f64::max), 90.0);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_run() {
+        let logger = Logger::new(LogLevel::Error);
+        for name in ["car_assembly.json", "bike_plant.json"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(name);
+            let runs: Vec<_> = (0..3)
+                .map(|_| {
+                    let loaded = load_simulation_from_config(path.to_str().unwrap(), &logger).unwrap();
+                    let mut app = build_app(loaded, name.to_string());
+                    run_to_completion(&mut app);
+                    let mut log: Vec<(u32, Vec<(u32, MachineStatus)>)> = app.status_log.clone().into_iter().collect();
+                    log.sort_by_key(|&(machine_id, _)| machine_id);
+                    (run_kpis(&app), log)
+                })
+                .collect();
+            assert_eq!(runs[0], runs[1], "{}", name);
+            assert_eq!(runs[0], runs[2], "{}", name);
+        }
+    }
+
+    #[test]
+    fn example_configs_run_to_completion() {
+        let logger = Logger::new(LogLevel::Error);
+        for name in ["test.json", "bakery_layout.json", "car_assembly.json", "wafer_fab.json", "bike_plant.json"] {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(name);
+            let loaded = load_simulation_from_config(path.to_str().unwrap(), &logger).unwrap();
+            let mut app = build_app(loaded, name.to_string());
+            run_to_completion(&mut app);
+            assert_eq!(items_done(&app), app.items, "{}", name);
+        }
     }
 }
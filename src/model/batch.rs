@@ -0,0 +1,187 @@
+//! Batch processing rules for machines that load many parts at once
+//!
+//! Furnaces, ovens and curing stations run a whole load in one cycle.
+//! A batch policy decides when a waiting load is big enough to start:
+//! - a full load (`max_size`) starts right away
+//! - once `min_size` parts wait, the machine holds the door open for up to
+//!   `max_wait` minutes hoping to fill up, then starts with what it has
+//! - only compatible parts (same recipe) share a load
+//!
+//! Sizes count parts, not queue entries: a transfer batch of several parts is
+//! loaded whole, and never pushes a load past `max_size`.
+
+/// Which parts may share one load
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchCompatibility {
+    /// Any parts can be loaded together
+    Any,
+    /// Only parts with the same recipe key are loaded together
+    SameRecipe,
+}
+
+/// Batch rule for a machine bucket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchPolicy {
+    pub min_size: usize,
+    pub max_size: usize,
+    /// Minutes to wait for a fuller load once `min_size` is reached (None = start at once)
+    pub max_wait: Option<u32>,
+    pub compatibility: BatchCompatibility,
+}
+
+/// A queued job (one or more parts) as seen by the batch policy
+#[derive(Debug, Clone, Copy)]
+pub struct BatchCandidate<'a> {
+    pub recipe: &'a str,
+    pub queued_at: u32,
+    /// Parts in the job
+    pub units: usize,
+}
+
+/// What a batch machine should do right now
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchDecision {
+    /// Load these queue positions (oldest first)
+    Start(Vec<usize>),
+    /// A load could start at this time if nothing else arrives
+    WaitUntil(u32),
+    /// Not enough compatible parts yet
+    Wait,
+}
+
+impl BatchPolicy {
+    pub fn new(min_size: usize, max_size: usize, max_wait: Option<u32>, compatibility: BatchCompatibility) -> Self {
+        let min_size = min_size.max(1);
+        BatchPolicy {
+            min_size,
+            max_size: max_size.max(min_size),
+            max_wait,
+            compatibility,
+        }
+    }
+
+    /// Pick the next load from the queue
+    ///
+    /// `flush` starts undersized loads; use it when no more parts can arrive.
+    pub fn decide(&self, candidates: &[BatchCandidate], now: u32, flush: bool) -> BatchDecision {
+        // Group queue positions by recipe, oldest part first
+        let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by_key(|&idx| (candidates[idx].queued_at, idx));
+        for idx in order {
+            let key = match self.compatibility {
+                BatchCompatibility::Any => "",
+                BatchCompatibility::SameRecipe => candidates[idx].recipe,
+            };
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, members)) => members.push(idx),
+                None => groups.push((key, vec![idx])),
+            }
+        }
+
+        let oldest = |members: &Vec<usize>| candidates[members[0]].queued_at;
+        let units = |members: &Vec<usize>| members.iter().map(|&idx| candidates[idx].units).sum::<usize>();
+        // Oldest jobs first, stopping before one that would overfill the load
+        let take = |members: &Vec<usize>| {
+            let mut load = Vec::new();
+            let mut size = 0;
+            for &idx in members {
+                let units = candidates[idx].units;
+                // A job is never split, so one larger than a load goes alone
+                if size + units > self.max_size && !load.is_empty() {
+                    break;
+                }
+                load.push(idx);
+                size += units;
+            }
+            load
+        };
+
+        // Groups are ordered by their oldest part, so the first match wins
+        if let Some((_, members)) = groups.iter().find(|(_, m)| units(m) >= self.max_size) {
+            return BatchDecision::Start(take(members));
+        }
+
+        let mut wake_at: Option<u32> = None;
+        for (_, members) in &groups {
+            if units(members) < self.min_size {
+                continue;
+            }
+            let ready_at = oldest(members) + self.max_wait.unwrap_or(0);
+            if ready_at <= now {
+                return BatchDecision::Start(take(members));
+            }
+            wake_at = Some(wake_at.map_or(ready_at, |t| t.min(ready_at)));
+        }
+
+        if flush {
+            if let Some((_, members)) = groups.first() {
+                return BatchDecision::Start(take(members));
+            }
+        }
+
+        match wake_at {
+            Some(time) => BatchDecision::WaitUntil(time),
+            None => BatchDecision::Wait,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(spec: &[(&'static str, u32)]) -> Vec<BatchCandidate<'static>> {
+        spec.iter()
+            .map(|&(recipe, queued_at)| BatchCandidate { recipe, queued_at, units: 1 })
+            .collect()
+    }
+
+    #[test]
+    fn full_load_starts_immediately() {
+        let policy = BatchPolicy::new(2, 3, Some(60), BatchCompatibility::Any);
+        let queue = parts(&[("a", 0), ("b", 1), ("a", 2), ("a", 3)]);
+        assert_eq!(policy.decide(&queue, 3, false), BatchDecision::Start(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn partial_load_waits_for_timeout() {
+        let policy = BatchPolicy::new(2, 5, Some(30), BatchCompatibility::Any);
+        let queue = parts(&[("a", 10), ("a", 15)]);
+        assert_eq!(policy.decide(&queue, 20, false), BatchDecision::WaitUntil(40));
+        assert_eq!(policy.decide(&queue, 40, false), BatchDecision::Start(vec![0, 1]));
+    }
+
+    #[test]
+    fn below_minimum_waits_unless_flushed() {
+        let policy = BatchPolicy::new(3, 5, None, BatchCompatibility::Any);
+        let queue = parts(&[("a", 0), ("a", 1)]);
+        assert_eq!(policy.decide(&queue, 100, false), BatchDecision::Wait);
+        assert_eq!(policy.decide(&queue, 100, true), BatchDecision::Start(vec![0, 1]));
+    }
+
+    #[test]
+    fn recipes_are_not_mixed() {
+        let policy = BatchPolicy::new(2, 4, None, BatchCompatibility::SameRecipe);
+        let queue = parts(&[("dry", 0), ("wet", 1), ("wet", 2), ("dry", 5)]);
+        // "dry" has the oldest part and reaches the minimum, so it loads first
+        assert_eq!(policy.decide(&queue, 5, false), BatchDecision::Start(vec![0, 3]));
+
+        let queue = parts(&[("dry", 0), ("wet", 1), ("wet", 2)]);
+        assert_eq!(policy.decide(&queue, 5, false), BatchDecision::Start(vec![1, 2]));
+    }
+
+    #[test]
+    fn loads_count_parts_not_jobs() {
+        let policy = BatchPolicy::new(4, 5, None, BatchCompatibility::Any);
+        let mut queue = parts(&[("a", 0), ("a", 1), ("a", 2)]);
+        queue[0].units = 3;
+        queue[1].units = 3;
+        // 6 parts wait: the second job would overfill the load of 5
+        assert_eq!(policy.decide(&queue, 2, false), BatchDecision::Start(vec![0]));
+        // One job of 4 parts meets the minimum of 4 on its own
+        queue[2].units = 4;
+        assert_eq!(policy.decide(&queue[2..], 2, false), BatchDecision::Start(vec![0]));
+        assert_eq!(policy.decide(&queue[1..2], 2, false), BatchDecision::Wait);
+    }
+}
@@ -0,0 +1,240 @@
+//! Bottleneck detection by the active period method
+//!
+//! A bucket is active while none of its machines is starved or blocked, so
+//! it has no spare capacity. At any moment the bucket with the longest active
+//! period is the bottleneck. Where the active periods of one bottleneck and
+//! the next overlap, the bottleneck is shifting between them. Both buckets get
+//! that time. Otherwise the bucket is the sole bottleneck.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// One uninterrupted stretch of a bucket being active
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivePeriod {
+    pub bucket_id: u32,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl ActivePeriod {
+    fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    /// Time both periods are active, if any
+    fn overlap(&self, other: &ActivePeriod) -> Option<(u32, u32)> {
+        let start = self.start.max(other.start);
+        let end = self.end.min(other.end);
+        (end > start).then_some((start, end))
+    }
+}
+
+/// Minutes a bucket was the sole or a shifting bottleneck
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BottleneckShare {
+    pub bucket_id: u32,
+    pub sole: u32,
+    pub shifting: u32,
+}
+
+impl BottleneckShare {
+    pub fn total(&self) -> u32 {
+        self.sole + self.shifting
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BottleneckDetector {
+    /// bucket id -> start of its running active period
+    open: HashMap<u32, u32>,
+    /// Finished active periods
+    pub periods: Vec<ActivePeriod>,
+}
+
+impl BottleneckDetector {
+    pub fn new() -> Self {
+        BottleneckDetector::default()
+    }
+
+    /// Note whether a bucket is active from `current_time` on
+    pub fn observe(&mut self, bucket_id: u32, active: bool, current_time: u32) {
+        match (self.open.get(&bucket_id).copied(), active) {
+            (None, true) => {
+                self.open.insert(bucket_id, current_time);
+            }
+            (Some(start), false) => {
+                self.open.remove(&bucket_id);
+                if current_time > start {
+                    self.periods.push(ActivePeriod {
+                        bucket_id,
+                        start,
+                        end: current_time,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Forget the periods so far; running ones count from `current_time` (end of the warm-up)
+    pub fn reset(&mut self, current_time: u32) {
+        self.periods.clear();
+        for start in self.open.values_mut() {
+            *start = current_time;
+        }
+    }
+
+    /// Every active period so far, running ones cut at `current_time`
+    pub fn periods_until(&self, current_time: u32) -> Vec<ActivePeriod> {
+        let mut periods = self.periods.clone();
+        periods.extend(
+            self.open
+                .iter()
+                .filter(|(_, &start)| current_time > start)
+                .map(|(&bucket_id, &start)| ActivePeriod {
+                    bucket_id,
+                    start,
+                    end: current_time,
+                }),
+        );
+        periods
+    }
+
+    /// Bucket with the longest running active period at `current_time`
+    pub fn current(&self, current_time: u32) -> Option<u32> {
+        self.open
+            .iter()
+            .filter(|(_, &start)| current_time > start)
+            .max_by_key(|(&bucket_id, &start)| (current_time - start, Reverse(bucket_id)))
+            .map(|(&bucket_id, _)| bucket_id)
+    }
+
+    /// Sole and shifting bottleneck minutes per bucket between `from` and `to`, most first
+    pub fn analyze(&self, from: u32, to: u32, current_time: u32) -> Vec<BottleneckShare> {
+        let periods = self.periods_until(current_time);
+        let mut cuts: Vec<u32> = periods
+            .iter()
+            .flat_map(|p| [p.start, p.end])
+            .chain([from, to])
+            .filter(|&t| t >= from && t <= to)
+            .collect();
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        // The bottleneck period through each stretch between period starts and ends
+        let mut sequence: Vec<usize> = Vec::new();
+        for cut in cuts.windows(2) {
+            let longest = periods
+                .iter()
+                .enumerate()
+                .filter(|(_, p)| p.start <= cut[0] && p.end >= cut[1])
+                .max_by_key(|(_, p)| (p.len(), Reverse(p.bucket_id)))
+                .map(|(idx, _)| idx);
+            if let Some(idx) = longest {
+                if sequence.last() != Some(&idx) {
+                    sequence.push(idx);
+                }
+            }
+        }
+
+        let clip = |start: u32, end: u32| end.min(to).saturating_sub(start.max(from));
+        let mut shares: HashMap<u32, BottleneckShare> = HashMap::new();
+        for (i, &idx) in sequence.iter().enumerate() {
+            let period = periods[idx];
+            let before = i
+                .checked_sub(1)
+                .and_then(|prev| periods[sequence[prev]].overlap(&period));
+            let after = sequence
+                .get(i + 1)
+                .and_then(|&next| period.overlap(&periods[next]));
+            let sole_start = before.map(|(_, end)| end).unwrap_or(period.start);
+            let sole_end = after.map(|(start, _)| start).unwrap_or(period.end);
+            let share = shares.entry(period.bucket_id).or_insert(BottleneckShare {
+                bucket_id: period.bucket_id,
+                ..Default::default()
+            });
+            if sole_end > sole_start {
+                share.sole += clip(sole_start, sole_end);
+            }
+            if let Some((start, end)) = after {
+                let minutes = clip(start, end);
+                share.shifting += minutes;
+                let next = periods[sequence[i + 1]].bucket_id;
+                shares
+                    .entry(next)
+                    .or_insert(BottleneckShare {
+                        bucket_id: next,
+                        ..Default::default()
+                    })
+                    .shifting += minutes;
+            }
+        }
+
+        let mut shares: Vec<BottleneckShare> = shares.into_values().filter(|s| s.total() > 0).collect();
+        shares.sort_by_key(|s| (Reverse(s.total()), Reverse(s.sole), s.bucket_id));
+        shares
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector(periods: &[(u32, u32, u32)]) -> BottleneckDetector {
+        let mut detector = BottleneckDetector::new();
+        detector.periods = periods
+            .iter()
+            .map(|&(bucket_id, start, end)| ActivePeriod { bucket_id, start, end })
+            .collect();
+        detector
+    }
+
+    #[test]
+    fn observed_activity_becomes_periods() {
+        let mut detector = BottleneckDetector::new();
+        detector.observe(0, true, 0);
+        detector.observe(1, true, 5);
+        detector.observe(0, true, 8);
+        assert_eq!(detector.current(10), Some(0));
+        detector.observe(0, false, 12);
+        assert_eq!(detector.current(20), Some(1));
+        assert_eq!(
+            detector.periods,
+            vec![ActivePeriod {
+                bucket_id: 0,
+                start: 0,
+                end: 12
+            }]
+        );
+        assert_eq!(detector.periods_until(20).len(), 2);
+    }
+
+    #[test]
+    fn overlapping_bottlenecks_share_the_shift() {
+        // Bucket 0 active 0-60, bucket 1 active 50-100; bucket 2 briefly 10-20
+        let detector = detector(&[(0, 0, 60), (1, 50, 100), (2, 10, 20)]);
+        let shares = detector.analyze(0, 100, 100);
+        assert_eq!(
+            shares,
+            vec![
+                BottleneckShare {
+                    bucket_id: 0,
+                    sole: 50,
+                    shifting: 10
+                },
+                BottleneckShare {
+                    bucket_id: 1,
+                    sole: 40,
+                    shifting: 10
+                },
+            ]
+        );
+
+        // A window sees only its part
+        let shares = detector.analyze(55, 100, 100);
+        assert_eq!(shares[0].bucket_id, 1);
+        assert_eq!((shares[0].sole, shares[0].shifting), (40, 5));
+        assert_eq!((shares[1].sole, shares[1].shifting), (0, 5));
+    }
+}
@@ -0,0 +1,233 @@
+//! Side-by-side comparison of scenarios over replications
+//!
+//! Each scenario runs several times with different seeds. Every KPI is shown
+//! as its mean over the replications, with its change from the first
+//! (baseline) scenario. A Welch t-test at the 95% level marks the changes
+//! that are more than replication noise. Replications that stalled before
+//! every item was done are left out of the means and counted separately.
+
+/// Mean and sample standard deviation of one KPI over replications
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub n: usize,
+}
+
+impl Estimate {
+    pub fn new(values: &[f64]) -> Self {
+        let n = values.len();
+        let mean = if n > 0 { values.iter().sum::<f64>() / n as f64 } else { 0.0 };
+        let std_dev = if n > 1 {
+            (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        Estimate { mean, std_dev, n }
+    }
+
+    /// Whether the means differ at the 95% level (Welch's t-test)
+    pub fn differs_from(&self, other: &Estimate) -> bool {
+        if self.n < 2 || other.n < 2 {
+            return false;
+        }
+        let a = self.std_dev.powi(2) / self.n as f64;
+        let b = other.std_dev.powi(2) / other.n as f64;
+        let diff = (self.mean - other.mean).abs();
+        if a + b == 0.0 {
+            // No noise at all: any change is real
+            return diff > 1e-9;
+        }
+        let t = diff / (a + b).sqrt();
+        let df = (a + b).powi(2) / (a.powi(2) / (self.n - 1) as f64 + b.powi(2) / (other.n - 1) as f64);
+        t > t_critical_95(df)
+    }
+}
+
+/// Two-sided 95% critical value of Student's t for `df` degrees of freedom (rounded down)
+pub fn t_critical_95(df: f64) -> f64 {
+    const TABLE: [f64; 30] = [
+        12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131,
+        2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+    ];
+    let df = df.floor().max(1.0) as usize;
+    match df {
+        1..=30 => TABLE[df - 1],
+        31..=60 => 2.000,
+        61..=120 => 1.980,
+        _ => 1.960,
+    }
+}
+
+/// (KPI name, value) pairs of one run, or None if it stalled
+pub type RunKpis<'a> = Option<Vec<(&'a str, f64)>>;
+
+/// One KPI across the scenarios
+#[derive(Debug, Clone, PartialEq)]
+pub struct KpiComparison {
+    pub name: String,
+    /// One per scenario; None when the scenario has no completed run reporting this KPI
+    pub estimates: Vec<Option<Estimate>>,
+}
+
+impl KpiComparison {
+    /// Change of scenario `idx` from the baseline, and whether it is significant
+    pub fn delta(&self, idx: usize) -> Option<(f64, bool)> {
+        let (base, other) = (self.estimates[0].as_ref()?, self.estimates[idx].as_ref()?);
+        Some((other.mean - base.mean, other.differs_from(base)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub scenarios: Vec<String>,
+    pub kpis: Vec<KpiComparison>,
+    /// Replications per scenario that stalled, left out of the estimates
+    pub stalled: Vec<usize>,
+}
+
+impl Comparison {
+    /// `runs[scenario][replication]`; KPIs are matched by name, and a scenario need not report all of them
+    pub fn new(scenarios: Vec<String>, runs: &[Vec<RunKpis>]) -> Self {
+        let mut names: Vec<&str> = Vec::new();
+        for kpis in runs.iter().flatten().flatten() {
+            for &(name, _) in kpis {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        let kpis = names
+            .iter()
+            .map(|&name| KpiComparison {
+                name: name.to_string(),
+                estimates: runs
+                    .iter()
+                    .map(|reps| {
+                        let values: Vec<f64> = reps
+                            .iter()
+                            .flatten()
+                            .filter_map(|kpis| kpis.iter().find(|&&(n, _)| n == name).map(|&(_, v)| v))
+                            .collect();
+                        (!values.is_empty()).then(|| Estimate::new(&values))
+                    })
+                    .collect(),
+            })
+            .collect();
+        let stalled = runs.iter().map(|reps| reps.iter().filter(|kpis| kpis.is_none()).count()).collect();
+        Comparison {
+            scenarios,
+            kpis,
+            stalled,
+        }
+    }
+
+    /// Plain text table: a mean column per scenario, then its delta from the baseline
+    pub fn to_table(&self) -> String {
+        let mut header = vec!["KPI".to_string()];
+        for (idx, scenario) in self.scenarios.iter().enumerate() {
+            header.push(scenario.clone());
+            if idx > 0 {
+                header.push("delta".to_string());
+            }
+        }
+        let mut rows = vec![header];
+        for kpi in &self.kpis {
+            let mut row = vec![kpi.name.clone()];
+            for (idx, estimate) in kpi.estimates.iter().enumerate() {
+                row.push(match estimate {
+                    Some(estimate) => format!("{:.2} ±{:.2}", estimate.mean, estimate.std_dev),
+                    None => "n/a".to_string(),
+                });
+                if idx > 0 {
+                    row.push(match kpi.delta(idx) {
+                        Some((delta, significant)) => format!("{:+.2}{}", delta, if significant { " *" } else { "" }),
+                        None => "n/a".to_string(),
+                    });
+                }
+            }
+            rows.push(row);
+        }
+
+        let widths: Vec<usize> = (0..rows[0].len())
+            .map(|col| rows.iter().map(|row| row[col].chars().count()).max().unwrap_or(0))
+            .collect();
+        let mut table = String::new();
+        for row in &rows {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .enumerate()
+                .map(|(col, (cell, &width))| {
+                    if col == 0 {
+                        format!("{:<width$}", cell)
+                    } else {
+                        format!("{:>width$}", cell)
+                    }
+                })
+                .collect();
+            table.push_str(cells.join("  ").trim_end());
+            table.push('\n');
+        }
+        table.push_str("Mean ±std dev over replications; * = differs from the first scenario (Welch t-test, 95%)\n");
+        for (scenario, &stalled) in self.scenarios.iter().zip(&self.stalled) {
+            if stalled > 0 {
+                table.push_str(&format!(
+                    "{}: {} replication(s) stalled before every item was done and are left out\n",
+                    scenario, stalled
+                ));
+            }
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn welch_test_separates_signal_from_noise() {
+        let base = Estimate::new(&[10.0, 11.0, 9.0, 10.0]);
+        assert_eq!(base.mean, 10.0);
+        assert!((base.std_dev - (2.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert!(Estimate::new(&[14.0, 15.0, 13.0, 14.0]).differs_from(&base));
+        assert!(!Estimate::new(&[10.5, 11.5, 9.0, 10.0]).differs_from(&base));
+        // Deterministic runs: any change counts, none doesn't
+        assert!(Estimate::new(&[5.0, 5.0]).differs_from(&Estimate::new(&[6.0, 6.0])));
+        assert!(!Estimate::new(&[5.0, 5.0]).differs_from(&Estimate::new(&[5.0, 5.0])));
+        assert!(!Estimate::new(&[5.0]).differs_from(&Estimate::new(&[9.0])));
+    }
+
+    #[test]
+    fn table_shows_means_and_marked_deltas() {
+        let runs = vec![
+            vec![Some(vec![("Throughput", 4.0)]), Some(vec![("Throughput", 4.0)])],
+            vec![Some(vec![("Throughput", 6.0)]), Some(vec![("Throughput", 6.0)])],
+        ];
+        let comparison = Comparison::new(vec!["a.json".to_string(), "b.json".to_string()], &runs);
+        assert_eq!(comparison.kpis[0].delta(1), Some((2.0, true)));
+        let table = comparison.to_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "KPI             a.json      b.json    delta");
+        assert_eq!(lines[1], "Throughput  4.00 ±0.00  6.00 ±0.00  +2.00 *");
+    }
+
+    #[test]
+    fn kpis_are_matched_by_name_and_stalled_runs_left_out() {
+        let runs = vec![
+            vec![Some(vec![("Makespan", 30.0), ("Cost", 100.0)])],
+            vec![Some(vec![("Makespan", 20.0)]), None],
+        ];
+        let comparison = Comparison::new(vec!["a.json".to_string(), "b.json".to_string()], &runs);
+        assert_eq!(comparison.kpis.len(), 2);
+        assert_eq!(comparison.kpis[0].delta(1), Some((-10.0, false)));
+        assert_eq!(comparison.kpis[1].estimates[1], None);
+        assert_eq!(comparison.kpis[1].delta(1), None);
+        assert_eq!(comparison.stalled, vec![0, 1]);
+        let table = comparison.to_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[2], "Cost      100.00 ±0.00          n/a     n/a");
+        assert_eq!(lines[4], "b.json: 1 replication(s) stalled before every item was done and are left out");
+    }
+}
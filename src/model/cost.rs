@@ -0,0 +1,219 @@
+//! Cost accounting for a finished run
+//!
+//! Money is worked out once at the end from the time each machine and staff
+//! member spent busy, in setup or idle:
+//! - labor: every staff member is paid for the whole run at their role's
+//!   hourly rate, with an overtime multiplier past the regular hours
+//! - machines: operating cost per hour while running or in setup, plus a
+//!   fixed cost per hour over the whole run (depreciation, floor space)
+//! - material per item that left the line, plus a disposal cost per scrapped item
+//!
+//! Labor and machine cost are also split by what the time was spent on.
+
+use std::collections::HashMap;
+
+/// Pay for one role
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LaborRate {
+    pub hourly: f64,
+    /// Pay factor for time past the regular hours (1.5 = time and a half)
+    pub overtime_multiplier: f64,
+}
+
+/// Running costs for one machine bucket (per machine)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MachineRates {
+    /// Per hour while processing or in setup
+    pub operating_per_hour: f64,
+    /// Per hour over the whole run, whether the machine runs or not
+    pub fixed_per_hour: f64,
+}
+
+/// How one machine spent the run, in minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MachineUsage {
+    pub bucket_id: u32,
+    pub busy: u32,
+    pub setup: u32,
+    pub idle: u32,
+}
+
+/// How one staff member spent the run, in minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaffUsage {
+    pub role_id: u32,
+    pub busy: u32,
+    pub setup: u32,
+    pub idle: u32,
+}
+
+/// All prices for a run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostModel {
+    pub labor_rates: HashMap<u32, LaborRate>,
+    pub machine_rates: HashMap<u32, MachineRates>,
+    pub material_per_item: f64,
+    /// Extra cost per scrapped item (disposal), on top of its lost material
+    pub scrap_cost: f64,
+    /// Minutes per person paid at the regular rate
+    pub regular_minutes: u32,
+}
+
+/// Result of costing a run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostBreakdown {
+    pub labor: f64,
+    /// Overtime premium (already part of `labor`)
+    pub overtime: f64,
+    pub machine_operating: f64,
+    pub machine_fixed: f64,
+    pub material: f64,
+    pub scrap: f64,
+    /// Labor and machine cost by what the time was spent on
+    pub busy: f64,
+    pub setup: f64,
+    pub idle: f64,
+}
+
+impl CostBreakdown {
+    pub fn total(&self) -> f64 {
+        self.labor + self.machine_operating + self.machine_fixed + self.material + self.scrap
+    }
+
+    /// Total cost divided over the finished goods (None if nothing finished)
+    pub fn per_good(&self, finished: u32) -> Option<f64> {
+        if finished == 0 {
+            None
+        } else {
+            Some(self.total() / finished as f64)
+        }
+    }
+}
+
+impl CostModel {
+    /// Cost a run of `elapsed` minutes
+    pub fn breakdown(
+        &self,
+        machines: &[MachineUsage],
+        staff: &[StaffUsage],
+        items_out: u32,
+        scrapped: u32,
+        elapsed: u32,
+    ) -> CostBreakdown {
+        let mut result = CostBreakdown::default();
+
+        for person in staff {
+            let Some(rate) = self.labor_rates.get(&person.role_id) else { continue };
+            let regular = elapsed.min(self.regular_minutes) as f64 / 60.0;
+            let extra = elapsed.saturating_sub(self.regular_minutes) as f64 / 60.0;
+            let premium = extra * rate.hourly * (rate.overtime_multiplier - 1.0);
+            let pay = (regular + extra) * rate.hourly + premium;
+            result.labor += pay;
+            result.overtime += premium;
+            split(&mut result, pay, person.busy, person.setup, person.idle);
+        }
+
+        for machine in machines {
+            let Some(rates) = self.machine_rates.get(&machine.bucket_id) else { continue };
+            let busy = machine.busy as f64 / 60.0 * rates.operating_per_hour;
+            let setup = machine.setup as f64 / 60.0 * rates.operating_per_hour;
+            let fixed = elapsed as f64 / 60.0 * rates.fixed_per_hour;
+            result.machine_operating += busy + setup;
+            result.machine_fixed += fixed;
+            result.busy += busy;
+            result.setup += setup;
+            split(&mut result, fixed, machine.busy, machine.setup, machine.idle);
+        }
+
+        result.material = items_out as f64 * self.material_per_item;
+        result.scrap = scrapped as f64 * self.scrap_cost;
+        result
+    }
+}
+
+/// Share `amount` over busy/setup/idle by minutes
+fn split(result: &mut CostBreakdown, amount: f64, busy: u32, setup: u32, idle: u32) {
+    let total = (busy + setup + idle) as f64;
+    if total <= 0.0 {
+        result.idle += amount;
+        return;
+    }
+    result.busy += amount * busy as f64 / total;
+    result.setup += amount * setup as f64 / total;
+    result.idle += amount * idle as f64 / total;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn overtime_is_paid_past_regular_hours() {
+        let mut model = CostModel {
+            regular_minutes: 480,
+            ..CostModel::default()
+        };
+        model.labor_rates.insert(
+            0,
+            LaborRate {
+                hourly: 20.0,
+                overtime_multiplier: 1.5,
+            },
+        );
+        let staff = [StaffUsage {
+            role_id: 0,
+            busy: 360,
+            setup: 0,
+            idle: 240,
+        }];
+        let cost = model.breakdown(&[], &staff, 0, 0, 600);
+
+        // 8h * 20 + 2h * 30
+        assert!(close(cost.labor, 220.0));
+        assert!(close(cost.overtime, 20.0));
+        assert!(close(cost.busy, 132.0));
+        assert!(close(cost.idle, 88.0));
+    }
+
+    #[test]
+    fn machine_cost_splits_by_state() {
+        let mut model = CostModel::default();
+        model.machine_rates.insert(
+            3,
+            MachineRates {
+                operating_per_hour: 60.0,
+                fixed_per_hour: 12.0,
+            },
+        );
+        let machines = [MachineUsage {
+            bucket_id: 3,
+            busy: 30,
+            setup: 10,
+            idle: 20,
+        }];
+        let cost = model.breakdown(&machines, &[], 0, 0, 60);
+
+        assert!(close(cost.machine_operating, 40.0));
+        assert!(close(cost.machine_fixed, 12.0));
+        assert!(close(cost.busy, 36.0));
+        assert!(close(cost.setup, 12.0));
+        assert!(close(cost.idle, 4.0));
+    }
+
+    #[test]
+    fn material_and_scrap_are_per_item() {
+        let model = CostModel {
+            material_per_item: 5.0,
+            scrap_cost: 2.0,
+            ..CostModel::default()
+        };
+        let cost = model.breakdown(&[], &[], 10, 2, 60);
+        assert!(close(cost.total(), 54.0));
+        assert!(close(cost.per_good(8).unwrap(), 6.75));
+        assert_eq!(cost.per_good(0), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Energy use per machine state
+//!
+//! Each machine has a power draw (kW) for every state it can be in. A meter
+//! integrates that draw over time. An optional idle shutdown policy switches a
+//! machine off once it has been idle long enough. The next job then starts
+//! with a warm-up at setup power.
+
+/// What a machine is doing, as far as power draw is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerState {
+    Off,
+    Idle,
+    Processing,
+    Setup,
+    Down,
+}
+
+impl PowerState {
+    pub const ALL: [PowerState; 5] = [
+        PowerState::Off,
+        PowerState::Idle,
+        PowerState::Processing,
+        PowerState::Setup,
+        PowerState::Down,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PowerState::Off => "off",
+            PowerState::Idle => "idle",
+            PowerState::Processing => "processing",
+            PowerState::Setup => "setup",
+            PowerState::Down => "down",
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            PowerState::Off => 0,
+            PowerState::Idle => 1,
+            PowerState::Processing => 2,
+            PowerState::Setup => 3,
+            PowerState::Down => 4,
+        }
+    }
+}
+
+/// Power draw in kW per state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PowerProfile {
+    pub off: f64,
+    pub idle: f64,
+    pub processing: f64,
+    pub setup: f64,
+    pub down: f64,
+}
+
+impl PowerProfile {
+    pub fn draw(&self, state: PowerState) -> f64 {
+        match state {
+            PowerState::Off => self.off,
+            PowerState::Idle => self.idle,
+            PowerState::Processing => self.processing,
+            PowerState::Setup => self.setup,
+            PowerState::Down => self.down,
+        }
+    }
+}
+
+/// Switch a machine off after it has been idle for a while
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleShutdown {
+    /// Idle minutes before the machine is switched off
+    pub after: u32,
+    /// Warm-up minutes (at setup power) before the next job can run
+    pub restart: u32,
+}
+
+/// Energy meter for one machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyMeter {
+    pub profile: PowerProfile,
+    pub shutdown: Option<IdleShutdown>,
+    state: PowerState,
+    /// When the current state began
+    since: u32,
+    /// Time energy has been counted up to
+    counted_until: u32,
+    kwh_by_state: [f64; 5],
+    /// Times the idle shutdown switched the machine off
+    pub shutdowns: u32,
+}
+
+impl EnergyMeter {
+    pub fn new(profile: PowerProfile, shutdown: Option<IdleShutdown>) -> Self {
+        EnergyMeter {
+            profile,
+            shutdown,
+            state: PowerState::Idle,
+            since: 0,
+            counted_until: 0,
+            kwh_by_state: [0.0; 5],
+            shutdowns: 0,
+        }
+    }
+
+    /// State at `current_time`, counting an idle shutdown that has come due
+    pub fn state_at(&self, current_time: u32) -> PowerState {
+        match (self.state, self.shutdown) {
+            (PowerState::Idle, Some(policy)) if current_time >= self.since + policy.after => PowerState::Off,
+            (state, _) => state,
+        }
+    }
+
+    /// Current draw in kW
+    pub fn power_at(&self, current_time: u32) -> f64 {
+        self.profile.draw(self.state_at(current_time))
+    }
+
+    /// Count energy up to `current_time`
+    pub fn accumulate_until(&mut self, current_time: u32) {
+        if current_time <= self.counted_until {
+            return;
+        }
+        if let (PowerState::Idle, Some(policy)) = (self.state, self.shutdown) {
+            let off_at = self.since + policy.after;
+            if current_time >= off_at {
+                self.add(PowerState::Idle, off_at.max(self.counted_until));
+                self.state = PowerState::Off;
+                self.since = off_at;
+                self.shutdowns += 1;
+            }
+        }
+        self.add(self.state, current_time);
+    }
+
+    fn add(&mut self, state: PowerState, until: u32) {
+        let minutes = until.saturating_sub(self.counted_until);
+        self.kwh_by_state[state.index()] += self.profile.draw(state) * minutes as f64 / 60.0;
+        self.counted_until = self.counted_until.max(until);
+    }
+
+    /// Switch to a new state at `current_time`
+    pub fn set_state(&mut self, state: PowerState, current_time: u32) {
+        self.accumulate_until(current_time);
+        let current = self.state;
+        // An idle machine staying idle keeps its idle clock; an off machine stays off
+        if state == current || (state == PowerState::Idle && current == PowerState::Off) {
+            return;
+        }
+        self.state = state;
+        self.since = current_time;
+    }
+
+    /// Start counting afresh at `current_time` (end of the warm-up)
+    pub fn reset_statistics(&mut self, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.kwh_by_state = [0.0; 5];
+        self.shutdowns = 0;
+    }
+
+    pub fn kwh(&self) -> f64 {
+        self.kwh_by_state.iter().sum()
+    }
+
+    pub fn kwh_in(&self, state: PowerState) -> f64 {
+        self.kwh_by_state[state.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> PowerProfile {
+        PowerProfile {
+            off: 0.0,
+            idle: 6.0,
+            processing: 30.0,
+            setup: 12.0,
+            down: 1.0,
+        }
+    }
+
+    #[test]
+    fn meter_integrates_power_by_state() {
+        let mut meter = EnergyMeter::new(profile(), None);
+        meter.set_state(PowerState::Setup, 10);
+        meter.set_state(PowerState::Processing, 20);
+        meter.set_state(PowerState::Idle, 80);
+        meter.accumulate_until(90);
+
+        assert!((meter.kwh_in(PowerState::Idle) - 2.0).abs() < 1e-9);
+        assert!((meter.kwh_in(PowerState::Setup) - 2.0).abs() < 1e-9);
+        assert!((meter.kwh_in(PowerState::Processing) - 30.0).abs() < 1e-9);
+        assert!((meter.kwh() - 34.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn idle_machine_switches_off() {
+        let mut meter = EnergyMeter::new(profile(), Some(IdleShutdown { after: 20, restart: 5 }));
+        assert_eq!(meter.state_at(19), PowerState::Idle);
+        assert_eq!(meter.state_at(20), PowerState::Off);
+
+        meter.accumulate_until(10);
+        meter.accumulate_until(60);
+        assert!((meter.kwh_in(PowerState::Idle) - 2.0).abs() < 1e-9);
+        assert_eq!(meter.shutdowns, 1);
+
+        // Reporting idle again does not restart the idle clock
+        meter.set_state(PowerState::Idle, 70);
+        assert_eq!(meter.state_at(70), PowerState::Off);
+        meter.set_state(PowerState::Setup, 80);
+        assert_eq!(meter.state_at(80), PowerState::Setup);
+    }
+}
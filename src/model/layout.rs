@@ -0,0 +1,77 @@
+//! Plant layout for staff walking between machine buckets
+//!
+//! Buckets can be placed on a floor plan (walking time = straight-line
+//! distance / walking speed) and individual pairs can be overridden with a
+//! measured travel time. Buckets the layout does not know are reached instantly.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Layout {
+    positions: HashMap<u32, (f64, f64)>,
+    travel_times: HashMap<(u32, u32), u32>,
+    /// Distance units walked per minute
+    pub walk_speed: f64,
+}
+
+impl Layout {
+    pub fn new(walk_speed: f64) -> Self {
+        Layout {
+            walk_speed,
+            ..Layout::default()
+        }
+    }
+
+    /// Place a bucket on the floor plan
+    pub fn set_position(&mut self, bucket_id: u32, x: f64, y: f64) {
+        self.positions.insert(bucket_id, (x, y));
+    }
+
+    /// Fixed walking time between two buckets (both directions)
+    pub fn set_travel_time(&mut self, from: u32, to: u32, minutes: u32) {
+        self.travel_times.insert((from, to), minutes);
+        self.travel_times.insert((to, from), minutes);
+    }
+
+    /// Minutes to walk from one bucket to another
+    pub fn walk_time(&self, from: u32, to: u32) -> u32 {
+        if from == to {
+            return 0;
+        }
+        if let Some(&minutes) = self.travel_times.get(&(from, to)) {
+            return minutes;
+        }
+        match (self.positions.get(&from), self.positions.get(&to)) {
+            (Some(&(x1, y1)), Some(&(x2, y2))) if self.walk_speed > 0.0 => {
+                ((x2 - x1).hypot(y2 - y1) / self.walk_speed).ceil() as u32
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walk_time_uses_distance_over_speed() {
+        let mut layout = Layout::new(2.0);
+        layout.set_position(0, 0.0, 0.0);
+        layout.set_position(1, 6.0, 8.0);
+        assert_eq!(layout.walk_time(0, 1), 5);
+        assert_eq!(layout.walk_time(1, 0), 5);
+        assert_eq!(layout.walk_time(1, 1), 0);
+        assert_eq!(layout.walk_time(0, 7), 0);
+    }
+
+    #[test]
+    fn travel_time_overrides_positions() {
+        let mut layout = Layout::new(1.0);
+        layout.set_position(0, 0.0, 0.0);
+        layout.set_position(1, 100.0, 0.0);
+        layout.set_travel_time(1, 0, 3);
+        assert_eq!(layout.walk_time(0, 1), 3);
+        assert_eq!(layout.walk_time(1, 0), 3);
+    }
+}
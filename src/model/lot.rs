@@ -0,0 +1,162 @@
+//! Lot sizes and transfer batches
+//!
+//! A line releases its items in lots, all at once or one lot every so often.
+//! A lot moves through the steps in transfer batches. A step can pass its
+//! first batch on while it is still working on the rest (overlapped
+//! production). The step's per-lot time is spent on the first transfer batch
+//! of a lot it sees; later batches only take time per unit.
+//!
+//! The tracker follows units from release until they leave the line, for
+//! lot cycle times and work in progress.
+
+use std::collections::HashMap;
+
+/// How a line releases its items
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LotPolicy {
+    /// Items per lot
+    pub size: u32,
+    /// Items moving between steps together (at most `size`)
+    pub transfer_batch: u32,
+    /// Minutes between lot releases (0 = every lot at the start)
+    pub release_every: u32,
+}
+
+impl Default for LotPolicy {
+    fn default() -> Self {
+        LotPolicy {
+            size: 1,
+            transfer_batch: 1,
+            release_every: 0,
+        }
+    }
+}
+
+impl LotPolicy {
+    /// Lots for `items` items: release time and transfer batch sizes of each
+    pub fn plan(&self, items: u32) -> Vec<(u32, Vec<u32>)> {
+        let size = self.size.max(1);
+        let batch = self.transfer_batch.clamp(1, size);
+        let mut lots = Vec::new();
+        let mut left = items;
+        while left > 0 {
+            let lot = left.min(size);
+            left -= lot;
+            let mut batches = vec![batch; (lot / batch) as usize];
+            if !lot.is_multiple_of(batch) {
+                batches.push(lot % batch);
+            }
+            lots.push((lots.len() as u32 * self.release_every, batches));
+        }
+        lots
+    }
+}
+
+/// Cycle times and work in progress by lot
+#[derive(Debug, Clone, Default)]
+pub struct LotTracker {
+    /// lot id -> (release time, units not yet out)
+    open: HashMap<u32, (u32, u32)>,
+    /// Lots whose every unit has left the line
+    pub completed: u32,
+    /// Minutes from release until the last unit left, per completed lot
+    pub cycle_times: Vec<u32>,
+    /// Units released and not yet out
+    pub wip: u32,
+    pub peak_wip: u32,
+    wip_minutes: u64,
+    last_change: u32,
+}
+
+impl LotTracker {
+    pub fn new() -> Self {
+        LotTracker::default()
+    }
+
+    pub fn release(&mut self, lot_id: u32, units: u32, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.open.insert(lot_id, (current_time, units));
+        self.wip += units;
+        self.peak_wip = self.peak_wip.max(self.wip);
+    }
+
+    /// Units of a lot left the line (finished, scrapped or put into a store)
+    pub fn exit(&mut self, lot_id: u32, units: u32, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.wip = self.wip.saturating_sub(units);
+        let Some((released_at, left)) = self.open.get_mut(&lot_id) else { return };
+        *left = left.saturating_sub(units);
+        if *left == 0 {
+            self.cycle_times.push(current_time.saturating_sub(*released_at));
+            self.completed += 1;
+            self.open.remove(&lot_id);
+        }
+    }
+
+    /// Start counting afresh at `current_time` (end of the warm-up); open lots stay open
+    pub fn reset_statistics(&mut self, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.completed = 0;
+        self.cycle_times.clear();
+        self.wip_minutes = 0;
+        self.peak_wip = self.wip;
+    }
+
+    /// Mean minutes from release until a lot's last unit left
+    pub fn average_cycle_time(&self) -> Option<f64> {
+        if self.completed == 0 {
+            None
+        } else {
+            Some(self.cycle_times.iter().map(|&t| t as f64).sum::<f64>() / self.completed as f64)
+        }
+    }
+
+    /// Mean units in progress over `elapsed` minutes of statistics
+    pub fn average_wip(&self, elapsed: u32) -> f64 {
+        if elapsed > 0 {
+            self.wip_minutes as f64 / elapsed as f64
+        } else {
+            0.0
+        }
+    }
+
+    pub fn accumulate_until(&mut self, current_time: u32) {
+        if current_time > self.last_change {
+            self.wip_minutes += self.wip as u64 * (current_time - self.last_change) as u64;
+            self.last_change = current_time;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lots_split_into_transfer_batches() {
+        let policy = LotPolicy {
+            size: 5,
+            transfer_batch: 2,
+            release_every: 30,
+        };
+        let plan = policy.plan(7);
+        assert_eq!(plan, vec![(0, vec![2, 2, 1]), (30, vec![2])]);
+        assert_eq!(LotPolicy::default().plan(2), vec![(0, vec![1]), (0, vec![1])]);
+    }
+
+    #[test]
+    fn tracker_measures_cycle_time_and_wip() {
+        let mut tracker = LotTracker::new();
+        tracker.release(0, 4, 0);
+        tracker.release(1, 4, 10);
+        tracker.exit(0, 2, 20);
+        tracker.exit(0, 2, 30);
+        tracker.exit(1, 4, 40);
+
+        assert_eq!(tracker.completed, 2);
+        assert_eq!(tracker.average_cycle_time(), Some(30.0));
+        assert_eq!(tracker.peak_wip, 8);
+        // 4*10 + 8*10 + 6*10 + 4*10 unit-minutes over 40
+        assert!((tracker.average_wip(40) - 5.5).abs() < 1e-9);
+    }
+}
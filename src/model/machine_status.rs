@@ -0,0 +1,215 @@
+//! Where a machine's time goes
+//!
+//! A machine is always in exactly one status. Busy and setup are time spent on
+//! jobs. The idle time is split by cause, because the causes need different
+//! fixes:
+//! - starved: nothing to work on (empty queue, missing material, batch filling)
+//!   and more work has to come from upstream;
+//! - waiting for staff: work is there but nobody can run it, so more people or
+//!   other staffing is needed;
+//! - blocked: work is there but a shared resource or partner machine is taken;
+//! - down: in or waiting for maintenance.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MachineStatus {
+    Busy,
+    Setup,
+    Starved,
+    Blocked,
+    WaitingForStaff,
+    Down,
+}
+
+impl MachineStatus {
+    pub const ALL: [MachineStatus; 6] = [
+        MachineStatus::Busy,
+        MachineStatus::Setup,
+        MachineStatus::Starved,
+        MachineStatus::Blocked,
+        MachineStatus::WaitingForStaff,
+        MachineStatus::Down,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MachineStatus::Busy => "busy",
+            MachineStatus::Setup => "setup",
+            MachineStatus::Starved => "starved",
+            MachineStatus::Blocked => "blocked",
+            MachineStatus::WaitingForStaff => "waiting for staff",
+            MachineStatus::Down => "down",
+        }
+    }
+
+    /// Working, or held up by its own staffing or maintenance (not by its neighbours)
+    pub fn is_active(&self) -> bool {
+        !matches!(self, MachineStatus::Starved | MachineStatus::Blocked)
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            MachineStatus::Busy => 0,
+            MachineStatus::Setup => 1,
+            MachineStatus::Starved => 2,
+            MachineStatus::Blocked => 3,
+            MachineStatus::WaitingForStaff => 4,
+            MachineStatus::Down => 5,
+        }
+    }
+}
+
+/// Why an idle machine is not running a job
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitReason {
+    /// Nothing queued
+    NextJob,
+    /// Staff to start a job, or for one of its phases ("load", "unload", ...)
+    Staff(Option<&'static str>),
+    Maintenance,
+    /// Maintenance is due but no technician is free
+    Technician,
+    /// A resource pool, by name
+    Resource(String),
+    /// A raw material or store, by name
+    Material(String),
+    /// A machine the step also uses, by bucket name
+    Machine(String),
+    /// A batch machine collecting a load
+    BatchFill { waiting: usize, max_size: usize },
+}
+
+impl WaitReason {
+    /// Status of a machine waiting for this
+    pub fn status(&self) -> MachineStatus {
+        match self {
+            WaitReason::NextJob | WaitReason::Material(_) | WaitReason::BatchFill { .. } => MachineStatus::Starved,
+            WaitReason::Staff(_) => MachineStatus::WaitingForStaff,
+            WaitReason::Maintenance | WaitReason::Technician => MachineStatus::Down,
+            WaitReason::Resource(_) | WaitReason::Machine(_) => MachineStatus::Blocked,
+        }
+    }
+}
+
+impl fmt::Display for WaitReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WaitReason::NextJob => write!(f, "Next process"),
+            WaitReason::Staff(None) => write!(f, "Staff"),
+            WaitReason::Staff(Some(phase)) => write!(f, "Staff ({})", phase),
+            WaitReason::Maintenance => write!(f, "Maintenance"),
+            WaitReason::Technician => write!(f, "Maintenance technician"),
+            WaitReason::Resource(name) => write!(f, "Resource: {}", name),
+            WaitReason::Material(name) => write!(f, "Material: {}", name),
+            WaitReason::Machine(name) => write!(f, "Machine: {}", name),
+            WaitReason::BatchFill { waiting, max_size } => {
+                write!(f, "Batch fill ({}/{})", waiting.min(max_size), max_size)
+            }
+        }
+    }
+}
+
+/// Minutes a machine (or a bucket of machines) spent in each status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusTimes {
+    pub current: MachineStatus,
+    minutes: [u32; 6],
+    since: u32,
+}
+
+impl Default for StatusTimes {
+    fn default() -> Self {
+        StatusTimes {
+            current: MachineStatus::Starved,
+            minutes: [0; 6],
+            since: 0,
+        }
+    }
+}
+
+impl StatusTimes {
+    pub fn new() -> Self {
+        StatusTimes::default()
+    }
+
+    /// Switch to `status` at `current_time`
+    pub fn set(&mut self, status: MachineStatus, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.current = status;
+    }
+
+    pub fn accumulate_until(&mut self, current_time: u32) {
+        if current_time > self.since {
+            self.minutes[self.current.index()] += current_time - self.since;
+            self.since = current_time;
+        }
+    }
+
+    /// Start counting afresh at `current_time` (end of the warm-up)
+    pub fn reset(&mut self, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.minutes = [0; 6];
+    }
+
+    pub fn minutes(&self, status: MachineStatus) -> u32 {
+        self.minutes[status.index()]
+    }
+
+    pub fn total(&self) -> u32 {
+        self.minutes.iter().sum()
+    }
+
+    /// Fraction of the recorded time spent in `status`
+    pub fn share(&self, status: MachineStatus) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.minutes(status) as f64 / total as f64,
+        }
+    }
+
+    /// Add another machine's minutes (bucket totals)
+    pub fn add(&mut self, other: &StatusTimes) {
+        for (mine, theirs) in self.minutes.iter_mut().zip(other.minutes) {
+            *mine += theirs;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_goes_to_the_current_status() {
+        let mut times = StatusTimes::new();
+        times.set(MachineStatus::Busy, 10);
+        times.set(MachineStatus::WaitingForStaff, 25);
+        times.set(MachineStatus::Busy, 30);
+        times.accumulate_until(40);
+
+        assert_eq!(times.minutes(MachineStatus::Starved), 10);
+        assert_eq!(times.minutes(MachineStatus::Busy), 25);
+        assert_eq!(times.minutes(MachineStatus::WaitingForStaff), 5);
+        assert_eq!(times.total(), 40);
+        assert!((times.share(MachineStatus::Busy) - 0.625).abs() < 1e-9);
+
+        let mut bucket = StatusTimes::new();
+        bucket.add(&times);
+        bucket.add(&times);
+        assert_eq!(bucket.minutes(MachineStatus::Busy), 50);
+    }
+
+    #[test]
+    fn wait_reasons_split_idle_time_by_cause() {
+        assert_eq!(WaitReason::NextJob.status(), MachineStatus::Starved);
+        assert_eq!(WaitReason::Staff(Some("load")).status(), MachineStatus::WaitingForStaff);
+        assert_eq!(WaitReason::Resource("Jig".to_string()).status(), MachineStatus::Blocked);
+        assert_eq!(WaitReason::Technician.status(), MachineStatus::Down);
+        assert_eq!(WaitReason::Staff(Some("load")).to_string(), "Staff (load)");
+        assert_eq!(
+            WaitReason::BatchFill { waiting: 5, max_size: 4 }.to_string(),
+            "Batch fill (4/4)"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Preventive maintenance (PM) that takes machines offline
+//!
+//! Each machine in a bucket with a maintenance plan tracks its own counters.
+//! A PM becomes due by calendar interval, by cycles run or by run time.
+//! The machine stops taking new jobs from then on. Depending on the plan, it
+//! either finishes its current job first or is stopped mid-job, and the job
+//! resumes after the PM. Plans can require maintenance technicians.
+
+/// When a machine is due for maintenance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaintenanceTrigger {
+    /// Every `every` minutes of simulated time
+    Calendar { every: u32 },
+    /// After every `every` completed processes
+    Cycles { every: u32 },
+    /// After every `every` minutes the machine spent processing
+    RunTime { every: u32 },
+}
+
+/// Preventive maintenance plan for a machine bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaintenancePlan {
+    pub trigger: MaintenanceTrigger,
+    /// Minutes the machine is offline per PM
+    pub duration: u32,
+    /// Let the running job finish first (false = stop it and resume after the PM)
+    pub wait_for_job: bool,
+    /// Technicians needed for the whole PM
+    pub technicians: u32,
+    /// Only staff with this role id count as technicians (None = any qualified staff)
+    pub technician_role: Option<u32>,
+}
+
+/// Maintenance state of one machine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceState {
+    pub plan: MaintenancePlan,
+    cycles: u32,
+    run_minutes: u32,
+    /// Time the current PM became due (None = not due)
+    pub due_since: Option<u32>,
+    /// Time the current PM started (None = not in maintenance)
+    pub started_at: Option<u32>,
+    pub completed: u32,
+    /// Minutes spent in maintenance
+    pub down_minutes: u32,
+    /// Minutes between a PM becoming due and starting
+    pub delay_minutes: u32,
+}
+
+impl MaintenanceState {
+    pub fn new(plan: MaintenancePlan) -> Self {
+        MaintenanceState {
+            plan,
+            cycles: 0,
+            run_minutes: 0,
+            due_since: None,
+            started_at: None,
+            completed: 0,
+            down_minutes: 0,
+            delay_minutes: 0,
+        }
+    }
+
+    /// Machine may not start new jobs
+    pub fn blocks_jobs(&self) -> bool {
+        self.due_since.is_some() || self.started_at.is_some()
+    }
+
+    /// Count a finished process; returns true if that made a PM due
+    pub fn record_process(&mut self, run_minutes: u32, current_time: u32) -> bool {
+        self.cycles += 1;
+        self.run_minutes += run_minutes;
+        let reached = match self.plan.trigger {
+            MaintenanceTrigger::Calendar { .. } => false,
+            MaintenanceTrigger::Cycles { every } => self.cycles >= every,
+            MaintenanceTrigger::RunTime { every } => self.run_minutes >= every,
+        };
+        reached && self.mark_due(current_time)
+    }
+
+    /// A calendar PM fell due; returns false if one is already pending
+    pub fn mark_due(&mut self, current_time: u32) -> bool {
+        if self.blocks_jobs() {
+            return false;
+        }
+        self.due_since = Some(current_time);
+        true
+    }
+
+    pub fn start(&mut self, current_time: u32) {
+        if let Some(since) = self.due_since.take() {
+            self.delay_minutes += current_time.saturating_sub(since);
+        }
+        self.started_at = Some(current_time);
+    }
+
+    /// Clear the counters reported (end of the warm-up); the trigger keeps counting
+    pub fn reset_statistics(&mut self) {
+        self.completed = 0;
+        self.down_minutes = 0;
+        self.delay_minutes = 0;
+    }
+
+    pub fn finish(&mut self, current_time: u32) {
+        if let Some(start) = self.started_at.take() {
+            self.down_minutes += current_time.saturating_sub(start);
+            self.completed += 1;
+        }
+        self.cycles = 0;
+        self.run_minutes = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(trigger: MaintenanceTrigger) -> MaintenancePlan {
+        MaintenancePlan {
+            trigger,
+            duration: 30,
+            wait_for_job: true,
+            technicians: 0,
+            technician_role: None,
+        }
+    }
+
+    #[test]
+    fn cycle_count_triggers_and_resets() {
+        let mut state = MaintenanceState::new(plan(MaintenanceTrigger::Cycles { every: 2 }));
+        assert!(!state.record_process(10, 10));
+        assert!(state.record_process(10, 20));
+        assert!(state.blocks_jobs());
+
+        state.start(25);
+        state.finish(55);
+        assert!(!state.blocks_jobs());
+        assert_eq!(state.completed, 1);
+        assert_eq!(state.down_minutes, 30);
+        assert_eq!(state.delay_minutes, 5);
+        assert!(!state.record_process(10, 60));
+    }
+
+    #[test]
+    fn run_time_triggers_on_processing_minutes() {
+        let mut state = MaintenanceState::new(plan(MaintenanceTrigger::RunTime { every: 60 }));
+        assert!(!state.record_process(45, 45));
+        assert!(state.record_process(20, 65));
+    }
+
+    #[test]
+    fn calendar_due_is_not_doubled() {
+        let mut state = MaintenanceState::new(plan(MaintenanceTrigger::Calendar { every: 100 }));
+        assert!(!state.record_process(500, 10));
+        assert!(state.mark_due(100));
+        assert!(!state.mark_due(200));
+    }
+}
@@ -1,5 +1,257 @@
+//! Raw materials, stock on hand and supplier replenishment
+//!
+//! Steps take material from stock when they start. Each material is restocked
+//! by its replenishment policy:
+//! - (s, S): once stock on hand plus on order falls to s, order up to S
+//! - reorder point: once it falls to the point, order a fixed quantity
+//! - scheduled: a fixed quantity arrives at a fixed interval, whatever the stock
+//!
+//! Orders arrive after the material's lead time. A step that finds too little
+//! stock waits for the next delivery. Stores between production lines are
+//! materials too; the feeding line fills them instead of a supplier.
+
+/// How a material is restocked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Replenishment {
+    /// Never restocked; only the opening stock is used
+    None,
+    /// (s, S): order up to `order_up_to` once the position is at or below `reorder_at`
+    MinMax { reorder_at: u32, order_up_to: u32 },
+    /// Order `quantity` (as often as needed) once the position is at or below `reorder_at`
+    ReorderPoint { reorder_at: u32, quantity: u32 },
+    /// `quantity` arrives every `every` minutes
+    Scheduled { every: u32, quantity: u32 },
+}
+
+/// An order or scheduled delivery on its way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delivery {
+    pub arrives_at: u32,
+    pub quantity: u32,
+}
+
+/// A raw material with its stock and supplier
+#[derive(Debug, Clone, PartialEq)]
 pub struct Material {
+    pub id: u32,
     pub name: String,
+    pub on_hand: u32,
+    pub policy: Replenishment,
+    /// Minutes from placing an order to its arrival
+    pub lead_time: u32,
+    /// Deliveries on the way, oldest first
+    pub deliveries: Vec<Delivery>,
+    pub consumed: u32,
+    pub received: u32,
+    /// Orders placed (scheduled deliveries count too)
+    pub orders: u32,
+    /// Lowest stock on hand seen
+    pub lowest: u32,
+    /// Times a job could not start because this material ran short
+    pub wait_count: u32,
+    /// Minutes with nothing on hand
+    pub stockout_minutes: u32,
+    /// Unit-minutes on hand (integral of `on_hand` over time)
+    stock_minutes: u64,
+    last_change: u32,
+}
+
+impl Material {
+    pub fn new(id: u32, name: &str, on_hand: u32) -> Self {
+        Material {
+            id,
+            name: name.to_string(),
+            on_hand,
+            policy: Replenishment::None,
+            lead_time: 0,
+            deliveries: Vec::new(),
+            consumed: 0,
+            received: 0,
+            orders: 0,
+            lowest: on_hand,
+            wait_count: 0,
+            stockout_minutes: 0,
+            stock_minutes: 0,
+            last_change: 0,
+        }
+    }
+
+    pub fn on_order(&self) -> u32 {
+        self.deliveries.iter().map(|d| d.quantity).sum()
+    }
+
+    /// Add stock-on-hand time up to `current_time`
+    pub fn accumulate_until(&mut self, current_time: u32) {
+        if current_time > self.last_change {
+            let minutes = current_time - self.last_change;
+            self.stock_minutes += self.on_hand as u64 * minutes as u64;
+            if self.on_hand == 0 {
+                self.stockout_minutes += minutes;
+            }
+            self.last_change = current_time;
+        }
+    }
+
+    /// Start counting afresh at `current_time` (end of the warm-up)
+    pub fn reset_statistics(&mut self, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.consumed = 0;
+        self.received = 0;
+        self.orders = 0;
+        self.lowest = self.on_hand;
+        self.wait_count = 0;
+        self.stockout_minutes = 0;
+        self.stock_minutes = 0;
+    }
+
+    /// Average stock on hand over `elapsed` minutes of statistics
+    pub fn average_on_hand(&self, elapsed: u32) -> f64 {
+        if elapsed > 0 {
+            self.stock_minutes as f64 / elapsed as f64
+        } else {
+            self.on_hand as f64
+        }
+    }
+
+    /// Take `quantity` from stock; false (and nothing taken) if there is too little
+    pub fn consume(&mut self, quantity: u32, current_time: u32) -> bool {
+        if self.on_hand < quantity {
+            return false;
+        }
+        self.accumulate_until(current_time);
+        self.on_hand -= quantity;
+        self.consumed += quantity;
+        self.lowest = self.lowest.min(self.on_hand);
+        true
+    }
+
+    /// Place an order if the policy calls for one; returns its arrival time
+    pub fn replenish(&mut self, current_time: u32) -> Option<u32> {
+        let position = self.on_hand + self.on_order();
+        let (quantity, arrives_at) = match self.policy {
+            Replenishment::None => return None,
+            Replenishment::MinMax {
+                reorder_at,
+                order_up_to,
+            } => {
+                if position > reorder_at || order_up_to <= position {
+                    return None;
+                }
+                (order_up_to - position, current_time + self.lead_time)
+            }
+            Replenishment::ReorderPoint { reorder_at, quantity } => {
+                if position > reorder_at || quantity == 0 {
+                    return None;
+                }
+                // Enough lots to lift the position above the reorder point
+                let lots = (reorder_at - position) / quantity + 1;
+                (lots * quantity, current_time + self.lead_time)
+            }
+            Replenishment::Scheduled { every, quantity } => {
+                // The next delivery is booked once the previous one has arrived
+                if !self.deliveries.is_empty() {
+                    return None;
+                }
+                (quantity, current_time + every)
+            }
+        };
+        self.deliveries.push(Delivery { arrives_at, quantity });
+        self.deliveries.sort_by_key(|d| d.arrives_at);
+        self.orders += 1;
+        Some(arrives_at)
+    }
+
+    /// Put units made inside the plant (another line's output) into stock
+    pub fn put_away(&mut self, quantity: u32, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.on_hand += quantity;
+        self.received += quantity;
+    }
+
+    /// Put every delivery due by `current_time` into stock; returns the units received
+    pub fn receive_due(&mut self, current_time: u32) -> u32 {
+        self.accumulate_until(current_time);
+        let due = self.deliveries.iter().take_while(|d| d.arrives_at <= current_time).count();
+        let quantity: u32 = self.deliveries.drain(..due).map(|d| d.quantity).sum();
+        self.put_away(quantity, current_time);
+        quantity
+    }
+}
+
+/// One requirement of a step: `quantity` units of material `material_id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaterialNeed {
+    pub material_id: u32,
+    pub quantity: u32,
+}
+
+/// All raw materials in the plant
+#[derive(Debug, Clone, Default)]
+pub struct Stockroom {
+    pub materials: Vec<Material>,
+}
+
+impl Stockroom {
+    pub fn new() -> Self {
+        Stockroom::default()
+    }
+
+    pub fn add_material(&mut self, material: Material) {
+        self.materials.push(material);
+    }
+
+    pub fn get(&self, material_id: u32) -> Option<&Material> {
+        self.materials.iter().find(|m| m.id == material_id)
+    }
+
+    pub fn get_mut(&mut self, material_id: u32) -> Option<&mut Material> {
+        self.materials.iter_mut().find(|m| m.id == material_id)
+    }
+
+    /// First requirement that stock on hand cannot cover, if any
+    pub fn first_shortage(&self, needs: &[MaterialNeed]) -> Option<u32> {
+        needs
+            .iter()
+            .find(|need| {
+                self.get(need.material_id)
+                    .map(|material| material.on_hand < need.quantity)
+                    .unwrap_or(true)
+            })
+            .map(|need| need.material_id)
+    }
+
+    /// Count a job that started waiting for a material
+    pub fn note_wait(&mut self, material_id: u32) {
+        if let Some(material) = self.get_mut(material_id) {
+            material.wait_count += 1;
+        }
+    }
+
+    /// Take every requirement, or nothing at all
+    pub fn try_consume(&mut self, needs: &[MaterialNeed], current_time: u32) -> bool {
+        if self.first_shortage(needs).is_some() {
+            return false;
+        }
+        for need in needs {
+            if let Some(material) = self.get_mut(need.material_id) {
+                material.consume(need.quantity, current_time);
+            }
+        }
+        true
+    }
+
+    /// Bring stock counters up to `current_time`
+    pub fn accumulate_until(&mut self, current_time: u32) {
+        for material in &mut self.materials {
+            material.accumulate_until(current_time);
+        }
+    }
+
+    pub fn reset_statistics(&mut self, current_time: u32) {
+        for material in &mut self.materials {
+            material.reset_statistics(current_time);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -8,9 +260,83 @@ mod tests {
 
     #[test]
     fn material_keeps_name() {
-        let material = Material {
-            name: "Steel Sheet".to_string(),
-        };
+        let material = Material::new(0, "Steel Sheet", 0);
         assert_eq!(material.name, "Steel Sheet");
     }
+
+    #[test]
+    fn min_max_orders_up_to_the_maximum() {
+        let mut material = Material::new(0, "Bolts", 12);
+        material.policy = Replenishment::MinMax {
+            reorder_at: 5,
+            order_up_to: 20,
+        };
+        material.lead_time = 30;
+
+        assert!(material.consume(6, 10));
+        assert_eq!(material.replenish(10), None);
+        assert!(material.consume(2, 20));
+        assert_eq!(material.replenish(20), Some(50));
+        assert_eq!(material.on_order(), 16);
+        // Stock on order counts, so no second order
+        assert!(material.consume(4, 30));
+        assert_eq!(material.replenish(30), None);
+
+        assert_eq!(material.receive_due(49), 0);
+        assert_eq!(material.receive_due(50), 16);
+        assert_eq!(material.on_hand, 16);
+        assert_eq!(material.lowest, 0);
+        assert_eq!(material.stockout_minutes, 20);
+    }
+
+    #[test]
+    fn reorder_point_orders_whole_lots() {
+        let mut material = Material::new(0, "Panels", 3);
+        material.policy = Replenishment::ReorderPoint {
+            reorder_at: 10,
+            quantity: 4,
+        };
+        assert_eq!(material.replenish(0), Some(0));
+        // 3 + 2 lots of 4 lifts the position to 11
+        assert_eq!(material.on_order(), 8);
+    }
+
+    #[test]
+    fn scheduled_deliveries_repeat_after_arrival() {
+        let mut material = Material::new(0, "Resin", 0);
+        material.policy = Replenishment::Scheduled { every: 60, quantity: 10 };
+        assert_eq!(material.replenish(0), Some(60));
+        assert_eq!(material.replenish(5), None);
+        assert_eq!(material.receive_due(60), 10);
+        assert_eq!(material.replenish(60), Some(120));
+        assert_eq!(material.orders, 2);
+    }
+
+    #[test]
+    fn stockroom_takes_all_or_nothing() {
+        let mut stock = Stockroom::new();
+        stock.add_material(Material::new(0, "Frame", 2));
+        stock.add_material(Material::new(1, "Screw", 3));
+        let needs = [
+            MaterialNeed {
+                material_id: 0,
+                quantity: 1,
+            },
+            MaterialNeed {
+                material_id: 1,
+                quantity: 4,
+            },
+        ];
+        assert_eq!(stock.first_shortage(&needs), Some(1));
+        assert!(!stock.try_consume(&needs, 0));
+        assert_eq!(stock.get(0).unwrap().on_hand, 2);
+    }
+
+    #[test]
+    fn average_stock_is_time_weighted() {
+        let mut material = Material::new(0, "Glue", 10);
+        material.consume(10, 30);
+        material.accumulate_until(60);
+        assert!((material.average_on_hand(60) - 5.0).abs() < 1e-9);
+    }
 }
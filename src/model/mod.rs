@@ -1,9 +1,28 @@
 /// The model module contains all core simulation structures
+pub mod batch;
+pub mod bottleneck;
+pub mod compare;
+pub mod cost;
+pub mod energy;
+pub mod layout;
+pub mod lot;
 pub mod machine;
+pub mod machine_status;
+pub mod maintenance;
 pub mod material;
+pub mod oee;
+pub mod optimize;
 pub mod process;
 pub mod production_line;
+pub mod quality;
+pub mod random;
+pub mod report;
+pub mod resource;
+pub mod sampler;
 pub mod staff;
+pub mod sweep;
 pub mod time;
+pub mod transport;
+pub mod warmup;
 pub mod simulation_example;
 pub mod staff_scheduling;
@@ -0,0 +1,82 @@
+//! Overall equipment effectiveness (OEE)
+//!
+//! OEE is availability × performance × quality, for one machine or a bucket:
+//! - availability: operating time (everything but downtime) out of the
+//!   observed time. Breakdowns and planned downtime such as maintenance are
+//!   the availability losses.
+//! - performance: the ideal minutes for the work done (the steps' nominal
+//!   times) out of operating time. Staff slower than nominal lower it, and so
+//!   do starving, blocking and waiting for staff, as idling.
+//! - quality: units that passed the step's check out of units processed.
+//!
+//! A factor with nothing to measure (no operating time, no units) is None,
+//! and so is the OEE built from it.
+
+/// Work done on a machine, for performance and quality
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OeeCounters {
+    /// Nominal minutes of the jobs completed
+    pub ideal_minutes: u32,
+    pub units: u32,
+    pub good: u32,
+}
+
+impl OeeCounters {
+    pub fn record(&mut self, ideal_minutes: u32, units: u32, good: u32) {
+        self.ideal_minutes += ideal_minutes;
+        self.units += units;
+        self.good += good;
+    }
+
+    /// Add another machine's counters (bucket totals)
+    pub fn add(&mut self, other: &OeeCounters) {
+        self.record(other.ideal_minutes, other.units, other.good);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Oee {
+    pub availability: Option<f64>,
+    pub performance: Option<f64>,
+    pub quality: Option<f64>,
+}
+
+impl Oee {
+    /// `down_minutes` covers breakdowns and planned downtime within `observed_minutes`
+    pub fn new(observed_minutes: u32, down_minutes: u32, counters: &OeeCounters) -> Self {
+        let operating = observed_minutes.saturating_sub(down_minutes);
+        Oee {
+            availability: (observed_minutes > 0).then(|| operating as f64 / observed_minutes as f64),
+            performance: (operating > 0).then(|| counters.ideal_minutes as f64 / operating as f64),
+            quality: (counters.units > 0).then(|| counters.good as f64 / counters.units as f64),
+        }
+    }
+
+    pub fn oee(&self) -> Option<f64> {
+        Some(self.availability? * self.performance? * self.quality?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oee_is_the_product_of_its_factors() {
+        let mut counters = OeeCounters::default();
+        counters.record(30, 4, 3);
+        counters.record(6, 1, 1);
+        // 10 of 50 minutes down, 36 ideal minutes in the 40 operating, 4 of 5 good
+        let oee = Oee::new(50, 10, &counters);
+        assert!((oee.availability.unwrap() - 0.8).abs() < 1e-9);
+        assert!((oee.performance.unwrap() - 0.9).abs() < 1e-9);
+        assert!((oee.quality.unwrap() - 0.8).abs() < 1e-9);
+        assert!((oee.oee().unwrap() - 0.576).abs() < 1e-9);
+
+        // An idle machine has no quality to measure, so no OEE either
+        let idle = Oee::new(50, 0, &OeeCounters::default());
+        assert_eq!((idle.availability, idle.performance, idle.quality), (Some(1.0), Some(0.0), None));
+        assert_eq!(idle.oee(), None);
+        assert_eq!(Oee::new(0, 0, &counters).availability, None);
+    }
+}
@@ -0,0 +1,208 @@
+//! Search for the cheapest machine and staff counts that meet a target
+//!
+//! Each dimension is a count (machines in a bucket, staff in a role) with
+//! bounds and a cost per unit. The caller scores a point by its shortfall
+//! against the target: 0 when the target is met, growing the further off it
+//! is, and infinite when the point can't be run at all. Each point is
+//! simulated only once.
+//!
+//! The greedy search starts from the minimum counts, and keeps adding the unit
+//! that closes the most shortfall per unit cost. When no single unit helps, it
+//! adds the cheapest one. Once the target is met, it drops units the target
+//! does not need. Simulated annealing can then look further from the greedy
+//! result for a cheaper point.
+
+use std::collections::HashMap;
+
+use super::random::SimRng;
+
+/// One count being searched over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dimension {
+    pub min: u32,
+    pub max: u32,
+    pub unit_cost: f64,
+}
+
+pub struct Search<F> {
+    dimensions: Vec<Dimension>,
+    evaluate: F,
+    cache: HashMap<Vec<u32>, f64>,
+}
+
+impl<F: FnMut(&[u32]) -> f64> Search<F> {
+    /// `evaluate` returns the shortfall of a point (0 = target met)
+    pub fn new(dimensions: Vec<Dimension>, evaluate: F) -> Self {
+        Search {
+            dimensions,
+            evaluate,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn cost(&self, point: &[u32]) -> f64 {
+        point
+            .iter()
+            .zip(&self.dimensions)
+            .map(|(&count, dim)| count as f64 * dim.unit_cost)
+            .sum()
+    }
+
+    /// Points simulated so far
+    pub fn evaluations(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn shortfall(&mut self, point: &[u32]) -> f64 {
+        if let Some(&shortfall) = self.cache.get(point) {
+            return shortfall;
+        }
+        let shortfall = (self.evaluate)(point).max(0.0);
+        self.cache.insert(point.to_vec(), shortfall);
+        shortfall
+    }
+
+    /// None if even the maximum counts miss the target
+    pub fn greedy(&mut self) -> Option<Vec<u32>> {
+        let mut point: Vec<u32> = self.dimensions.iter().map(|d| d.min).collect();
+        let mut shortfall = self.shortfall(&point);
+        while shortfall > 0.0 {
+            let mut best: Option<(f64, f64, usize, f64)> = None; // (gain per cost, unit cost, dim, shortfall)
+            for dim in 0..self.dimensions.len() {
+                if point[dim] >= self.dimensions[dim].max {
+                    continue;
+                }
+                point[dim] += 1;
+                let next = self.shortfall(&point);
+                point[dim] -= 1;
+                let unit_cost = self.dimensions[dim].unit_cost;
+                // Two stalled points are equally far off (and infinity minus infinity is NaN)
+                let gain = if next == shortfall { 0.0 } else { shortfall - next };
+                let score = gain / unit_cost.max(1e-9);
+                let better = match best {
+                    None => true,
+                    Some((best_score, best_cost, _, _)) => {
+                        score > best_score + 1e-12 || ((score - best_score).abs() <= 1e-12 && unit_cost < best_cost)
+                    }
+                };
+                if better {
+                    best = Some((score, unit_cost, dim, next));
+                }
+            }
+            let (score, _, dim, next) = best?;
+            if score <= 0.0 {
+                // No single unit helps; add the cheapest one and look again
+                let dim = (0..self.dimensions.len())
+                    .filter(|&d| point[d] < self.dimensions[d].max)
+                    .min_by(|&a, &b| self.dimensions[a].unit_cost.total_cmp(&self.dimensions[b].unit_cost))?;
+                point[dim] += 1;
+                shortfall = self.shortfall(&point);
+            } else {
+                point[dim] += 1;
+                shortfall = next;
+            }
+        }
+        self.prune(&mut point);
+        Some(point)
+    }
+
+    /// Drop units, dearest first, while the target is still met
+    fn prune(&mut self, point: &mut [u32]) {
+        let mut order: Vec<usize> = (0..self.dimensions.len()).collect();
+        order.sort_by(|&a, &b| self.dimensions[b].unit_cost.total_cmp(&self.dimensions[a].unit_cost));
+        loop {
+            let mut dropped = false;
+            for &dim in &order {
+                if point[dim] <= self.dimensions[dim].min {
+                    continue;
+                }
+                point[dim] -= 1;
+                if self.shortfall(point) > 0.0 {
+                    point[dim] += 1;
+                } else {
+                    dropped = true;
+                }
+            }
+            if !dropped {
+                break;
+            }
+        }
+    }
+
+    /// Simulated annealing from `start`; the cheapest point found that meets the target
+    pub fn anneal(&mut self, start: Vec<u32>, iterations: u32, rng: &mut SimRng) -> Option<Vec<u32>> {
+        let max_cost = self.cost(&self.dimensions.iter().map(|d| d.max).collect::<Vec<_>>());
+        // A point that misses the target never beats one that meets it
+        let penalty = max_cost.max(1.0) * 10.0;
+        let mut current = start;
+        let mut current_score = self.cost(&current) + penalty * self.shortfall(&current);
+        let mut best = (self.shortfall(&current) == 0.0).then(|| current.clone());
+        let initial = self.dimensions.iter().map(|d| d.unit_cost).fold(1.0, f64::max);
+        for i in 0..iterations {
+            let temperature = initial * 0.01f64.powf(i as f64 / iterations.max(1) as f64);
+            let dim = (rng.next_u64() % self.dimensions.len() as u64) as usize;
+            let mut next = current.clone();
+            if rng.chance(0.5) {
+                if next[dim] >= self.dimensions[dim].max {
+                    continue;
+                }
+                next[dim] += 1;
+            } else {
+                if next[dim] <= self.dimensions[dim].min {
+                    continue;
+                }
+                next[dim] -= 1;
+            }
+            let shortfall = self.shortfall(&next);
+            let score = self.cost(&next) + penalty * shortfall;
+            if score <= current_score || rng.next_f64() < (-(score - current_score) / temperature).exp() {
+                if shortfall == 0.0 && best.as_ref().is_none_or(|b| self.cost(&next) < self.cost(b)) {
+                    best = Some(next.clone());
+                }
+                current = next;
+                current_score = score;
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Output is limited by the scarcer of two resources giving 2 and 3 units each
+    fn shortfall(point: &[u32]) -> f64 {
+        let output = (point[0] * 2).min(point[1] * 3) as f64;
+        ((6.0 - output) / 6.0).max(0.0)
+    }
+
+    fn dimensions() -> Vec<Dimension> {
+        vec![
+            Dimension { min: 1, max: 6, unit_cost: 1.0 },
+            Dimension { min: 1, max: 6, unit_cost: 1.0 },
+        ]
+    }
+
+    #[test]
+    fn greedy_adds_what_helps_most_then_trims() {
+        let mut search = Search::new(dimensions(), shortfall);
+        assert_eq!(search.greedy(), Some(vec![3, 2]));
+        assert!(search.evaluations() < 36);
+
+        let mut search = Search::new(dimensions(), |_: &[u32]| 1.0);
+        assert_eq!(search.greedy(), None);
+
+        // Points that can't run at all are skipped over, not taken as met
+        let mut search = Search::new(dimensions(), |p: &[u32]| if p[1] < 3 { f64::INFINITY } else { shortfall(p) });
+        assert_eq!(search.greedy(), Some(vec![3, 3]));
+    }
+
+    #[test]
+    fn annealing_finds_a_cheaper_point() {
+        let mut search = Search::new(dimensions(), shortfall);
+        let best = search.anneal(vec![6, 6], 400, &mut SimRng::new(3)).unwrap();
+        assert_eq!(search.cost(&best), 5.0);
+        assert_eq!(shortfall(&best), 0.0);
+    }
+}
@@ -1,10 +1,40 @@
+//! Operations that may need several machines at once
+//!
+//! A process names the machine types (buckets) it needs. Jobs queue at the
+//! first one; a machine from each of the others is seized together with it
+//! and released when the job completes (a robot plus a fixture table, say).
+//! Its time is a fixed part per job plus a part per unit in the job.
+
 use super::machine::MachineType;
 
+#[derive(Debug, Clone)]
 pub struct Process {
     pub required_machine: Vec<MachineType>,
+    /// Minutes per job, whatever its quantity
+    pub fixed_time: u32,
     pub time_per_unit: u32,
 }
 
+impl Process {
+    pub fn new(required_machine: Vec<MachineType>, fixed_time: u32, time_per_unit: u32) -> Self {
+        Process {
+            required_machine,
+            fixed_time,
+            time_per_unit,
+        }
+    }
+
+    /// Minutes for a job of `units` units
+    pub fn duration(&self, units: u32) -> u32 {
+        self.fixed_time + self.time_per_unit * units
+    }
+
+    /// Buckets a machine is seized from besides the one the job queues at
+    pub fn secondary_buckets(&self) -> Vec<u32> {
+        self.required_machine.iter().skip(1).map(|m| m.id).collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,14 +43,19 @@ mod tests {
     fn process_carries_required_machines_and_duration() {
         let welder = MachineType::new(0, "Welder", 1);
         let assembler = MachineType::new(1, "Assembler", 2);
-        let process = Process {
-            required_machine: vec![welder.clone(), assembler.clone()],
-            time_per_unit: 15,
-        };
+        let process = Process::new(vec![welder.clone(), assembler.clone()], 0, 15);
 
         assert_eq!(process.time_per_unit, 15);
         assert_eq!(process.required_machine.len(), 2);
         assert_eq!(process.required_machine[0].name, welder.name);
         assert_eq!(process.required_machine[1].staff_required, assembler.staff_required);
+        assert_eq!(process.secondary_buckets(), vec![1]);
+    }
+
+    #[test]
+    fn duration_is_fixed_plus_per_unit() {
+        let process = Process::new(vec![MachineType::automated(0, "Robot")], 5, 3);
+        assert_eq!(process.duration(1), 8);
+        assert_eq!(process.duration(4), 17);
     }
 }
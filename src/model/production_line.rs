@@ -1,31 +1,139 @@
-use super::material::Material;
+//! Production lines in a plant
+//!
+//! A plant runs one or more lines, each with its own steps. The plant keeps
+//! every step in one list, so a line is a run of consecutive steps. A line
+//! can take its input from a store that another line fills. Each item that
+//! leaves the feeding line puts one unit into the store; the consuming line
+//! takes one unit per item at its first step. Items leaving a line that
+//! feeds no store are finished goods.
 
+use std::ops::Range;
+
+use super::lot::LotPolicy;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ProductionLine {
     pub name: String,
-    pub start_material: Material,
-    pub end_material: Material,
+    /// What the line takes per item (a store or raw material), if anything
+    pub start_material: Option<String>,
+    /// What the line makes
+    pub end_material: Option<String>,
+    /// Stock the first step draws from (material id)
+    pub input: Option<u32>,
+    /// Store filled by items leaving the line (material id)
+    pub output: Option<u32>,
+    /// The line's steps in the plant's step list
+    pub steps: Range<usize>,
+    /// Items the line builds
+    pub items: u32,
+    /// How the items are released and moved between steps
+    pub lots: LotPolicy,
+    pub completed: u32,
+    pub scrapped: u32,
+}
+
+impl ProductionLine {
+    pub fn new(name: &str, steps: Range<usize>, items: u32) -> Self {
+        ProductionLine {
+            name: name.to_string(),
+            start_material: None,
+            end_material: None,
+            input: None,
+            output: None,
+            steps,
+            items,
+            lots: LotPolicy::default(),
+            completed: 0,
+            scrapped: 0,
+        }
+    }
+}
+
+/// All lines of a plant
+#[derive(Debug, Clone, Default)]
+pub struct Plant {
+    pub lines: Vec<ProductionLine>,
+}
+
+impl Plant {
+    pub fn new() -> Self {
+        Plant::default()
+    }
+
+    pub fn add_line(&mut self, line: ProductionLine) {
+        self.lines.push(line);
+    }
+
+    /// Index of the line a step belongs to
+    pub fn line_of_step(&self, step: usize) -> Option<usize> {
+        self.lines.iter().position(|line| line.steps.contains(&step))
+    }
+
+    /// Step after `step` on the same line (None at the end of its line)
+    pub fn next_step(&self, step: usize) -> Option<usize> {
+        let line = &self.lines[self.line_of_step(step)?];
+        (step + 1 < line.steps.end).then_some(step + 1)
+    }
+
+    /// Count items leaving the line of `step`; returns the store they go to, if any
+    pub fn record_completion(&mut self, step: usize, units: u32) -> Option<u32> {
+        let idx = self.line_of_step(step)?;
+        let line = &mut self.lines[idx];
+        line.completed += units;
+        line.output
+    }
+
+    pub fn record_scrap(&mut self, step: usize) {
+        if let Some(idx) = self.line_of_step(step) {
+            self.lines[idx].scrapped += 1;
+        }
+    }
+
+    /// Items put into stores for other lines
+    pub fn transferred(&self) -> u32 {
+        self.lines
+            .iter()
+            .filter(|line| line.output.is_some())
+            .map(|line| line.completed)
+            .sum()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn plant() -> Plant {
+        let mut plant = Plant::new();
+        let mut body = ProductionLine::new("Body Line", 0..3, 5);
+        body.end_material = Some("Body".to_string());
+        body.output = Some(7);
+        let mut assembly = ProductionLine::new("Final Line", 3..5, 5);
+        assembly.start_material = Some("Body".to_string());
+        assembly.input = Some(7);
+        plant.add_line(body);
+        plant.add_line(assembly);
+        plant
+    }
+
+    #[test]
+    fn steps_stay_on_their_line() {
+        let plant = plant();
+        assert_eq!(plant.line_of_step(4), Some(1));
+        assert_eq!(plant.next_step(1), Some(2));
+        assert_eq!(plant.next_step(2), None);
+        assert_eq!(plant.next_step(3), Some(4));
+        assert_eq!(plant.next_step(4), None);
+        assert_eq!(plant.line_of_step(5), None);
+    }
+
     #[test]
-    fn production_line_links_materials() {
-        let start = Material {
-            name: "Raw Steel".to_string(),
-        };
-        let end = Material {
-            name: "Chassis".to_string(),
-        };
-        let line = ProductionLine {
-            name: "Chassis Line".to_string(),
-            start_material: start,
-            end_material: end,
-        };
-
-        assert_eq!(line.name, "Chassis Line");
-        assert_eq!(line.start_material.name, "Raw Steel");
-        assert_eq!(line.end_material.name, "Chassis");
+    fn completions_feed_the_store() {
+        let mut plant = plant();
+        assert_eq!(plant.record_completion(2, 1), Some(7));
+        assert_eq!(plant.record_completion(4, 1), None);
+        plant.record_scrap(0);
+        assert_eq!(plant.transferred(), 1);
+        assert_eq!(plant.lines[0].completed + plant.lines[0].scrapped, 2);
     }
 }
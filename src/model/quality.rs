@@ -0,0 +1,168 @@
+//! Yield, scrap and rework handling for process steps
+//!
+//! A step with a yield policy inspects every item that completes it.
+//! Passing items move on as usual; failing items are scrapped, sent to a
+//! rework operation, or sent back to an earlier step. Loops are capped per
+//! item so a bad part is eventually scrapped instead of circling forever.
+
+use super::random::SimRng;
+
+/// What to do with an item that fails a step
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureAction {
+    /// Throw the item away
+    Scrap,
+    /// Run a rework operation on another bucket, then repeat the failing step
+    Rework { bucket_id: u32, duration: u32 },
+    /// Send the item back to an earlier step and let it flow forward again
+    ReturnTo { step_index: usize },
+}
+
+/// Per-step yield configuration
+#[derive(Debug, Clone, PartialEq)]
+pub struct YieldPolicy {
+    /// Probability (0.0 - 1.0) that an item passes this step
+    pub yield_rate: f64,
+    pub on_fail: FailureAction,
+    /// How many times one item may loop back from this step before it is scrapped
+    pub max_reworks: u32,
+}
+
+/// Outcome of inspecting one item at the end of a step
+#[derive(Debug, Clone, PartialEq)]
+pub enum Disposition {
+    Pass,
+    Scrap,
+    Rework { bucket_id: u32, duration: u32 },
+    ReturnTo { step_index: usize },
+}
+
+impl YieldPolicy {
+    pub fn new(yield_rate: f64, on_fail: FailureAction, max_reworks: u32) -> Self {
+        YieldPolicy {
+            yield_rate: yield_rate.clamp(0.0, 1.0),
+            on_fail,
+            max_reworks,
+        }
+    }
+
+    /// Decide what happens to an item leaving this step
+    /// `reworks_so_far` is how often this item already looped back from this step
+    pub fn inspect(&self, rng: &mut SimRng, reworks_so_far: u32) -> Disposition {
+        if rng.chance(self.yield_rate) {
+            return Disposition::Pass;
+        }
+        match &self.on_fail {
+            FailureAction::Scrap => Disposition::Scrap,
+            _ if reworks_so_far >= self.max_reworks => Disposition::Scrap,
+            FailureAction::Rework { bucket_id, duration } => Disposition::Rework {
+                bucket_id: *bucket_id,
+                duration: *duration,
+            },
+            FailureAction::ReturnTo { step_index } => Disposition::ReturnTo {
+                step_index: *step_index,
+            },
+        }
+    }
+}
+
+/// Quality counters for one step
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepQuality {
+    /// Items inspected on their first visit to this step
+    pub first_attempts: u32,
+    /// Items that passed on their first visit
+    pub first_pass: u32,
+    /// Items scrapped at this step
+    pub scrapped: u32,
+    /// Items sent to rework or back to an earlier step
+    pub reworked: u32,
+}
+
+impl StepQuality {
+    /// Record one inspection result
+    pub fn record(&mut self, first_attempt: bool, disposition: &Disposition) {
+        if first_attempt {
+            self.first_attempts += 1;
+            if *disposition == Disposition::Pass {
+                self.first_pass += 1;
+            }
+        }
+        match disposition {
+            Disposition::Pass => {}
+            Disposition::Scrap => self.scrapped += 1,
+            Disposition::Rework { .. } | Disposition::ReturnTo { .. } => self.reworked += 1,
+        }
+    }
+
+    /// Share of items that passed on their first visit (None before any inspection)
+    pub fn first_pass_yield(&self) -> Option<f64> {
+        if self.first_attempts == 0 {
+            None
+        } else {
+            Some(self.first_pass as f64 / self.first_attempts as f64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_yield_always_passes() {
+        let policy = YieldPolicy::new(1.0, FailureAction::Scrap, 0);
+        let mut rng = SimRng::new(3);
+        for _ in 0..100 {
+            assert_eq!(policy.inspect(&mut rng, 0), Disposition::Pass);
+        }
+    }
+
+    #[test]
+    fn failing_item_is_reworked_until_limit() {
+        let policy = YieldPolicy::new(
+            0.0,
+            FailureAction::Rework {
+                bucket_id: 4,
+                duration: 20,
+            },
+            2,
+        );
+        let mut rng = SimRng::new(3);
+        assert_eq!(
+            policy.inspect(&mut rng, 1),
+            Disposition::Rework {
+                bucket_id: 4,
+                duration: 20
+            }
+        );
+        assert_eq!(policy.inspect(&mut rng, 2), Disposition::Scrap);
+    }
+
+    #[test]
+    fn failing_item_returns_to_earlier_step() {
+        let policy = YieldPolicy::new(0.0, FailureAction::ReturnTo { step_index: 1 }, 1);
+        let mut rng = SimRng::new(3);
+        assert_eq!(
+            policy.inspect(&mut rng, 0),
+            Disposition::ReturnTo { step_index: 1 }
+        );
+    }
+
+    #[test]
+    fn step_quality_tracks_first_pass_yield() {
+        let mut stats = StepQuality::default();
+        assert_eq!(stats.first_pass_yield(), None);
+
+        stats.record(true, &Disposition::Pass);
+        stats.record(true, &Disposition::ReturnTo { step_index: 0 });
+        stats.record(false, &Disposition::Pass);
+        stats.record(true, &Disposition::Scrap);
+
+        assert_eq!(stats.first_attempts, 3);
+        assert_eq!(stats.first_pass, 1);
+        assert_eq!(stats.reworked, 1);
+        assert_eq!(stats.scrapped, 1);
+        assert!((stats.first_pass_yield().unwrap() - 1.0 / 3.0).abs() < 1e-9);
+    }
+}
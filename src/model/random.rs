@@ -0,0 +1,133 @@
+/// Small deterministic random number generator for the simulation
+///
+/// We only need reproducible draws (same seed = same run), so a
+/// SplitMix64 generator is plenty and keeps the dependency list short.
+#[derive(Debug, Clone)]
+pub struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        SimRng { state: seed }
+    }
+
+    /// Next raw 64-bit value
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns true with the given probability
+    pub fn chance(&mut self, probability: f64) -> bool {
+        if probability >= 1.0 {
+            true
+        } else if probability <= 0.0 {
+            false
+        } else {
+            self.next_f64() < probability
+        }
+    }
+}
+
+/// Random duration in whole minutes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Distribution {
+    Fixed(u32),
+    Uniform { min: u32, max: u32 },
+    Triangular { min: u32, mode: u32, max: u32 },
+    Exponential { mean: f64 },
+}
+
+impl Distribution {
+    /// Draw one value, rounded to the nearest minute
+    pub fn sample(&self, rng: &mut SimRng) -> u32 {
+        match *self {
+            Distribution::Fixed(value) => value,
+            Distribution::Uniform { min, max } => {
+                let (lo, hi) = (min.min(max) as f64, min.max(max) as f64);
+                (lo + rng.next_f64() * (hi - lo)).round() as u32
+            }
+            Distribution::Triangular { min, mode, max } => {
+                let (a, c, b) = (min as f64, mode as f64, max as f64);
+                if b <= a {
+                    return min;
+                }
+                let u = rng.next_f64();
+                let split = (c - a) / (b - a);
+                let value = if u < split {
+                    a + (u * (b - a) * (c - a)).sqrt()
+                } else {
+                    b - ((1.0 - u) * (b - a) * (b - c)).sqrt()
+                };
+                value.round() as u32
+            }
+            Distribution::Exponential { mean } => {
+                let u = rng.next_f64();
+                (-(1.0 - u).ln() * mean.max(0.0)).round() as u32
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_sequence() {
+        let mut a = SimRng::new(42);
+        let mut b = SimRng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+
+    #[test]
+    fn floats_stay_in_unit_range() {
+        let mut rng = SimRng::new(7);
+        for _ in 0..1000 {
+            let x = rng.next_f64();
+            assert!((0.0..1.0).contains(&x));
+        }
+    }
+
+    #[test]
+    fn chance_respects_bounds() {
+        let mut rng = SimRng::new(1);
+        assert!(rng.chance(1.0));
+        assert!(!rng.chance(0.0));
+        let hits = (0..10_000).filter(|_| rng.chance(0.25)).count();
+        assert!(hits > 2_000 && hits < 3_000);
+    }
+
+    #[test]
+    fn distributions_stay_in_range() {
+        let mut rng = SimRng::new(11);
+        assert_eq!(Distribution::Fixed(7).sample(&mut rng), 7);
+        for _ in 0..1000 {
+            let u = Distribution::Uniform { min: 3, max: 9 }.sample(&mut rng);
+            assert!((3..=9).contains(&u));
+            let t = Distribution::Triangular { min: 2, mode: 4, max: 10 }.sample(&mut rng);
+            assert!((2..=10).contains(&t));
+        }
+    }
+
+    #[test]
+    fn exponential_sample_mean_is_close() {
+        let mut rng = SimRng::new(5);
+        let dist = Distribution::Exponential { mean: 20.0 };
+        let total: u32 = (0..5000).map(|_| dist.sample(&mut rng)).sum();
+        let mean = total as f64 / 5000.0;
+        assert!((mean - 20.0).abs() < 1.5, "mean was {}", mean);
+    }
+}
@@ -0,0 +1,314 @@
+//! Self-contained HTML report pieces
+//!
+//! Tables, bar charts, step plots, histograms and Gantt charts as HTML with
+//! inline SVG. The page has no scripts, fonts or images to fetch, so it opens
+//! anywhere and can be mailed as a single file.
+
+/// Fill colours for series, in order
+pub const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#59a14f", "#e15759", "#76b7b2", "#edc948", "#b07aa1", "#9c755f",
+];
+
+const WIDTH: f64 = 720.0;
+const LABEL_WIDTH: f64 = 160.0;
+const ROW_HEIGHT: f64 = 22.0;
+const PLOT_HEIGHT: f64 = 220.0;
+const MARGIN: f64 = 30.0;
+
+/// Text safe to put inside HTML or SVG
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    for header in headers {
+        html.push_str(&format!("<th>{}</th>", escape(header)));
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(&format!("<td>{}</td>", escape(cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// Horizontal bars for shares between 0 and 1, labelled as percentages
+pub fn bar_chart(bars: &[(String, f64)]) -> String {
+    let height = bars.len() as f64 * ROW_HEIGHT + 10.0;
+    let span = WIDTH - LABEL_WIDTH - 60.0;
+    let mut svg = svg_open(height);
+    for (i, (label, share)) in bars.iter().enumerate() {
+        let y = 5.0 + i as f64 * ROW_HEIGHT;
+        let share = share.clamp(0.0, 1.0);
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            LABEL_WIDTH - 6.0,
+            y + 15.0,
+            escape(label)
+        ));
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"16\" fill=\"{}\"/>",
+            LABEL_WIDTH,
+            y + 2.0,
+            share * span,
+            PALETTE[i % PALETTE.len()]
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\">{:.1}%</text>",
+            LABEL_WIDTH + share * span + 4.0,
+            y + 15.0,
+            share * 100.0
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Step plot of values that hold until the next point, one line per series
+pub fn step_chart(series: &[(String, Vec<(u32, f64)>)]) -> String {
+    let end = series
+        .iter()
+        .flat_map(|(_, points)| points.last().map(|&(t, _)| t))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    let top = series
+        .iter()
+        .flat_map(|(_, points)| points.iter().map(|&(_, v)| v))
+        .fold(0.0, f64::max)
+        .max(1.0);
+    let (x, y) = scales(end as f64, top);
+    let mut svg = svg_open(PLOT_HEIGHT + MARGIN + series.len() as f64 * ROW_HEIGHT);
+    svg.push_str(&axes(end as f64, top, "min"));
+    for (i, (name, points)) in series.iter().enumerate() {
+        let color = PALETTE[i % PALETTE.len()];
+        let mut path = String::new();
+        for (j, &(t, v)) in points.iter().enumerate() {
+            if j == 0 {
+                path.push_str(&format!("M{:.1},{:.1}", x(t as f64), y(v)));
+            } else {
+                path.push_str(&format!(" H{:.1} V{:.1}", x(t as f64), y(v)));
+            }
+        }
+        svg.push_str(&format!(
+            "<path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"/>",
+            path, color
+        ));
+        svg.push_str(&legend(i, PLOT_HEIGHT + MARGIN, name, color));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Counts of `values` in `bins` equal-width bins
+pub fn histogram_bins(values: &[u32], bins: usize) -> Vec<(u32, u32, u32)> {
+    let (Some(&low), Some(&high)) = (values.iter().min(), values.iter().max()) else {
+        return Vec::new();
+    };
+    let bins = bins.max(1) as u32;
+    let width = ((high - low) / bins + 1).max(1);
+    let mut counts = vec![0; bins as usize];
+    for &value in values {
+        counts[((value - low) / width).min(bins - 1) as usize] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| {
+            let from = low + i as u32 * width;
+            (from, from + width, count)
+        })
+        .filter(|&(from, _, _)| from <= high)
+        .collect()
+}
+
+/// Column chart of `values` counted in up to `bins` bins
+pub fn histogram(values: &[u32], bins: usize, unit: &str) -> String {
+    let bins = histogram_bins(values, bins);
+    let top = bins.iter().map(|&(_, _, count)| count).max().unwrap_or(0).max(1) as f64;
+    let (_, y) = scales(1.0, top);
+    let column = (WIDTH - 2.0 * MARGIN) / bins.len().max(1) as f64;
+    let mut svg = svg_open(PLOT_HEIGHT + MARGIN);
+    svg.push_str(&axes(0.0, top, unit));
+    for (i, &(from, to, count)) in bins.iter().enumerate() {
+        let left = MARGIN + i as f64 * column;
+        svg.push_str(&format!(
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}-{} {}: {}</title></rect>",
+            left + 1.0,
+            y(count as f64),
+            (column - 2.0).max(1.0),
+            y(0.0) - y(count as f64),
+            PALETTE[0],
+            from,
+            to,
+            unit,
+            count
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            left + column / 2.0,
+            PLOT_HEIGHT + 15.0,
+            from
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// One bar of a Gantt row
+#[derive(Debug, Clone, PartialEq)]
+pub struct GanttSpan {
+    pub start: u32,
+    pub end: u32,
+    pub color: &'static str,
+    pub title: String,
+}
+
+/// Rows of spans between `from` and `to`, with a legend of (label, colour)
+pub fn gantt(rows: &[(String, Vec<GanttSpan>)], from: u32, to: u32, legend_items: &[(&str, &str)]) -> String {
+    let span = (WIDTH - LABEL_WIDTH - 10.0) / to.saturating_sub(from).max(1) as f64;
+    let x = |t: u32| LABEL_WIDTH + (t.clamp(from, to) - from) as f64 * span;
+    let body = rows.len() as f64 * ROW_HEIGHT;
+    let mut svg = svg_open(body + 20.0 + legend_items.len() as f64 * ROW_HEIGHT);
+    for (i, (label, spans)) in rows.iter().enumerate() {
+        let y = i as f64 * ROW_HEIGHT;
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+            LABEL_WIDTH - 6.0,
+            y + 15.0,
+            escape(label)
+        ));
+        for s in spans.iter().filter(|s| s.end > from && s.start < to) {
+            svg.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"16\" fill=\"{}\"><title>{}</title></rect>",
+                x(s.start),
+                y + 2.0,
+                x(s.end) - x(s.start),
+                s.color,
+                escape(&s.title)
+            ));
+        }
+    }
+    svg.push_str(&format!(
+        "<text x=\"{:.1}\" y=\"{:.1}\">{} min</text><text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\">{} min</text>",
+        LABEL_WIDTH,
+        body + 14.0,
+        from,
+        WIDTH - 10.0,
+        body + 14.0,
+        to
+    ));
+    for (i, (label, color)) in legend_items.iter().enumerate() {
+        svg.push_str(&legend(i, body + 20.0, label, color));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A whole page; `sections` are (heading, HTML body)
+pub fn page(title: &str, sections: &[(String, String)]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n<style>\n\
+         body {{ font-family: sans-serif; margin: 2em; color: #222; }}\n\
+         table {{ border-collapse: collapse; margin-bottom: 1em; }}\n\
+         th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: right; }}\n\
+         th:first-child, td:first-child {{ text-align: left; }}\n\
+         svg {{ font-size: 12px; display: block; margin-bottom: 1em; }}\n\
+         pre {{ background: #f6f6f6; padding: 1em; }}\n\
+         </style>\n</head>\n<body>\n<h1>{0}</h1>\n",
+        escape(title)
+    );
+    for (heading, body) in sections {
+        html.push_str(&format!("<h2>{}</h2>\n{}", escape(heading), body));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn svg_open(height: f64) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1:.0}\" viewBox=\"0 0 {0} {1:.0}\">",
+        WIDTH, height
+    )
+}
+
+/// Maps from data to plot coordinates for a plot from 0 to `end` and 0 to `top`
+fn scales(end: f64, top: f64) -> (impl Fn(f64) -> f64, impl Fn(f64) -> f64) {
+    let x = move |t: f64| MARGIN + t / end * (WIDTH - 2.0 * MARGIN);
+    let y = move |v: f64| PLOT_HEIGHT - v / top * (PLOT_HEIGHT - 10.0);
+    (x, y)
+}
+
+/// Axis lines with the top value, plus the end of the time axis when `end` > 0
+fn axes(end: f64, top: f64, unit: &str) -> String {
+    let mut svg = format!(
+        "<path d=\"M{0},10 V{1} H{2}\" fill=\"none\" stroke=\"#888\"/><text x=\"{3}\" y=\"14\" text-anchor=\"end\">{4}</text>",
+        MARGIN,
+        PLOT_HEIGHT,
+        WIDTH - MARGIN,
+        MARGIN - 4.0,
+        top
+    );
+    if end > 0.0 {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{} {}</text>",
+            WIDTH - MARGIN,
+            PLOT_HEIGHT + 15.0,
+            end,
+            escape(unit)
+        ));
+    } else {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+            WIDTH - MARGIN,
+            PLOT_HEIGHT + 28.0,
+            escape(unit)
+        ));
+    }
+    svg
+}
+
+fn legend(i: usize, top: f64, label: &str, color: &str) -> String {
+    let y = top + i as f64 * ROW_HEIGHT;
+    format!(
+        "<rect x=\"{}\" y=\"{:.1}\" width=\"12\" height=\"12\" fill=\"{}\"/><text x=\"{}\" y=\"{:.1}\">{}</text>",
+        MARGIN,
+        y + 3.0,
+        color,
+        MARGIN + 18.0,
+        y + 13.0,
+        escape(label)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_binned_evenly() {
+        let bins = histogram_bins(&[10, 12, 15, 19, 30], 4);
+        assert_eq!(bins, vec![(10, 16, 3), (16, 22, 1), (22, 28, 0), (28, 34, 1)]);
+        assert_eq!(histogram_bins(&[7, 7], 5), vec![(7, 8, 2)]);
+        assert!(histogram_bins(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn page_is_self_contained() {
+        let chart = bar_chart(&[("Oven <2>".to_string(), 0.5)]);
+        assert!(chart.contains("Oven &lt;2&gt;"));
+        assert!(chart.contains("50.0%"));
+        let html = page("Run", &[("Utilization".to_string(), chart)]);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h2>Utilization</h2>"));
+        assert!(!html.contains("src=") && !html.contains("href="));
+    }
+}
@@ -0,0 +1,206 @@
+//! Shared resource pools (fixtures, jigs, pallets, test probes)
+//!
+//! A step can need some units of a pool on top of its machine and staff.
+//! The runner checks every requirement first and only then takes them all,
+//! so a job never holds a fixture while it waits for an operator.
+
+/// A named pool of identical units
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourcePool {
+    pub id: u32,
+    pub name: String,
+    pub capacity: u32,
+    pub in_use: u32,
+    /// Highest number of units in use at once
+    pub peak_in_use: u32,
+    /// Unit-minutes in use (integral of `in_use` over time)
+    pub busy_minutes: u64,
+    /// Times a job could not start because this pool was empty
+    pub wait_count: u32,
+    last_change: u32,
+}
+
+impl ResourcePool {
+    pub fn new(id: u32, name: &str, capacity: u32) -> Self {
+        ResourcePool {
+            id,
+            name: name.to_string(),
+            capacity,
+            in_use: 0,
+            peak_in_use: 0,
+            busy_minutes: 0,
+            wait_count: 0,
+            last_change: 0,
+        }
+    }
+
+    pub fn available(&self) -> u32 {
+        self.capacity.saturating_sub(self.in_use)
+    }
+
+    /// Add usage up to `current_time`
+    pub fn accumulate_until(&mut self, current_time: u32) {
+        if current_time > self.last_change {
+            self.busy_minutes += self.in_use as u64 * (current_time - self.last_change) as u64;
+            self.last_change = current_time;
+        }
+    }
+
+    /// Start counting afresh at `current_time` (end of the warm-up)
+    pub fn reset_statistics(&mut self, current_time: u32) {
+        self.accumulate_until(current_time);
+        self.busy_minutes = 0;
+        self.wait_count = 0;
+        self.peak_in_use = self.in_use;
+    }
+
+    /// Share of capacity used over `elapsed` minutes of statistics
+    pub fn utilization(&self, elapsed: u32) -> f64 {
+        let capacity = self.capacity as f64 * elapsed as f64;
+        if capacity > 0.0 {
+            self.busy_minutes as f64 / capacity
+        } else {
+            0.0
+        }
+    }
+}
+
+/// One requirement of a step: `quantity` units of pool `resource_id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceNeed {
+    pub resource_id: u32,
+    pub quantity: u32,
+}
+
+/// All pools in the plant
+#[derive(Debug, Clone, Default)]
+pub struct ResourcePools {
+    pub pools: Vec<ResourcePool>,
+}
+
+impl ResourcePools {
+    pub fn new() -> Self {
+        ResourcePools::default()
+    }
+
+    pub fn add_pool(&mut self, pool: ResourcePool) {
+        self.pools.push(pool);
+    }
+
+    pub fn get(&self, resource_id: u32) -> Option<&ResourcePool> {
+        self.pools.iter().find(|p| p.id == resource_id)
+    }
+
+    fn get_mut(&mut self, resource_id: u32) -> Option<&mut ResourcePool> {
+        self.pools.iter_mut().find(|p| p.id == resource_id)
+    }
+
+    /// First requirement that cannot be met right now, if any
+    pub fn first_shortage(&self, needs: &[ResourceNeed]) -> Option<u32> {
+        needs
+            .iter()
+            .find(|need| {
+                self.get(need.resource_id)
+                    .map(|pool| pool.available() < need.quantity)
+                    .unwrap_or(true)
+            })
+            .map(|need| need.resource_id)
+    }
+
+    /// Count a job that started waiting for a pool
+    pub fn note_wait(&mut self, resource_id: u32) {
+        if let Some(pool) = self.get_mut(resource_id) {
+            pool.wait_count += 1;
+        }
+    }
+
+    /// Take every requirement, or nothing at all
+    pub fn try_acquire(&mut self, needs: &[ResourceNeed], current_time: u32) -> bool {
+        if self.first_shortage(needs).is_some() {
+            return false;
+        }
+        for need in needs {
+            if let Some(pool) = self.get_mut(need.resource_id) {
+                pool.accumulate_until(current_time);
+                pool.in_use += need.quantity;
+                pool.peak_in_use = pool.peak_in_use.max(pool.in_use);
+            }
+        }
+        true
+    }
+
+    /// Give back units taken by `try_acquire`
+    pub fn release(&mut self, needs: &[ResourceNeed], current_time: u32) {
+        for need in needs {
+            if let Some(pool) = self.get_mut(need.resource_id) {
+                pool.accumulate_until(current_time);
+                pool.in_use = pool.in_use.saturating_sub(need.quantity);
+            }
+        }
+    }
+
+    /// Bring usage counters up to `current_time`
+    pub fn accumulate_until(&mut self, current_time: u32) {
+        for pool in &mut self.pools {
+            pool.accumulate_until(current_time);
+        }
+    }
+
+    pub fn reset_statistics(&mut self, current_time: u32) {
+        for pool in &mut self.pools {
+            pool.reset_statistics(current_time);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pools() -> ResourcePools {
+        let mut pools = ResourcePools::new();
+        pools.add_pool(ResourcePool::new(0, "Fixture", 2));
+        pools.add_pool(ResourcePool::new(1, "Probe", 1));
+        pools
+    }
+
+    #[test]
+    fn acquires_all_or_nothing() {
+        let mut pools = pools();
+        let needs = [
+            ResourceNeed { resource_id: 0, quantity: 1 },
+            ResourceNeed { resource_id: 1, quantity: 1 },
+        ];
+        assert!(pools.try_acquire(&needs, 0));
+        assert!(!pools.try_acquire(&needs, 0));
+
+        // The failed attempt must not have taken a fixture
+        assert_eq!(pools.get(0).unwrap().in_use, 1);
+        assert_eq!(pools.first_shortage(&needs), Some(1));
+
+        pools.note_wait(1);
+        assert_eq!(pools.get(1).unwrap().wait_count, 1);
+    }
+
+    #[test]
+    fn unknown_pool_is_a_shortage() {
+        let pools = pools();
+        let needs = [ResourceNeed { resource_id: 9, quantity: 1 }];
+        assert_eq!(pools.first_shortage(&needs), Some(9));
+    }
+
+    #[test]
+    fn release_tracks_utilization() {
+        let mut pools = pools();
+        let needs = [ResourceNeed { resource_id: 0, quantity: 2 }];
+        assert!(pools.try_acquire(&needs, 10));
+        pools.release(&needs, 20);
+        pools.accumulate_until(40);
+
+        let fixture = pools.get(0).unwrap();
+        assert_eq!(fixture.in_use, 0);
+        assert_eq!(fixture.peak_in_use, 2);
+        assert_eq!(fixture.busy_minutes, 20);
+        assert!((fixture.utilization(40) - 0.25).abs() < 1e-9);
+    }
+}
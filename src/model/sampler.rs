@@ -0,0 +1,150 @@
+//! Time series of queue lengths, busy machines and staff, and WIP
+//!
+//! The sampler records a snapshot after every event time, or at a fixed
+//! interval. Between events nothing changes, so an interval sample that falls
+//! between two events shows the state left by the earlier one. The series can
+//! be written out as CSV. A queue that keeps growing shows up here, even when
+//! its average over the whole run looks fine.
+
+/// One bucket at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BucketSample {
+    pub bucket_id: u32,
+    pub queue: u32,
+    pub busy_machines: u32,
+    pub busy_staff: u32,
+}
+
+/// The plant at one point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub time: u32,
+    /// Items queued, in process or in transit
+    pub wip: u32,
+    /// Every bucket, in bucket id order
+    pub buckets: Vec<BucketSample>,
+}
+
+/// When samples are taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleMode {
+    /// After the events at each simulated time
+    EveryEvent,
+    /// Every `n` minutes, from time 0
+    Every(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct Sampler {
+    pub mode: SampleMode,
+    pub samples: Vec<Sample>,
+    /// Next interval sample (EveryEvent mode ignores it)
+    next_at: u32,
+}
+
+impl Sampler {
+    pub fn new(mode: SampleMode) -> Self {
+        Sampler {
+            mode,
+            samples: Vec::new(),
+            next_at: 0,
+        }
+    }
+
+    /// Interval sample times before `current_time` that are still to be taken
+    pub fn due_before(&mut self, current_time: u32) -> Vec<u32> {
+        let SampleMode::Every(every) = self.mode else { return Vec::new() };
+        let mut due = Vec::new();
+        while self.next_at < current_time {
+            due.push(self.next_at);
+            self.next_at += every.max(1);
+        }
+        due
+    }
+
+    /// Whether the state after the events at `current_time` should be sampled
+    pub fn due_at(&mut self, current_time: u32) -> bool {
+        match self.mode {
+            SampleMode::EveryEvent => true,
+            SampleMode::Every(every) => {
+                if self.next_at == current_time {
+                    self.next_at += every.max(1);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+
+    /// Add a sample; a later one for the same time replaces it
+    pub fn record(&mut self, sample: Sample) {
+        match self.samples.last_mut() {
+            Some(last) if last.time == sample.time => *last = sample,
+            _ => self.samples.push(sample),
+        }
+    }
+
+    /// Samples as CSV, one row per sample; `names` label the buckets in id order
+    pub fn to_csv(&self, names: &[String]) -> String {
+        let mut csv = String::from("time,wip");
+        for name in names {
+            let name = name.replace(',', " ");
+            csv.push_str(&format!(",{0} queue,{0} busy machines,{0} busy staff", name));
+        }
+        csv.push('\n');
+        for sample in &self.samples {
+            csv.push_str(&format!("{},{}", sample.time, sample.wip));
+            for bucket in &sample.buckets {
+                csv.push_str(&format!(
+                    ",{},{},{}",
+                    bucket.queue, bucket.busy_machines, bucket.busy_staff
+                ));
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(time: u32, queue: u32) -> Sample {
+        Sample {
+            time,
+            wip: queue + 1,
+            buckets: vec![BucketSample {
+                bucket_id: 0,
+                queue,
+                busy_machines: 1,
+                busy_staff: 0,
+            }],
+        }
+    }
+
+    #[test]
+    fn interval_samples_fill_gaps_between_events() {
+        let mut sampler = Sampler::new(SampleMode::Every(10));
+        assert_eq!(sampler.due_before(0), Vec::<u32>::new());
+        assert!(sampler.due_at(0));
+        assert_eq!(sampler.due_before(35), vec![10, 20, 30]);
+        assert!(!sampler.due_at(35));
+        assert_eq!(sampler.due_before(40), Vec::<u32>::new());
+        assert!(sampler.due_at(40));
+    }
+
+    #[test]
+    fn csv_has_a_column_set_per_bucket() {
+        let mut sampler = Sampler::new(SampleMode::EveryEvent);
+        sampler.record(sample(0, 3));
+        sampler.record(sample(0, 2));
+        sampler.record(sample(5, 1));
+        assert_eq!(sampler.samples.len(), 2);
+        assert_eq!(
+            sampler.to_csv(&["Oven, big".to_string()]),
+            "time,wip,Oven  big queue,Oven  big busy machines,Oven  big busy staff\n0,3,2,1,0\n5,2,1,1,0\n"
+        );
+    }
+}
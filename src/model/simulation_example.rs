@@ -1,5 +1,5 @@
-/// This module shows practical examples of using the time simulation system
-/// It demonstrates how to track machine availability, process completion, etc.
+//! This module shows practical examples of using the time simulation system
+//! It demonstrates how to track machine availability, process completion, etc.
 
 use super::time::{Simulator, SimulationTime, EventType};
 
@@ -9,11 +9,13 @@ use super::time::{Simulator, SimulationTime, EventType};
 /// - Machine 1 processes items that take 10 minutes each
 /// - We want to schedule 3 items to be processed
 /// - We want to track when each item is done
+#[allow(dead_code)] // an example, exercised by its tests
 pub struct MachineSimulator {
     pub simulator: Simulator,
     pub machine_id: u32,
 }
 
+#[allow(dead_code)]
 impl MachineSimulator {
     pub fn new(machine_id: u32) -> Self {
         MachineSimulator {
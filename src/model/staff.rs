@@ -1,11 +1,52 @@
+use std::collections::HashMap;
+
+/// Learning curve: each doubling of repetitions multiplies the duration by `rate`
+/// (0.9 = a "90% curve"), never dropping below `floor`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LearningCurve {
+    pub rate: f64,
+    pub floor: f64,
+}
+
+impl LearningCurve {
+    /// Duration factor for the `repetition`-th time (1-based) a task is done
+    pub fn factor(&self, repetition: u32) -> f64 {
+        let n = repetition.max(1) as f64;
+        n.powf(self.rate.log2()).max(self.floor)
+    }
+}
+
+/// Fatigue: durations grow with time worked since the last break
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FatigueModel {
+    /// Extra duration per hour worked (0.05 = 5% slower each hour)
+    pub per_hour: f64,
+    /// Upper bound for the fatigue factor
+    pub max_factor: f64,
+    /// Minutes of work after which the next free moment is spent on a break
+    pub break_after: Option<u32>,
+    /// Length of a break; idle gaps at least this long also count as one
+    pub break_minutes: u32,
+}
+
+impl FatigueModel {
+    pub fn factor(&self, minutes_worked: u32) -> f64 {
+        (1.0 + self.per_hour * minutes_worked as f64 / 60.0).min(self.max_factor)
+    }
+}
+
 /// Represents a role that a staff member can have
 /// Different roles may have different capabilities or costs
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Role {
     pub id: u32,
     pub name: String,
     /// Some roles might be specialists that can only work on certain machines
     pub machine_ids: Vec<u32>,  // Empty = can work on any machine
+    /// Durations shrink with practice (None = no learning)
+    pub learning: Option<LearningCurve>,
+    /// Durations grow with time worked since the last break (None = no fatigue)
+    pub fatigue: Option<FatigueModel>,
 }
 
 impl Role {
@@ -15,6 +56,8 @@ impl Role {
             id,
             name: name.to_string(),
             machine_ids: Vec::new(),
+            learning: None,
+            fatigue: None,
         }
     }
 
@@ -24,6 +67,8 @@ impl Role {
             id,
             name: name.to_string(),
             machine_ids,
+            learning: None,
+            fatigue: None,
         }
     }
 
@@ -37,6 +82,29 @@ impl Role {
     }
 }
 
+/// How quickly someone works a machine, as a factor on the process duration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Proficiency {
+    /// Still learning: 1.4x the standard time
+    Trainee,
+    Standard,
+    /// 0.85x the standard time
+    Expert,
+    /// Any other duration factor
+    Factor(f64),
+}
+
+impl Proficiency {
+    pub fn factor(&self) -> f64 {
+        match *self {
+            Proficiency::Trainee => 1.4,
+            Proficiency::Standard => 1.0,
+            Proficiency::Expert => 0.85,
+            Proficiency::Factor(factor) => factor.max(0.0),
+        }
+    }
+}
+
 /// Represents a staff member who can operate machines
 #[derive(Debug, Clone)]
 pub struct Staff {
@@ -52,6 +120,25 @@ pub struct Staff {
     pub idle_time: u32,
     /// Last time availability changed (tracks idle accumulation)
     pub last_status_change: u32,
+    /// Duration factor per machine bucket; buckets not listed run at standard pace
+    pub proficiency: HashMap<u32, f64>,
+    /// Staffed phases worked per machine bucket (drives the learning curve)
+    pub repetitions: HashMap<u32, u32>,
+    /// Minutes worked since the last break (drives fatigue)
+    pub worked_since_break: u32,
+    pub on_break: bool,
+    /// Total minutes spent on scheduled breaks
+    pub break_time: u32,
+    /// Machine bucket last worked at (None before the first assignment)
+    pub location: Option<u32>,
+    /// Total minutes spent walking between buckets
+    pub travel_time: u32,
+    /// End of the current walk (while busy)
+    pub walking_until: u32,
+    /// Minutes spent on setup phases
+    pub setup_time: u32,
+    /// Minutes worked per machine, walking excluded
+    pub busy_by_machine: HashMap<u32, u32>,
 }
 
 impl Staff {
@@ -66,9 +153,93 @@ impl Staff {
             available_at: 0,
             idle_time: 0,
             last_status_change: 0,
+            proficiency: HashMap::new(),
+            repetitions: HashMap::new(),
+            worked_since_break: 0,
+            on_break: false,
+            break_time: 0,
+            location: None,
+            travel_time: 0,
+            walking_until: 0,
+            setup_time: 0,
+            busy_by_machine: HashMap::new(),
         }
     }
 
+    /// Set how quickly this staff member works the machines of a bucket
+    pub fn set_proficiency(&mut self, bucket_id: u32, proficiency: Proficiency) {
+        self.proficiency.insert(bucket_id, proficiency.factor());
+    }
+
+    /// Factor applied to process durations in a machine bucket (1.0 = standard)
+    pub fn duration_factor(&self, bucket_id: u32) -> f64 {
+        self.proficiency.get(&bucket_id).copied().unwrap_or(1.0)
+    }
+
+    /// Factor for the next task in a machine bucket: proficiency, practice and fatigue combined
+    pub fn task_factor(&self, bucket_id: u32, current_time: u32) -> f64 {
+        let mut factor = self.duration_factor(bucket_id);
+        if let Some(learning) = &self.role.learning {
+            let done = self.repetitions.get(&bucket_id).copied().unwrap_or(0);
+            factor *= learning.factor(done + 1);
+        }
+        if let Some(fatigue) = &self.role.fatigue {
+            factor *= fatigue.factor(self.fatigue_minutes(current_time));
+        }
+        factor
+    }
+
+    /// Minutes of work that count towards fatigue at `current_time`
+    fn fatigue_minutes(&self, current_time: u32) -> u32 {
+        match &self.role.fatigue {
+            // A long enough idle stretch is as good as a break
+            Some(fatigue)
+                if self.is_available
+                    && fatigue.break_minutes > 0
+                    && current_time.saturating_sub(self.last_status_change) >= fatigue.break_minutes =>
+            {
+                0
+            }
+            _ => self.worked_since_break,
+        }
+    }
+
+    /// Record a walk to a machine bucket that starts at `current_time`
+    pub fn walk_to(&mut self, bucket_id: u32, minutes: u32, current_time: u32) {
+        self.travel_time += minutes;
+        self.walking_until = current_time + minutes;
+        self.location = Some(bucket_id);
+    }
+
+    /// Count one more repetition on a machine bucket
+    pub fn record_repetition(&mut self, bucket_id: u32) {
+        *self.repetitions.entry(bucket_id).or_insert(0) += 1;
+    }
+
+    /// Whether the role's fatigue model calls for a break now
+    pub fn break_due(&self) -> bool {
+        match &self.role.fatigue {
+            Some(FatigueModel { break_after: Some(after), .. }) => self.worked_since_break >= *after,
+            _ => false,
+        }
+    }
+
+    /// Send an idle staff member on a break; returns when it ends
+    pub fn start_break(&mut self, current_time: u32) -> Option<u32> {
+        if !self.is_available || !self.break_due() {
+            return None;
+        }
+        let minutes = self.role.fatigue.map(|f| f.break_minutes).unwrap_or(0);
+        self.idle_time += current_time.saturating_sub(self.last_status_change);
+        self.is_available = false;
+        self.on_break = true;
+        self.available_at = current_time + minutes;
+        self.last_status_change = current_time;
+        self.break_time += minutes;
+        self.worked_since_break = 0;
+        Some(self.available_at)
+    }
+
     /// Check if this staff member can work on a specific machine
     pub fn can_work_on(&self, machine_id: u32) -> bool {
         self.role.can_work_on(machine_id)
@@ -78,6 +249,7 @@ impl Staff {
     /// Returns true if successfully assigned, false if busy
     pub fn assign_to_machine(&mut self, machine_id: u32, duration: u32, current_time: u32) -> bool {
         if self.is_available && self.can_work_on(machine_id) {
+            self.worked_since_break = self.fatigue_minutes(current_time) + duration;
             // Accumulate idle time up to assignment
             self.idle_time += current_time.saturating_sub(self.last_status_change);
             self.is_available = false;
@@ -90,10 +262,31 @@ impl Staff {
         }
     }
 
+    /// Credit the time worked since the last change to the current machine
+    pub fn record_work_until(&mut self, current_time: u32) {
+        if self.is_available || self.on_break {
+            return;
+        }
+        let Some(machine_id) = self.current_machine else { return };
+        // A walk to the machine comes first and counts as travel
+        let start = self.last_status_change.max(self.walking_until);
+        if current_time > start {
+            *self.busy_by_machine.entry(machine_id).or_insert(0) += current_time - start;
+        }
+        self.last_status_change = self.last_status_change.max(current_time);
+    }
+
+    /// Minutes worked on any machine
+    pub fn busy_time(&self) -> u32 {
+        self.busy_by_machine.values().sum()
+    }
+
     /// Release this staff member from a machine
     pub fn release_from_machine(&mut self, current_time: u32) {
         if current_time >= self.available_at {
+            self.record_work_until(current_time);
             self.is_available = true;
+            self.on_break = false;
             self.current_machine = None;
             self.last_status_change = current_time;
         }
@@ -180,4 +373,79 @@ mod tests {
         let success = staff.assign_to_machine(2, 10, 10);
         assert!(!success);
     }
+
+    #[test]
+    fn proficiency_scales_per_bucket() {
+        let mut staff = Staff::new(0, "John", Role::new(0, "Operator"));
+        staff.set_proficiency(1, Proficiency::Expert);
+        staff.set_proficiency(2, Proficiency::Trainee);
+        assert_eq!(staff.duration_factor(0), 1.0);
+        assert_eq!(staff.duration_factor(1), 0.85);
+        assert_eq!(staff.duration_factor(2), 1.4);
+    }
+
+    #[test]
+    fn learning_curve_shrinks_with_doublings() {
+        let curve = LearningCurve { rate: 0.8, floor: 0.6 };
+        assert!((curve.factor(1) - 1.0).abs() < 1e-9);
+        assert!((curve.factor(2) - 0.8).abs() < 1e-9);
+        assert!((curve.factor(4) - 0.64).abs() < 1e-9);
+        assert_eq!(curve.factor(100), 0.6);
+    }
+
+    #[test]
+    fn fatigue_builds_until_a_break() {
+        let mut role = Role::new(0, "Operator");
+        role.fatigue = Some(FatigueModel {
+            per_hour: 0.1,
+            max_factor: 1.5,
+            break_after: Some(120),
+            break_minutes: 15,
+        });
+        let mut staff = Staff::new(0, "John", role);
+
+        assert!(staff.assign_to_machine(0, 120, 0));
+        staff.release_from_machine(120);
+        assert!((staff.task_factor(0, 120) - 1.2).abs() < 1e-9);
+
+        assert_eq!(staff.start_break(120), Some(135));
+        assert!(!staff.is_available);
+        staff.release_from_machine(135);
+        assert_eq!(staff.break_time, 15);
+        assert!((staff.task_factor(0, 135) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn long_idle_gap_counts_as_rest() {
+        let mut role = Role::new(0, "Operator");
+        role.fatigue = Some(FatigueModel {
+            per_hour: 0.1,
+            max_factor: 2.0,
+            break_after: None,
+            break_minutes: 30,
+        });
+        let mut staff = Staff::new(0, "John", role);
+        assert!(staff.assign_to_machine(0, 60, 0));
+        staff.release_from_machine(60);
+        assert!((staff.task_factor(0, 70) - 1.1).abs() < 1e-9);
+        assert!((staff.task_factor(0, 90) - 1.0).abs() < 1e-9);
+        assert!(!staff.break_due());
+    }
+
+    #[test]
+    fn work_is_credited_to_the_machine_after_the_walk() {
+        let mut staff = Staff::new(0, "John", Role::new(0, "Operator"));
+        assert!(staff.assign_to_machine(3, 25, 10));
+        staff.walk_to(1, 5, 10);
+        staff.record_work_until(20);
+        assert_eq!(staff.busy_by_machine[&3], 5);
+        staff.release_from_machine(35);
+        assert_eq!(staff.busy_by_machine[&3], 20);
+        assert_eq!(staff.travel_time, 5);
+
+        assert!(staff.assign_to_machine(4, 10, 40));
+        staff.release_from_machine(50);
+        assert_eq!(staff.busy_time(), 30);
+        assert_eq!(staff.idle_time, 15);
+    }
 }
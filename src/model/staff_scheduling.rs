@@ -1,14 +1,17 @@
-/// Staff Scheduling System - How staff operates machines in the simulation
-/// 
-/// This module demonstrates:
-/// - Assigning staff to machines
-/// - Staff availability tracking
-/// - Constraints (staff skills, availability)
-/// - Bottleneck detection (waiting for staff)
+//! Staff Scheduling System - How staff operates machines in the simulation
+//!
+//! This module demonstrates:
+//! - Assigning staff to machines
+//! - Staff availability tracking
+//! - Constraints (staff skills, availability)
+//! - Bottleneck detection (waiting for staff)
 
 use crate::model::time::{Simulator, SimulationTime, EventType};
-use crate::model::staff::{Staff, Role};
+use crate::model::layout::Layout;
+use crate::model::maintenance::MaintenancePlan;
+use crate::model::staff::Staff;
 use crate::model::machine::MachineType;
+use crate::model::machine_status::{StatusTimes, WaitReason};
 
 /// Represents a machine in operation with its current state
 #[derive(Debug, Clone)]
@@ -16,29 +19,116 @@ pub struct MachineState {
     pub machine: MachineType,
     pub is_operating: bool,
     pub assigned_staff: Vec<u32>,  // IDs of staff working on this machine
-    pub waiting_for: Option<String>,
+    pub waiting_for: Option<WaitReason>,
     pub idle_time: u32,
     pub last_status_change: u32,
+    /// Pick the fastest qualified staff first (for bottleneck steps)
+    pub prefer_experts: bool,
+    /// Bucket of identical machines this one belongs to (learning is tracked per bucket)
+    pub bucket_id: u32,
+    /// Offline for maintenance (may still hold a paused job)
+    pub down: bool,
+    /// Minutes spent on setup phases
+    pub setup_time: u32,
+    /// When the crew of the current phase reaches the machine (it waits for them until then)
+    pub crew_arrives_at: u32,
+    /// Minutes in each status (busy, starved, waiting for staff, ...)
+    pub status_times: StatusTimes,
 }
 
 impl MachineState {
     pub fn new(machine: MachineType) -> Self {
         MachineState {
+            bucket_id: machine.id,
             machine,
             is_operating: false,
             assigned_staff: Vec::new(),
             waiting_for: None,
             idle_time: 0,
             last_status_change: 0,
+            prefer_experts: false,
+            down: false,
+            setup_time: 0,
+            crew_arrives_at: 0,
+            status_times: StatusTimes::new(),
         }
     }
 }
 
+/// Who sets the pace when several staff work one machine together
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrewPace {
+    /// The slowest member's proficiency applies
+    Slowest,
+    /// The crew works at its average proficiency
+    Average,
+}
+
+impl CrewPace {
+    /// Combined duration factor for a crew (1.0 for an empty crew)
+    pub fn factor(&self, factors: &[f64]) -> f64 {
+        if factors.is_empty() {
+            return 1.0;
+        }
+        match self {
+            CrewPace::Slowest => factors.iter().cloned().fold(f64::MIN, f64::max),
+            CrewPace::Average => factors.iter().sum::<f64>() / factors.len() as f64,
+        }
+    }
+}
+
+/// Part of a step with its own staffing need
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseKind {
+    Setup,
+    Load,
+    Run,
+    Unload,
+}
+
+impl PhaseKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PhaseKind::Setup => "setup",
+            PhaseKind::Load => "load",
+            PhaseKind::Run => "run",
+            PhaseKind::Unload => "unload",
+        }
+    }
+}
+
+/// One phase of a process: staff are only held for the phases that need them,
+/// so an operator can load one machine and walk off while it runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Phase {
+    pub kind: PhaseKind,
+    pub duration: u32,
+    /// Staff members needed during this phase
+    pub staff: u32,
+}
+
+impl Phase {
+    pub fn new(kind: PhaseKind, duration: u32, staff: u32) -> Self {
+        Phase { kind, duration, staff }
+    }
+}
+
+/// Minutes a staff member needs to walk to a bucket from where they last worked
+fn walk_time(layout: &Layout, staff: &Staff, bucket_id: u32) -> u32 {
+    staff
+        .location
+        .map(|from| layout.walk_time(from, bucket_id))
+        .unwrap_or(0)
+}
+
 /// Complete production simulation with staff scheduling
 pub struct ProductionSimulator {
     pub simulator: Simulator,
     pub machines: Vec<MachineState>,
     pub staff: Vec<Staff>,
+    pub pace: CrewPace,
+    /// Walking times between buckets (empty = staff move instantly)
+    pub layout: Layout,
 }
 
 impl ProductionSimulator {
@@ -47,6 +137,8 @@ impl ProductionSimulator {
             simulator: Simulator::new(),
             machines: Vec::new(),
             staff: Vec::new(),
+            pace: CrewPace::Slowest,
+            layout: Layout::default(),
         }
     }
 
@@ -114,7 +206,7 @@ impl ProductionSimulator {
                     process_id,
                 },
             );
-            machine.waiting_for = Some("Staff".to_string());
+            machine.waiting_for = Some(WaitReason::Staff(None));
             return false;
         }
 
@@ -150,16 +242,175 @@ impl ProductionSimulator {
         true
     }
 
+    /// Start one phase of a process on a machine
+    /// Returns false (and changes nothing) if not enough qualified staff are free
+    ///
+    /// The machine stays occupied between phases; the caller decides when the
+    /// whole process is done after the last `PhaseComplete`.
+    pub fn try_start_phase(
+        &mut self,
+        machine_id: u32,
+        process_id: u32,
+        phase: &Phase,
+        current_time: u32,
+    ) -> bool {
+        self.start_due_breaks(current_time);
+        let machine = match self.machines.get_mut(machine_id as usize) {
+            Some(m) => m,
+            None => return false,
+        };
+        let bucket_id = machine.bucket_id;
+
+        let staff_needed = phase.staff as usize;
+        let mut qualified: Vec<usize> = self
+            .staff
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.is_available && s.can_work_on(machine_id))
+            .map(|(idx, _)| idx)
+            .collect();
+        if qualified.len() < staff_needed {
+            return false;
+        }
+        // Nearest first; bottleneck machines take the fastest people before the nearest
+        let walk = |idx: &usize| walk_time(&self.layout, &self.staff[*idx], bucket_id);
+        qualified.sort_by_key(walk);
+        if machine.prefer_experts {
+            let factor = |idx: &usize| self.staff[*idx].task_factor(bucket_id, current_time);
+            qualified.sort_by(|a, b| factor(a).total_cmp(&factor(b)));
+        }
+        qualified.truncate(staff_needed);
+        let available_staff = qualified;
+        // The phase starts once the whole crew has arrived
+        let arrival = available_staff.iter().map(walk).max().unwrap_or(0);
+
+        // Unattended phases run at machine speed; staffed ones at the crew's pace
+        let factors: Vec<f64> = available_staff
+            .iter()
+            .map(|&idx| self.staff[idx].task_factor(bucket_id, current_time))
+            .collect();
+        let duration = (phase.duration as f64 * self.pace.factor(&factors)).round() as u32;
+
+        if !machine.is_operating {
+            machine.idle_time += current_time.saturating_sub(machine.last_status_change);
+            machine.last_status_change = current_time;
+            machine.is_operating = true;
+        }
+        machine.waiting_for = None;
+        let setup = if phase.kind == PhaseKind::Setup { duration } else { 0 };
+        machine.setup_time += setup;
+
+        let end_time = current_time + arrival + duration;
+        machine.crew_arrives_at = current_time + arrival;
+        if arrival > 0 {
+            self.simulator.schedule_event(
+                SimulationTime::new(current_time + arrival),
+                EventType::CrewArrived { machine_id },
+            );
+        }
+        for staff_idx in available_staff {
+            let staff_id = self.staff[staff_idx].id;
+            let walk = walk_time(&self.layout, &self.staff[staff_idx], bucket_id);
+            self.staff[staff_idx].walk_to(bucket_id, walk, current_time);
+            self.staff[staff_idx].assign_to_machine(machine_id, arrival + duration, current_time);
+            // Walking up and waiting for the rest of the crew is not work
+            self.staff[staff_idx].worked_since_break -= arrival;
+            self.staff[staff_idx].record_repetition(bucket_id);
+            self.staff[staff_idx].setup_time += setup;
+            machine.assigned_staff.push(staff_id);
+            self.simulator.schedule_event(
+                SimulationTime::new(end_time),
+                EventType::StaffReleased {
+                    staff_id,
+                    machine_id,
+                },
+            );
+        }
+
+        self.simulator.schedule_event(
+            SimulationTime::new(end_time),
+            EventType::PhaseComplete {
+                machine_id,
+                process_id,
+            },
+        );
+        true
+    }
+
+    /// Take a machine offline for maintenance once enough technicians are free
+    /// Returns when the maintenance ends, or None if technicians are missing
+    pub fn try_start_maintenance(&mut self, machine_id: u32, plan: &MaintenancePlan, current_time: u32) -> Option<u32> {
+        self.start_due_breaks(current_time);
+        let machine = self.machines.get_mut(machine_id as usize)?;
+        let bucket_id = machine.bucket_id;
+
+        let mut technicians: Vec<usize> = self
+            .staff
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| {
+                s.is_available
+                    && s.can_work_on(machine_id)
+                    && plan.technician_role.map(|role| s.role.id == role).unwrap_or(true)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        if technicians.len() < plan.technicians as usize {
+            return None;
+        }
+        let walk = |idx: &usize| walk_time(&self.layout, &self.staff[*idx], bucket_id);
+        technicians.sort_by_key(walk);
+        technicians.truncate(plan.technicians as usize);
+        let arrival = technicians.iter().map(walk).max().unwrap_or(0);
+        let end_time = current_time + arrival + plan.duration;
+
+        if !machine.is_operating {
+            machine.idle_time += current_time.saturating_sub(machine.last_status_change);
+            machine.last_status_change = current_time;
+        }
+        machine.down = true;
+        machine.waiting_for = Some(WaitReason::Maintenance);
+        for staff_idx in technicians {
+            let staff_id = self.staff[staff_idx].id;
+            let walk = walk_time(&self.layout, &self.staff[staff_idx], bucket_id);
+            self.staff[staff_idx].walk_to(bucket_id, walk, current_time);
+            self.staff[staff_idx].assign_to_machine(machine_id, end_time - current_time, current_time);
+            machine.assigned_staff.push(staff_id);
+            self.simulator.schedule_event(
+                SimulationTime::new(end_time),
+                EventType::StaffReleased { staff_id, machine_id },
+            );
+        }
+        self.simulator.schedule_event(
+            SimulationTime::new(end_time),
+            EventType::MaintenanceComplete { machine_id },
+        );
+        Some(end_time)
+    }
+
+    /// Send free staff whose fatigue model calls for it on a break
+    pub fn start_due_breaks(&mut self, current_time: u32) {
+        for staff in &mut self.staff {
+            if let Some(back_at) = staff.start_break(current_time) {
+                self.simulator.schedule_event(
+                    SimulationTime::new(back_at),
+                    EventType::StaffAvailable { staff_id: staff.id },
+                );
+            }
+        }
+    }
+
     /// Get a summary of current state
     pub fn get_status(&self) -> String {
         let mut status = format!("Production Status at time {}\n", self.simulator.elapsed_time());
         status.push_str("Machines:\n");
         for machine in &self.machines {
             let operating = if machine.is_operating { "Operating" } else { "Idle" };
-            let waiting = machine
-                .waiting_for
-                .as_deref()
-                .unwrap_or(if machine.is_operating { "" } else { "Next task" });
+            let waiting = match &machine.waiting_for {
+                Some(reason) => reason.to_string(),
+                None if machine.is_operating => String::new(),
+                None => "Next task".to_string(),
+            };
             status.push_str(&format!(
                 "  - {} (ID: {}): {} with {} staff{} | Idle: {} mins\n",
                 machine.machine.name,
@@ -189,6 +440,23 @@ impl ProductionSimulator {
         status
     }
 
+    /// Clear staff idle, busy, break, walking and setup times and machine status times (end of the warm-up)
+    pub fn reset_statistics(&mut self, current_time: u32) {
+        self.finalize_idle_time(current_time);
+        for staff in &mut self.staff {
+            staff.idle_time = 0;
+            staff.break_time = 0;
+            staff.travel_time = 0;
+            staff.setup_time = 0;
+            staff.busy_by_machine.clear();
+        }
+        for machine in &mut self.machines {
+            machine.idle_time = 0;
+            machine.setup_time = 0;
+            machine.status_times.reset(current_time);
+        }
+    }
+
     /// Update idle time for all available staff up to the provided time
     pub fn finalize_idle_time(&mut self, current_time: u32) {
         for staff in &mut self.staff {
@@ -203,7 +471,7 @@ impl ProductionSimulator {
                     match self.machines.get(machine_id as usize) {
                         Some(machine) => {
                             let still_assigned = machine.assigned_staff.contains(&staff.id);
-                            if !machine.is_operating || !still_assigned {
+                            if !(machine.is_operating || machine.down) || !still_assigned {
                                 staff.release_from_machine(current_time);
                             }
                         }
@@ -216,9 +484,10 @@ impl ProductionSimulator {
             }
 
             staff.accumulate_idle_until(current_time);
+            staff.record_work_until(current_time);
         }
         for machine in &mut self.machines {
-            if !machine.is_operating {
+            if !machine.is_operating && !machine.down {
                 // If machine is idle but still has staff assigned, free them
                 if !machine.assigned_staff.is_empty() {
                     for staff_id in machine.assigned_staff.drain(..) {
@@ -239,6 +508,8 @@ impl ProductionSimulator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::maintenance::MaintenanceTrigger;
+    use crate::model::staff::{FatigueModel, LearningCurve, Proficiency, Role};
 
     #[test]
     fn test_automated_machine() {
@@ -432,4 +703,153 @@ mod tests {
         prod.finalize_idle_time(20);
         assert_eq!(prod.staff[0].idle_time, 5);
     }
+
+    #[test]
+    fn operator_tends_machine_only_for_staffed_phases() {
+        let mut prod = ProductionSimulator::new();
+        prod.add_machine(MachineType::new(0, "Lathe A", 1));
+        prod.add_machine(MachineType::new(1, "Lathe B", 1));
+        prod.add_staff(Staff::new(0, "Tender", Role::new(0, "Operator")));
+
+        let load = Phase::new(PhaseKind::Load, 2, 1);
+        let run = Phase::new(PhaseKind::Run, 30, 0);
+
+        assert!(prod.try_start_phase(0, 0, &load, 0));
+        assert!(!prod.try_start_phase(1, 1, &load, 0)); // operator still loading A
+        assert!(!prod.machines[1].is_operating);
+
+        // After loading A the operator is free while A runs unattended
+        prod.staff[0].release_from_machine(2);
+        prod.machines[0].assigned_staff.clear();
+        assert!(prod.try_start_phase(0, 0, &run, 2));
+        assert!(prod.try_start_phase(1, 1, &load, 2));
+        assert!(prod.machines[0].is_operating);
+        assert!(prod.machines[1].is_operating);
+        assert_eq!(prod.machines[0].assigned_staff.len(), 0);
+        assert_eq!(prod.machines[1].assigned_staff, vec![0]);
+    }
+
+    #[test]
+    fn crew_pace_scales_staffed_phases() {
+        let mut prod = ProductionSimulator::new();
+        prod.add_machine(MachineType::new(0, "Press", 2));
+        let mut trainee = Staff::new(0, "Trainee", Role::new(0, "Operator"));
+        trainee.set_proficiency(0, Proficiency::Trainee);
+        let mut expert = Staff::new(1, "Expert", Role::new(0, "Operator"));
+        expert.set_proficiency(0, Proficiency::Factor(0.6));
+        prod.add_staff(trainee);
+        prod.add_staff(expert);
+
+        let load = Phase::new(PhaseKind::Load, 10, 2);
+        assert!(prod.try_start_phase(0, 0, &load, 0));
+        assert_eq!(prod.staff[0].available_at, 14); // slowest (1.4x) sets the pace
+
+        let mut prod2 = ProductionSimulator::new();
+        prod2.pace = CrewPace::Average;
+        prod2.add_machine(MachineType::new(0, "Press", 2));
+        prod2.staff = prod.staff.clone();
+        for staff in &mut prod2.staff {
+            staff.release_from_machine(20);
+        }
+        assert!(prod2.try_start_phase(0, 0, &load, 20));
+        assert_eq!(prod2.staff[0].available_at, 30); // average of 1.4 and 0.6
+    }
+
+    #[test]
+    fn bottleneck_machine_prefers_experts() {
+        let mut prod = ProductionSimulator::new();
+        prod.add_machine(MachineType::new(0, "Press", 1));
+        prod.machines[0].prefer_experts = true;
+        let mut expert = Staff::new(1, "Expert", Role::new(0, "Operator"));
+        expert.set_proficiency(0, Proficiency::Expert);
+        prod.add_staff(Staff::new(0, "Standard", Role::new(0, "Operator")));
+        prod.add_staff(expert);
+
+        let run = Phase::new(PhaseKind::Run, 20, 1);
+        assert!(prod.try_start_phase(0, 0, &run, 0));
+        assert_eq!(prod.machines[0].assigned_staff, vec![1]);
+        assert_eq!(prod.staff[1].available_at, 17);
+    }
+
+    #[test]
+    fn tired_operator_takes_a_break_before_the_next_job() {
+        let mut prod = ProductionSimulator::new();
+        prod.add_machine(MachineType::new(0, "Press", 1));
+        let mut role = Role::new(0, "Operator");
+        role.learning = Some(LearningCurve { rate: 0.5, floor: 0.0 });
+        role.fatigue = Some(FatigueModel {
+            per_hour: 0.0,
+            max_factor: 1.0,
+            break_after: Some(20),
+            break_minutes: 10,
+        });
+        prod.add_staff(Staff::new(0, "Op", role));
+
+        let run = Phase::new(PhaseKind::Run, 20, 1);
+        assert!(prod.try_start_phase(0, 0, &run, 0));
+        prod.staff[0].release_from_machine(20);
+        prod.machines[0].assigned_staff.clear();
+
+        // 20 minutes worked: the operator goes on break instead of starting
+        assert!(!prod.try_start_phase(0, 1, &run, 20));
+        assert!(prod.staff[0].on_break);
+        prod.staff[0].release_from_machine(30);
+
+        // Second repetition on a 50% curve takes half the time
+        assert!(prod.try_start_phase(0, 1, &run, 30));
+        assert_eq!(prod.staff[0].available_at, 40);
+    }
+
+    #[test]
+    fn staff_walk_before_starting_on_another_bucket() {
+        let mut prod = ProductionSimulator::new();
+        prod.add_machine(MachineType::new(0, "Lathe", 1));
+        prod.add_machine(MachineType::new(1, "Mill", 1));
+        prod.layout.set_travel_time(0, 1, 4);
+        prod.add_staff(Staff::new(0, "Floater", Role::new(0, "Operator")));
+
+        let run = Phase::new(PhaseKind::Run, 10, 1);
+        assert!(prod.try_start_phase(0, 0, &run, 0));
+        assert_eq!(prod.staff[0].available_at, 10); // no walk to the first job
+        prod.staff[0].release_from_machine(10);
+        prod.machines[0].assigned_staff.clear();
+
+        assert!(prod.try_start_phase(1, 1, &run, 10));
+        assert_eq!(prod.staff[0].available_at, 24);
+        assert_eq!(prod.staff[0].walking_until, 14);
+        assert_eq!(prod.staff[0].travel_time, 4);
+        // The mill waits for the walk; only the two runs count as work
+        assert_eq!(prod.machines[1].crew_arrives_at, 14);
+        assert_eq!(prod.staff[0].worked_since_break, 20);
+    }
+
+    #[test]
+    fn maintenance_waits_for_a_technician() {
+        let mut prod = ProductionSimulator::new();
+        prod.add_machine(MachineType::new(0, "Press", 1));
+        prod.add_staff(Staff::new(0, "Operator", Role::new(0, "Operator")));
+        prod.add_staff(Staff::new(1, "Tech", Role::new(5, "Maintenance Tech")));
+        let plan = MaintenancePlan {
+            trigger: MaintenanceTrigger::Cycles { every: 1 },
+            duration: 30,
+            wait_for_job: true,
+            technicians: 1,
+            technician_role: Some(5),
+        };
+
+        prod.staff[1].is_available = false;
+        assert_eq!(prod.try_start_maintenance(0, &plan, 0), None);
+        assert!(!prod.machines[0].down);
+
+        prod.staff[1].is_available = true;
+        assert_eq!(prod.try_start_maintenance(0, &plan, 10), Some(40));
+        assert!(prod.machines[0].down);
+        assert_eq!(prod.machines[0].assigned_staff, vec![1]);
+        assert!(prod.staff[0].is_available);
+
+        // The technician stays with the machine until the PM ends
+        prod.finalize_idle_time(20);
+        assert!(!prod.staff[1].is_available);
+        assert_eq!(prod.machines[0].idle_time, 10);
+    }
 }
@@ -0,0 +1,162 @@
+//! Designs of experiments for parameter sweeps
+//!
+//! A sweep varies a few factors (machine counts, headcounts, durations...)
+//! over their levels. A full-factorial design runs every combination of
+//! levels. A Latin hypercube takes a set number of scenarios, and spreads
+//! them so each factor's range is covered evenly. Scenarios are lists of
+//! level indices, one per factor. With no factors there is a single scenario:
+//! the base itself.
+
+use super::random::SimRng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Design {
+    FullFactorial,
+    LatinHypercube { samples: usize },
+}
+
+/// Levels from `from` to `to` inclusive, `step` apart
+pub fn range_levels(from: f64, to: f64, step: f64) -> Vec<f64> {
+    if step <= 0.0 || to < from {
+        return Vec::new();
+    }
+    let count = ((to - from) / step + 1e-9).floor() as usize + 1;
+    (0..count).map(|i| from + i as f64 * step).collect()
+}
+
+/// Scenarios as level indices; `levels` is the number of levels per factor
+pub fn design_points(design: Design, levels: &[usize], rng: &mut SimRng) -> Vec<Vec<usize>> {
+    if levels.is_empty() {
+        return vec![Vec::new()];
+    }
+    if levels.contains(&0) {
+        return Vec::new();
+    }
+    match design {
+        Design::FullFactorial => {
+            let mut points = vec![Vec::new()];
+            for &count in levels {
+                points = points
+                    .into_iter()
+                    .flat_map(|point: Vec<usize>| {
+                        (0..count).map(move |level| {
+                            let mut next = point.clone();
+                            next.push(level);
+                            next
+                        })
+                    })
+                    .collect();
+            }
+            points
+        }
+        Design::LatinHypercube { samples } => {
+            // One point per stratum of each factor, strata shuffled independently
+            let columns: Vec<Vec<usize>> = levels
+                .iter()
+                .map(|&count| {
+                    let mut strata: Vec<usize> = (0..samples).collect();
+                    for i in (1..strata.len()).rev() {
+                        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+                        strata.swap(i, j);
+                    }
+                    strata
+                        .into_iter()
+                        .map(|stratum| {
+                            let u = (stratum as f64 + rng.next_f64()) / samples as f64;
+                            ((u * count as f64) as usize).min(count - 1)
+                        })
+                        .collect()
+                })
+                .collect();
+            (0..samples).map(|i| columns.iter().map(|c| c[i]).collect()).collect()
+        }
+    }
+}
+
+/// One scenario's factor values, and its KPIs over the replications that completed
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepRow {
+    pub factors: Vec<f64>,
+    /// Replications that got every item done
+    pub completed: u32,
+    /// One per KPI; None when no replication completed
+    pub kpis: Vec<Option<f64>>,
+}
+
+/// One row per scenario: its factor values, completed replications, then its KPIs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SweepResults {
+    pub factors: Vec<String>,
+    pub kpis: Vec<String>,
+    pub rows: Vec<SweepRow>,
+}
+
+impl SweepResults {
+    /// KPIs of a scenario with no completed replication are left blank
+    pub fn to_csv(&self) -> String {
+        let mut header = vec!["scenario".to_string()];
+        header.extend(self.factors.iter().map(|name| name.replace(',', " ")));
+        header.push("completed".to_string());
+        header.extend(self.kpis.iter().map(|name| name.replace(',', " ")));
+        let mut csv = header.join(",");
+        csv.push('\n');
+        for (idx, row) in self.rows.iter().enumerate() {
+            let mut cells = vec![(idx + 1).to_string()];
+            cells.extend(row.factors.iter().map(|v| v.to_string()));
+            cells.push(row.completed.to_string());
+            cells.extend(row.kpis.iter().map(|v| v.map(|v| format!("{:.3}", v)).unwrap_or_default()));
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_factorial_runs_every_combination() {
+        let points = design_points(Design::FullFactorial, &[2, 3], &mut SimRng::new(1));
+        assert_eq!(points.len(), 6);
+        assert_eq!(points[0], vec![0, 0]);
+        assert_eq!(points[5], vec![1, 2]);
+        assert_eq!(range_levels(1.0, 4.0, 1.0), vec![1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(range_levels(0.5, 1.0, 0.25), vec![0.5, 0.75, 1.0]);
+        assert_eq!(design_points(Design::FullFactorial, &[], &mut SimRng::new(1)), vec![Vec::<usize>::new()]);
+    }
+
+    #[test]
+    fn latin_hypercube_covers_each_factor_evenly() {
+        let points = design_points(Design::LatinHypercube { samples: 8 }, &[4, 8], &mut SimRng::new(7));
+        assert_eq!(points.len(), 8);
+        for factor in 0..2 {
+            let mut column: Vec<usize> = points.iter().map(|p| p[factor]).collect();
+            column.sort_unstable();
+            if factor == 0 {
+                assert_eq!(column, vec![0, 0, 1, 1, 2, 2, 3, 3]);
+            } else {
+                assert_eq!(column, (0..8).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn results_are_one_row_per_scenario() {
+        let row = |ovens: f64, completed: u32, throughput: Option<f64>| SweepRow {
+            factors: vec![ovens],
+            completed,
+            kpis: vec![throughput],
+        };
+        let results = SweepResults {
+            factors: vec!["ovens".to_string()],
+            kpis: vec!["Throughput (/hr)".to_string()],
+            rows: vec![row(0.0, 0, None), row(1.0, 2, Some(2.5)), row(2.0, 2, Some(4.0))],
+        };
+        assert_eq!(
+            results.to_csv(),
+            "scenario,ovens,completed,Throughput (/hr)\n1,0,0,\n2,1,2,2.500\n3,2,2,4.000\n"
+        );
+    }
+}
@@ -1,9 +1,9 @@
-/// This module handles all time-related operations for the simulation
-/// 
-/// Key concepts:
-/// - SimulationTime: A simple counter (measured in minutes or seconds)
-/// - Event: Something that happens at a specific time
-/// - EventQueue: Priority queue that processes events in time order
+//! This module handles all time-related operations for the simulation
+//!
+//! Key concepts:
+//! - SimulationTime: A simple counter (measured in minutes or seconds)
+//! - Event: Something that happens at a specific time
+//! - EventQueue: Priority queue that processes events in time order
 
 use std::collections::BinaryHeap;
 use std::cmp::Ordering;
@@ -12,6 +12,7 @@ use std::cmp::Ordering;
 /// We use u32 to keep it simple. You could measure this as:
 /// - Number of seconds since simulation started
 /// - Number of minutes since simulation started
+///
 /// For assembly lines, minutes makes more sense
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SimulationTime(pub u32);
@@ -29,25 +30,23 @@ impl SimulationTime {
 
     /// Calculate duration between two times
     /// Example: time_later - time_earlier = duration
+    #[allow(dead_code)] // time helpers kept for the tests
     pub fn duration_until(&self, other: SimulationTime) -> u32 {
-        if other.0 >= self.0 {
-            other.0 - self.0
-        } else {
-            0
-        }
+        other.0.saturating_sub(self.0)
     }
 
     /// Add time to this time point
     /// Example: time_now + 30 minutes = process_end_time
+    #[allow(dead_code)]
     pub fn add_minutes(&self, minutes: u32) -> SimulationTime {
         SimulationTime(self.0 + minutes)
     }
 }
 
-/// Why we use u32 for cost:
-/// u32 can represent 0 to 4,294,967,295
-/// If measuring minutes: ~8,170 years of simulation
-/// That's plenty for most assembly line simulations!
+// Why we use u32 for cost:
+// u32 can represent 0 to 4,294,967,295
+// If measuring minutes: ~8,170 years of simulation
+// That's plenty for most assembly line simulations!
 
 /// Represents what type of event happened
 /// This helps us know what to do when an event occurs
@@ -72,6 +71,7 @@ pub enum EventType {
         staff_id: u32 
     },
     /// Staff was assigned to work on a machine
+    #[allow(dead_code)]
     StaffAssigned {
         staff_id: u32,
         machine_id: u32,
@@ -82,11 +82,40 @@ pub enum EventType {
         staff_id: u32,
         machine_id: u32,
     },
+    /// The crew of a phase walked up to the machine and starts work
+    CrewArrived {
+        machine_id: u32,
+    },
     /// A machine couldn't start because staff wasn't available
     StaffUnavailable {
         machine_id: u32,
         process_id: u32,
     },
+    /// One phase (setup, load, run, unload) of a process finished
+    PhaseComplete {
+        machine_id: u32,
+        process_id: u32,
+    },
+    /// A batch machine's load-wait timeout ran out
+    BatchTimeout {
+        bucket_id: u32,
+    },
+    /// An item arrived at the end of a transport move
+    TransportComplete {
+        transit_id: u32,
+    },
+    /// A calendar-based preventive maintenance fell due
+    MaintenanceDue {
+        machine_id: u32,
+    },
+    /// Preventive maintenance on a machine finished
+    MaintenanceComplete {
+        machine_id: u32,
+    },
+    /// A lot is released to the first step of its line
+    LotRelease {
+        lot_id: u32,
+    },
 }
 
 /// An event that happens at a specific time
@@ -174,6 +203,7 @@ impl Simulator {
     }
 
     /// Check if there are more events to process
+    #[allow(dead_code)]
     pub fn has_events(&self) -> bool {
         !self.event_queue.is_empty()
     }
@@ -268,7 +298,7 @@ mod tests {
         );
         
         // The earliest event should pop first (min-heap behavior)
-        assert_eq!(sim.has_events(), true);
+        assert!(sim.has_events());
         
         let event = sim.step();
         assert!(event.is_some());
@@ -0,0 +1,265 @@
+//! Material handling between machine buckets
+//!
+//! Without transport an item shows up in the next bucket's queue the moment
+//! its step completes. A transport network adds the move in between:
+//! - routes with fixed or random travel times
+//! - conveyors with a limited number of items on the belt
+//! - a shared pool of transporters (forklifts, AGVs) that each carry one item
+
+use std::collections::HashMap;
+
+use super::random::{Distribution, SimRng};
+
+/// A belt between two buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conveyor {
+    /// Most items that can ride the belt at once
+    pub capacity: usize,
+    /// Minutes from one end to the other (length / speed)
+    pub travel_time: u32,
+    /// Items currently on the belt
+    pub on_belt: usize,
+}
+
+impl Conveyor {
+    /// Build a conveyor from its length and speed (length units per minute)
+    pub fn new(capacity: usize, length: f64, speed: f64) -> Self {
+        let travel_time = if speed > 0.0 { (length / speed).ceil() as u32 } else { 0 };
+        Conveyor {
+            capacity: capacity.max(1),
+            travel_time,
+            on_belt: 0,
+        }
+    }
+
+    pub fn has_room(&self) -> bool {
+        self.on_belt < self.capacity
+    }
+}
+
+/// How items move from one bucket to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    /// Travel time for non-conveyor moves
+    pub travel: Distribution,
+    pub conveyor: Option<Conveyor>,
+    /// Whether the move needs a transporter from the shared pool
+    pub uses_transporter: bool,
+}
+
+/// Shared pool of transporters
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransporterPool {
+    pub name: String,
+    pub total: u32,
+    pub busy: u32,
+    /// Minutes spent carrying items, summed over all transporters
+    pub busy_minutes: u32,
+}
+
+impl TransporterPool {
+    pub fn new(name: &str, total: u32) -> Self {
+        TransporterPool {
+            name: name.to_string(),
+            total,
+            busy: 0,
+            busy_minutes: 0,
+        }
+    }
+
+    pub fn has_free(&self) -> bool {
+        self.busy < self.total
+    }
+}
+
+/// A move that has started, as needed to release its resources later
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trip {
+    pub from: u32,
+    pub to: u32,
+    pub duration: u32,
+    pub used_transporter: bool,
+    pub used_conveyor: bool,
+}
+
+/// Running totals for reports
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransportStats {
+    pub moves: u32,
+    pub travel_minutes: u32,
+    /// Minutes items spent waiting for a transporter or belt space
+    pub wait_minutes: u32,
+}
+
+/// All routes plus the shared transporter pool
+#[derive(Debug, Clone, Default)]
+pub struct TransportNetwork {
+    routes: HashMap<(u32, u32), Route>,
+    /// Used for bucket pairs without an explicit route
+    pub default_route: Option<Route>,
+    pub transporters: Option<TransporterPool>,
+    pub stats: TransportStats,
+}
+
+impl TransportNetwork {
+    pub fn new() -> Self {
+        TransportNetwork::default()
+    }
+
+    /// Clear move counters and transporter use (end of the warm-up)
+    pub fn reset_statistics(&mut self) {
+        self.stats = TransportStats::default();
+        if let Some(pool) = &mut self.transporters {
+            pool.busy_minutes = 0;
+        }
+    }
+
+    pub fn add_route(&mut self, from: u32, to: u32, route: Route) {
+        self.routes.insert((from, to), route);
+    }
+
+    /// Whether a move from `from` to `to` takes any time at all
+    pub fn has_route(&self, from: u32, to: u32) -> bool {
+        self.routes.contains_key(&(from, to)) || (from != to && self.default_route.is_some())
+    }
+
+    fn route(&self, from: u32, to: u32) -> Option<&Route> {
+        match self.routes.get(&(from, to)) {
+            Some(route) => Some(route),
+            None if from != to => self.default_route.as_ref(),
+            None => None,
+        }
+    }
+
+    fn route_mut(&mut self, from: u32, to: u32) -> Option<&mut Route> {
+        if self.routes.contains_key(&(from, to)) {
+            self.routes.get_mut(&(from, to))
+        } else if from != to {
+            self.default_route.as_mut()
+        } else {
+            None
+        }
+    }
+
+    /// Try to start a move; None means the item has to wait for a transporter or belt space
+    pub fn start_trip(&mut self, from: u32, to: u32, rng: &mut SimRng) -> Option<Trip> {
+        let route = self.route(from, to)?.clone();
+        if route.uses_transporter && !self.transporters.as_ref().map(|p| p.has_free()).unwrap_or(false) {
+            return None;
+        }
+        if let Some(conveyor) = &route.conveyor {
+            if !conveyor.has_room() {
+                return None;
+            }
+        }
+
+        let duration = match &route.conveyor {
+            Some(conveyor) => conveyor.travel_time,
+            None => route.travel.sample(rng),
+        };
+        if let Some(conveyor) = self.route_mut(from, to).and_then(|r| r.conveyor.as_mut()) {
+            conveyor.on_belt += 1;
+        }
+        if route.uses_transporter {
+            if let Some(pool) = self.transporters.as_mut() {
+                pool.busy += 1;
+                pool.busy_minutes += duration;
+            }
+        }
+        self.stats.moves += 1;
+        self.stats.travel_minutes += duration;
+
+        Some(Trip {
+            from,
+            to,
+            duration,
+            used_transporter: route.uses_transporter,
+            used_conveyor: route.conveyor.is_some(),
+        })
+    }
+
+    /// Free the transporter / belt slot held by a finished move
+    pub fn finish_trip(&mut self, trip: &Trip) {
+        if trip.used_conveyor {
+            if let Some(conveyor) = self.route_mut(trip.from, trip.to).and_then(|r| r.conveyor.as_mut()) {
+                conveyor.on_belt = conveyor.on_belt.saturating_sub(1);
+            }
+        }
+        if trip.used_transporter {
+            if let Some(pool) = self.transporters.as_mut() {
+                pool.busy = pool.busy.saturating_sub(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_route(minutes: u32) -> Route {
+        Route {
+            travel: Distribution::Fixed(minutes),
+            conveyor: None,
+            uses_transporter: false,
+        }
+    }
+
+    #[test]
+    fn buckets_without_routes_move_instantly() {
+        let mut network = TransportNetwork::new();
+        network.add_route(0, 1, fixed_route(4));
+        assert!(network.has_route(0, 1));
+        assert!(!network.has_route(1, 2));
+
+        network.default_route = Some(fixed_route(2));
+        assert!(network.has_route(1, 2));
+        assert!(!network.has_route(2, 2));
+
+        let mut rng = SimRng::new(1);
+        assert_eq!(network.start_trip(0, 1, &mut rng).unwrap().duration, 4);
+        assert_eq!(network.start_trip(1, 2, &mut rng).unwrap().duration, 2);
+        assert_eq!(network.stats.moves, 2);
+        assert_eq!(network.stats.travel_minutes, 6);
+    }
+
+    #[test]
+    fn conveyor_limits_items_on_belt() {
+        let mut network = TransportNetwork::new();
+        network.add_route(
+            0,
+            1,
+            Route {
+                conveyor: Some(Conveyor::new(2, 30.0, 4.0)),
+                ..fixed_route(0)
+            },
+        );
+        let mut rng = SimRng::new(1);
+        let first = network.start_trip(0, 1, &mut rng).unwrap();
+        assert_eq!(first.duration, 8); // 30 / 4 rounded up
+        assert!(network.start_trip(0, 1, &mut rng).is_some());
+        assert!(network.start_trip(0, 1, &mut rng).is_none());
+
+        network.finish_trip(&first);
+        assert!(network.start_trip(0, 1, &mut rng).is_some());
+    }
+
+    #[test]
+    fn transporter_pool_is_shared_between_routes() {
+        let mut network = TransportNetwork::new();
+        network.transporters = Some(TransporterPool::new("Forklift", 1));
+        let carried = Route {
+            uses_transporter: true,
+            ..fixed_route(5)
+        };
+        network.add_route(0, 1, carried.clone());
+        network.add_route(2, 3, carried);
+
+        let mut rng = SimRng::new(1);
+        let trip = network.start_trip(0, 1, &mut rng).unwrap();
+        assert!(network.start_trip(2, 3, &mut rng).is_none());
+        network.finish_trip(&trip);
+        assert!(network.start_trip(2, 3, &mut rng).is_some());
+        assert_eq!(network.transporters.as_ref().unwrap().busy_minutes, 10);
+    }
+}
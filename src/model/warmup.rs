@@ -0,0 +1,89 @@
+//! Warm-up length by Welch's method
+//!
+//! A run starts empty, so its first hours are not typical. Welch's method
+//! averages the throughput series of one or more replications, then smooths it
+//! with a moving average. The warm-up ends where the smoothed curve settles
+//! near its steady level. Here the level is the mean of the second half of the
+//! curve, and the curve has settled once it stays within a tolerance band
+//! around that level.
+
+/// Units finished in each `period` minutes up to `end`; a partial last period is dropped
+pub fn throughput_series(completions: &[u32], period: u32, end: u32) -> Vec<f64> {
+    let period = period.max(1);
+    let mut series = vec![0.0; (end / period) as usize];
+    for &time in completions {
+        if let Some(count) = series.get_mut((time / period) as usize) {
+            *count += 1.0;
+        }
+    }
+    series
+}
+
+/// Mean of the replications per period, smoothed over `window` periods each side
+///
+/// Near the start the window shrinks to fit, as in Welch (1983). The last
+/// `window` periods have no full window and are left out.
+pub fn welch_average(replications: &[Vec<f64>], window: usize) -> Vec<f64> {
+    let periods = replications.iter().map(Vec::len).min().unwrap_or(0);
+    if replications.is_empty() || periods <= window {
+        return Vec::new();
+    }
+    let mean: Vec<f64> = (0..periods)
+        .map(|i| replications.iter().map(|r| r[i]).sum::<f64>() / replications.len() as f64)
+        .collect();
+    (0..periods - window)
+        .map(|i| {
+            let half = i.min(window);
+            let values = &mean[i - half..=i + half];
+            values.iter().sum::<f64>() / values.len() as f64
+        })
+        .collect()
+}
+
+/// First period after which the smoothed curve stays within `tolerance` (a share) of its level
+///
+/// None when the curve is too short or still outside the band in its last period.
+pub fn suggest_warmup(smoothed: &[f64], tolerance: f64) -> Option<usize> {
+    if smoothed.len() < 2 {
+        return None;
+    }
+    let tail = &smoothed[smoothed.len() / 2..];
+    let level = tail.iter().sum::<f64>() / tail.len() as f64;
+    let band = (level * tolerance).abs();
+    let outside = smoothed.iter().rposition(|v| (v - level).abs() > band);
+    match outside {
+        None => Some(0),
+        Some(last) if last + 1 < smoothed.len() => Some(last + 1),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completions_are_counted_per_period() {
+        let series = throughput_series(&[5, 59, 60, 61, 130, 200], 60, 180);
+        assert_eq!(series, vec![2.0, 2.0, 1.0]);
+    }
+
+    #[test]
+    fn ramp_up_is_found_in_the_smoothed_curve() {
+        // Throughput ramps up over four periods, then holds at 10
+        let run: Vec<f64> = [0.0, 3.0, 6.0, 8.0]
+            .into_iter()
+            .chain(std::iter::repeat_n(10.0, 16))
+            .collect();
+        let flat = vec![10.0; 20];
+        let smoothed = welch_average(&[run.clone(), flat], 1);
+        assert_eq!(smoothed.len(), 19);
+        assert_eq!(smoothed[0], 5.0);
+        assert_eq!(suggest_warmup(&smoothed, 0.05), Some(4));
+
+        // Never settling gives no suggestion
+        let rising: Vec<f64> = (0..10).map(f64::from).collect();
+        assert_eq!(suggest_warmup(&rising, 0.05), None);
+        assert_eq!(welch_average(&[run], 30), Vec::<f64>::new());
+    }
+}